@@ -0,0 +1,96 @@
+// MIT License
+//
+// Copyright (c) 2016 Rafael Medina García <rafamedgar@gmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+//! Benchmark of the command dispatch path, without any I/O
+
+#[macro_use]
+extern crate criterion;
+extern crate texture;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use criterion::Criterion;
+
+use texture::command::GameCommand;
+use texture::master::GameMaster;
+use texture::scenario::{Loader, Scenario};
+use texture::state::{BaseState, BasicState};
+use texture::util::{NOTURN, TICK};
+
+struct Room;
+
+impl <S: BaseState> Scenario <S> for Room {
+    fn load(&self, _state: &Rc<RefCell<S>>,
+            _loader: &Rc<RefCell<Loader<S>>>)
+            -> i32 {
+        TICK
+    }
+
+    fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+                 loader: &Rc<RefCell<Loader<S>>>)
+                 -> i32 {
+        match command {
+            "push button" => {
+                state.borrow_mut().increment("presses".to_string(), 1);
+                loader.borrow_mut().write("Click.");
+            },
+            _ => {
+                loader.borrow_mut().write("Nothing happens.");
+            }
+        }
+
+        TICK
+    }
+}
+
+struct Look;
+
+impl <S> GameCommand <S> for Look {
+    fn execute(&self, _state: &Rc<RefCell<S>>,
+               loader: &Rc<RefCell<Loader<S>>>)
+               -> i32 {
+        loader.borrow_mut().write("A plain room with a button.");
+
+        NOTURN
+    }
+}
+
+fn dispatch(c: &mut Criterion) {
+    let state = Rc::new(RefCell::new(BasicState::new()));
+    let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Room)));
+    gm.add_command("look".to_string(), Box::new(Look));
+
+    let inputs: Vec<String> = ["look", "push button", "jump"].iter()
+        .cycle()
+        .take(3000)
+        .map(|i| i.to_string())
+        .collect();
+
+    c.bench_function("dispatch 3000 turns", move |b| {
+        b.iter(|| gm.bench(&inputs))
+    });
+}
+
+criterion_group!(benches, dispatch);
+criterion_main!(benches);
@@ -0,0 +1,248 @@
+extern crate texture;
+
+use std::cell::RefCell;
+use std::env;
+use std::rc::Rc;
+
+use texture::input::VecInput;
+use texture::master::GameMaster;
+use texture::output::BufferOutput;
+use texture::random::{Rng, WeightedTable};
+use texture::scenario::{Loader, Scenario};
+use texture::state::{BaseState, BasicState};
+use texture::util::{LOAD, NOTURN, TICK};
+
+
+// Possible results of an attack
+#[derive(Clone, Copy)]
+enum Hit {
+    Miss,
+    Normal,
+    Critical,
+}
+
+impl Hit {
+    fn damage(&self) -> i32 {
+        match *self {
+            Hit::Miss => 0,
+            Hit::Normal => 3,
+            Hit::Critical => 6,
+        }
+    }
+}
+
+// Scenarios
+struct Arena {
+    rng: RefCell<Rng>,
+    player_hits: WeightedTable<Hit>,
+    goblin_hits: WeightedTable<Hit>,
+}
+struct Victory;
+struct Defeat;
+
+impl Arena {
+    fn new(seed: u64) -> Arena {
+        let mut player_hits = WeightedTable::new();
+        player_hits.add(Hit::Miss, 2);
+        player_hits.add(Hit::Normal, 5);
+        player_hits.add(Hit::Critical, 1);
+
+        let mut goblin_hits = WeightedTable::new();
+        goblin_hits.add(Hit::Miss, 4);
+        goblin_hits.add(Hit::Normal, 4);
+
+        Arena {
+            rng: RefCell::new(Rng::new(seed)),
+            player_hits: player_hits,
+            goblin_hits: goblin_hits,
+        }
+    }
+
+    fn roll(&self, table: &WeightedTable<Hit>) -> Hit {
+        *table.pick(&mut self.rng.borrow_mut()).unwrap()
+    }
+
+    fn attack<S: BaseState>(&self, state: &Rc<RefCell<S>>,
+                            loader: &Rc<RefCell<Loader<S>>>)
+                            -> i32 {
+        let hit = self.roll(&self.player_hits);
+        let goblin_hp = state.borrow_mut()
+            .increment("goblin_hp".to_string(), -hit.damage());
+
+        match hit {
+            Hit::Miss => loader.borrow_mut().write("You miss the goblin."),
+            Hit::Normal => loader.borrow_mut().write("You hit the goblin."),
+            Hit::Critical => loader.borrow_mut().write("A critical hit!"),
+        }
+
+        if goblin_hp <= 0 {
+            loader.borrow_mut().load_by_name("victory");
+            return LOAD;
+        }
+
+        self.counter_attack(state, loader, 1)
+    }
+
+    fn counter_attack<S: BaseState>(&self, state: &Rc<RefCell<S>>,
+                                    loader: &Rc<RefCell<Loader<S>>>,
+                                    defense: i32)
+                                    -> i32 {
+        let hit = self.roll(&self.goblin_hits);
+        let damage = hit.damage() / defense;
+        let player_hp = state.borrow_mut()
+            .increment("player_hp".to_string(), -damage);
+
+        if damage == 0 {
+            loader.borrow_mut().write("The goblin fails to hurt you.");
+        } else {
+            loader.borrow_mut().write(
+                &format!("The goblin hits you for {} damage.", damage));
+        }
+
+        if player_hp <= 0 {
+            loader.borrow_mut().load_by_name("defeat");
+            return LOAD;
+        }
+
+        TICK
+    }
+}
+
+impl <S: BaseState> Scenario<S> for Arena {
+    fn load(&self, state: &Rc<RefCell<S>>,
+            _loader: &Rc<RefCell<Loader<S>>>)
+            -> i32 {
+        state.borrow_mut().set_value("player_hp".to_string(), 10);
+        state.borrow_mut().set_value("goblin_hp".to_string(), 8);
+
+        TICK
+    }
+
+    fn describe(&self, _state: &Rc<RefCell<S>>,
+                loader: &Rc<RefCell<Loader<S>>>) {
+        loader.borrow_mut()
+            .write("A goblin blocks your way, brandishing a rusty dagger.");
+    }
+
+    fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+                 loader: &Rc<RefCell<Loader<S>>>)
+                 -> i32 {
+        let mut words = command.split_whitespace();
+        let verb = words.next().unwrap_or("");
+        let target = words.next();
+
+        match (verb, target) {
+            ("attack", None) | ("attack", Some("goblin")) => {
+                self.attack(state, loader)
+            },
+            ("attack", Some(other)) => {
+                loader.borrow_mut()
+                    .write(&format!("There is no {} to attack.", other));
+                NOTURN
+            },
+            ("defend", _) => {
+                loader.borrow_mut().write("You raise your shield.");
+                self.counter_attack(state, loader, 2)
+            },
+            ("status", _) => {
+                let state = state.borrow();
+                loader.borrow_mut().write(&format!(
+                    "You: {} HP. Goblin: {} HP.",
+                    state.get_value("player_hp".to_string()),
+                    state.get_value("goblin_hp".to_string())));
+                NOTURN
+            },
+            _ => {
+                loader.borrow_mut()
+                    .write("Try to attack, defend or check your status.");
+                NOTURN
+            }
+        }
+    }
+}
+
+impl <S: BaseState> Scenario<S> for Victory {
+    fn load(&self, _state: &Rc<RefCell<S>>,
+            _loader: &Rc<RefCell<Loader<S>>>)
+            -> i32 {
+        TICK
+    }
+
+    fn describe(&self, _state: &Rc<RefCell<S>>,
+                loader: &Rc<RefCell<Loader<S>>>) {
+        loader.borrow_mut().write("The goblin falls. You are victorious!");
+    }
+
+    fn do_action(&self, _command: &str, _state: &Rc<RefCell<S>>,
+                 loader: &Rc<RefCell<Loader<S>>>)
+                 -> i32 {
+        loader.borrow_mut().write("The fight is over.");
+
+        NOTURN
+    }
+}
+
+impl <S: BaseState> Scenario<S> for Defeat {
+    fn load(&self, _state: &Rc<RefCell<S>>,
+            _loader: &Rc<RefCell<Loader<S>>>)
+            -> i32 {
+        TICK
+    }
+
+    fn describe(&self, _state: &Rc<RefCell<S>>,
+                loader: &Rc<RefCell<Loader<S>>>) {
+        loader.borrow_mut()
+            .write("You collapse. The goblin takes your purse.");
+    }
+
+    fn do_action(&self, _command: &str, _state: &Rc<RefCell<S>>,
+                 loader: &Rc<RefCell<Loader<S>>>)
+                 -> i32 {
+        loader.borrow_mut().write("The fight is over.");
+
+        NOTURN
+    }
+}
+
+fn main() {
+    // Run with `--script` to play a predefined fight (useful for CI)
+    let scripted = env::args().any(|a| a == "--script");
+
+    let state = Rc::new(RefCell::new(BasicState::new()));
+    let arena = Rc::new(RefCell::new(Arena::new(42)));
+
+    // Create game master
+    let mut gm = GameMaster::new(state.clone(), arena);
+
+    let loader = gm.loader();
+    loader.borrow_mut().register("victory".to_string(),
+                                 Rc::new(RefCell::new(Victory)));
+    loader.borrow_mut().register("defeat".to_string(),
+                                 Rc::new(RefCell::new(Defeat)));
+
+    if !scripted {
+        gm.start_game();
+        return;
+    }
+
+    let script = ["status", "attack goblin", "defend", "attack",
+                  "attack troll", "attack", "status"];
+    let inputs = script.iter().map(|s| s.to_string()).collect();
+    let buffer = BufferOutput::new();
+
+    gm.set_input(Box::new(VecInput::new(inputs)));
+    gm.set_output(Box::new(buffer.clone()));
+    gm.start_game();
+
+    let output = buffer.contents();
+    print!("{}", output);
+
+    // The fixed seed always plays the same fight
+    assert!(output.contains("There is no troll to attack."));
+    assert!(output.contains("The goblin falls. You are victorious!"));
+    assert!(output.ends_with("The fight is over.\n"));
+    assert_eq!(loader.borrow().current_scenario_name(),
+               Some("victory".to_string()));
+    assert!(state.borrow().get_value("goblin_hp".to_string()) <= 0);
+    assert!(state.borrow().get_value("player_hp".to_string()) > 0);
+}
@@ -1,12 +1,14 @@
 extern crate texture;
 
 use std::cell::RefCell;
+use std::env;
 use std::rc::Rc;
 use std::process;
 use std::collections::HashMap;
 
 use texture::master::GameMaster;
-use texture::command::GameCommand;
+use texture::command::{GameCommand, QuitCommand};
+use texture::input::VecInput;
 use texture::scenario::{Loader, Scenario};
 use texture::util::{TICK, LOAD};
 
@@ -117,6 +119,9 @@ impl <S: CustomState> GameCommand <S> for Exit {
 }
 
 fn main() {
+    // Run with `--script` to check that `quit` ends the game (useful for CI)
+    let scripted = env::args().any(|a| a == "--script");
+
     // Create custom state
     let mut state = MyState::new();
     state.clear();
@@ -132,7 +137,22 @@ fn main() {
     let exitcmd = Exit;
 
     gm.add_command("exit".to_string(), Box::new(exitcmd));
+    gm.add_command("quit".to_string(), Box::new(QuitCommand::new(None)));
+
+    if !scripted {
+        // Start game
+        gm.start_game();
+        return;
+    }
 
-    // Start game
+    assert!(gm.command_names().contains(&"quit".to_string()));
+
+    let script = ["tick", "quit", "tick"];
+    let inputs = script.iter().map(|s| s.to_string()).collect();
+
+    gm.set_input(Box::new(VecInput::new(inputs)));
     gm.start_game();
+
+    // The game ended on `quit`, so the last input was never read
+    assert_eq!(gm.recent_inputs(), vec!["tick", "quit"]);
 }
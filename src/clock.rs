@@ -0,0 +1,109 @@
+// MIT License
+//
+// Copyright (c) 2016 Rafael Medina García <rafamedgar@gmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Source of the real time elapsed in the game
+///
+/// Used by the `Loader` for events scheduled after a `Duration` (see
+/// `Loader::schedule_after()`), so tests can control time with a
+/// `ManualClock`.
+pub trait Clock {
+    /// Time elapsed since the clock started
+    fn elapsed(&self) -> Duration;
+}
+
+/// Clock following the wall-clock time, started when created
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> SystemClock {
+        SystemClock::new()
+    }
+}
+
+impl SystemClock {
+    /// Create a clock starting now
+    pub fn new() -> SystemClock {
+        SystemClock { start: Instant::now() }
+    }
+}
+
+impl Clock for SystemClock {
+    fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// Clock that only moves when told to
+///
+/// Clones share the same time, so a clone can be installed in the game
+/// while the original is used to advance it.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use texture::clock::{Clock, ManualClock};
+///
+/// let clock = ManualClock::new();
+/// let installed = clock.clone();
+///
+/// clock.advance(Duration::from_secs(3));
+/// clock.advance(Duration::from_secs(2));
+///
+/// assert_eq!(installed.elapsed(), Duration::from_secs(5));
+/// ```
+#[derive(Clone)]
+pub struct ManualClock {
+    time: Rc<Cell<Duration>>,
+}
+
+impl Default for ManualClock {
+    fn default() -> ManualClock {
+        ManualClock::new()
+    }
+}
+
+impl ManualClock {
+    /// Create a clock at zero
+    pub fn new() -> ManualClock {
+        ManualClock { time: Rc::new(Cell::new(Duration::from_secs(0))) }
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, amount: Duration) {
+        self.time.set(self.time.get() + amount);
+    }
+}
+
+impl Clock for ManualClock {
+    fn elapsed(&self) -> Duration {
+        self.time.get()
+    }
+}
@@ -21,9 +21,17 @@
 // SOFTWARE.
 
 use std::cell::RefCell;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::time::UNIX_EPOCH;
 
-use scenario::Loader;
+use output::OutputEvent;
+use persist::{Persistable, SaveError};
+use scenario::{DescriptionMode, Loader};
+use state::BaseState;
+use util::{LOAD, NOTURN, QUIT, TICK};
 
 
 pub trait GameCommand<S> {
@@ -60,4 +68,1040 @@ pub trait GameCommand<S> {
     /// ```
     fn execute(&self, state: &Rc<RefCell<S>>, loader: &Rc<RefCell<Loader<S>>>)
                -> i32;
+
+    /// Function to execute when command is called, receiving the words
+    /// typed after its name
+    ///
+    /// Arguments are only given to commands that take them (see
+    /// `takes_args()`); otherwise `args` is empty. Calls `execute()` by
+    /// default.
+    fn execute_args(&self, _args: &[&str], state: &Rc<RefCell<S>>,
+                    loader: &Rc<RefCell<Loader<S>>>)
+                    -> i32 {
+        self.execute(state, loader)
+    }
+
+    /// Whether the command accepts words after its name
+    ///
+    /// Returns `false` by default, so the command only runs when the input
+    /// is exactly its name.
+    fn takes_args(&self) -> bool {
+        false
+    }
+
+    /// Minimum and maximum (if any) number of arguments accepted
+    ///
+    /// When the command is typed with a different number of arguments, the
+    /// game master writes its usage (see `usage()`) instead of executing it,
+    /// without taking a turn. Only meaningful for commands that take
+    /// arguments. Returns `(0, None)` (any number) by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::{Cell, RefCell};
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::GameCommand;
+    /// use texture::data::DataScenario;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::Loader;
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// struct Give(Rc<Cell<u32>>);
+    ///
+    /// impl <S> GameCommand <S> for Give {
+    ///     fn execute(&self, state: &Rc<RefCell<S>>,
+    ///                loader: &Rc<RefCell<Loader<S>>>)
+    ///                -> i32 {
+    ///         self.0.set(self.0.get() + 1);
+    ///         TICK
+    ///     }
+    ///
+    ///     fn takes_args(&self) -> bool {
+    ///         true
+    ///     }
+    ///
+    ///     fn arity(&self) -> (usize, Option<usize>) {
+    ///         (2, Some(2))
+    ///     }
+    ///
+    ///     fn usage(&self) -> Option<&str> {
+    ///         Some("give <item> <person>")
+    ///     }
+    /// }
+    ///
+    /// let calls = Rc::new(Cell::new(0));
+    /// let hall = DataScenario::new("A long hall.".to_string());
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(hall)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.add_command("give".to_string(), Box::new(Give(calls.clone())));
+    ///
+    /// gm.step("give apple");
+    /// gm.step("give apple to guard");
+    /// assert_eq!(calls.get(), 0);
+    ///
+    /// gm.step("give apple guard");
+    /// assert_eq!(calls.get(), 1);
+    ///
+    /// assert_eq!(buffer.contents(),
+    ///            "Usage: give <item> <person>\nUsage: give <item> <person>\n");
+    /// assert_eq!(gm.turns(), 1);
+    /// ```
+    fn arity(&self) -> (usize, Option<usize>) {
+        (0, None)
+    }
+
+    /// How the command is typed, shown by the built-in help
+    ///
+    /// For instance, `"save <slot>"`. Returns `None` by default.
+    fn usage(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Built-in command ending the game
+///
+/// Writes a farewell message (if any) and returns `QUIT`. Works with any
+/// state type.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::command::QuitCommand;
+/// use texture::master::GameMaster;
+/// use texture::output::BufferOutput;
+/// use texture::scenario::{Loader, Scenario};
+/// use texture::util::{QUIT, TICK};
+///
+/// struct Room;
+///
+/// impl <S> Scenario <S> for Room {
+///     fn load(&self, state: &Rc<RefCell<S>>,
+///             loader: &Rc<RefCell<Loader<S>>>)
+///             -> i32 {
+///         TICK
+///     }
+///
+///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+///                  loader: &Rc<RefCell<Loader<S>>>)
+///                  -> i32 {
+///         TICK
+///     }
+/// }
+///
+/// // Any state type will do
+/// struct Nothing;
+///
+/// let buffer = BufferOutput::new();
+/// let state = Rc::new(RefCell::new(Nothing));
+///
+/// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Room)));
+/// gm.set_output(Box::new(buffer.clone()));
+/// gm.add_command("quit".to_string(),
+///                Box::new(QuitCommand::new(Some("Farewell!".to_string()))));
+///
+/// assert_eq!(gm.step("quit"), QUIT);
+/// assert_eq!(buffer.contents(), "Farewell!\n");
+/// ```
+pub struct QuitCommand {
+    message: Option<String>,
+    confirmation: Option<String>,
+}
+
+impl QuitCommand {
+    /// Create a command writing the given message before quitting
+    pub fn new(message: Option<String>) -> QuitCommand {
+        QuitCommand {
+            message: message,
+            confirmation: None,
+        }
+    }
+
+    /// Ask for confirmation before quitting with unsaved changes
+    ///
+    /// When the loader reports unsaved changes (see
+    /// `Loader::has_unsaved_changes()`), the question is asked and the game
+    /// only ends if the answer starts with `y`. Without unsaved changes,
+    /// the game ends right away.
+    ///
+    /// Changes are only tracked automatically for `BasicState` games, and
+    /// only for flags and values (see `GameMaster::track_unsaved_changes()`);
+    /// other games must call `Loader::mark_unsaved()` themselves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::{GameCommand, QuitCommand};
+    /// use texture::input::VecInput;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::Loader;
+    /// use texture::util::{NOTURN, QUIT};
+    ///
+    /// let mut quit = QuitCommand::new(Some("Bye!".to_string()));
+    /// quit.set_confirmation(Some("Quit without saving? ".to_string()));
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(()));
+    /// let loader = Rc::new(RefCell::new(Loader::new()));
+    /// loader.borrow_mut().set_output(Box::new(buffer.clone()));
+    /// loader.borrow_mut().set_input(Box::new(VecInput::new(vec![
+    ///     "no".to_string(),
+    ///     "yes".to_string(),
+    /// ])));
+    ///
+    /// // Nothing to lose
+    /// assert_eq!(quit.execute(&state, &loader), QUIT);
+    ///
+    /// loader.borrow_mut().mark_unsaved();
+    /// assert_eq!(quit.execute(&state, &loader), NOTURN);
+    /// assert_eq!(quit.execute(&state, &loader), QUIT);
+    ///
+    /// assert_eq!(buffer.contents(), "Bye!\nBye!\n");
+    /// ```
+    pub fn set_confirmation(&mut self, question: Option<String>) {
+        self.confirmation = question;
+    }
+}
+
+impl <S> GameCommand <S> for QuitCommand {
+    fn execute(&self, _state: &Rc<RefCell<S>>,
+               loader: &Rc<RefCell<Loader<S>>>)
+               -> i32 {
+        if let Some(ref question) = self.confirmation {
+            if loader.borrow().has_unsaved_changes() {
+                let mut loader = loader.borrow_mut();
+                loader.flush();
+
+                // Running out of input quits anyway
+                let answer = loader.read_line(question)
+                    .unwrap_or_else(|| "y".to_string());
+
+                if !answer.trim().to_lowercase().starts_with('y') {
+                    return NOTURN;
+                }
+            }
+        }
+
+        if let Some(ref message) = self.message {
+            loader.borrow_mut().write(message);
+        }
+
+        QUIT
+    }
+}
+
+/// Built-in command writing a fixed help text
+///
+/// Also useful for any other command showing a fixed text (e.g. `about`
+/// or `credits`); such commands can be read from data files (see
+/// `data::parse_commands_from_json()`) and registered with
+/// `GameMaster::add_commands()`. For a help listing the registered
+/// commands, see `GameMaster::set_help_command()`. Works with any state
+/// type.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::command::{GameCommand, HelpCommand};
+/// use texture::output::BufferOutput;
+/// use texture::scenario::Loader;
+///
+/// let buffer = BufferOutput::new();
+/// let loader = Rc::new(RefCell::new(Loader::new()));
+/// loader.borrow_mut().set_output(Box::new(buffer.clone()));
+///
+/// let help = HelpCommand::new("Type directions to move around.".to_string());
+/// help.execute(&Rc::new(RefCell::new(())), &loader);
+///
+/// assert_eq!(buffer.contents(), "Type directions to move around.\n");
+/// ```
+pub struct HelpCommand {
+    text: String,
+}
+
+impl HelpCommand {
+    /// Create a command writing the given text
+    pub fn new(text: String) -> HelpCommand {
+        HelpCommand { text: text }
+    }
+}
+
+impl <S> GameCommand <S> for HelpCommand {
+    fn execute(&self, _state: &Rc<RefCell<S>>,
+               loader: &Rc<RefCell<Loader<S>>>)
+               -> i32 {
+        loader.borrow_mut().write(&self.text);
+
+        NOTURN
+    }
+}
+
+/// Built-in command showing the score of the player
+///
+/// Requires `S: BaseState`.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::command::{GameCommand, ScoreCommand};
+/// use texture::output::BufferOutput;
+/// use texture::scenario::Loader;
+/// use texture::state::{BaseState, BasicState};
+///
+/// let buffer = BufferOutput::new();
+/// let loader = Rc::new(RefCell::new(Loader::new()));
+/// loader.borrow_mut().set_output(Box::new(buffer.clone()));
+///
+/// let state = Rc::new(RefCell::new(BasicState::new()));
+/// state.borrow_mut().set_max_score(50);
+/// state.borrow_mut().award_points(5, "woke up");
+///
+/// ScoreCommand.execute(&state, &loader);
+///
+/// assert_eq!(buffer.contents(), "You have scored 5 of a possible 50.\n");
+/// ```
+pub struct ScoreCommand;
+
+impl <S: BaseState> GameCommand <S> for ScoreCommand {
+    fn execute(&self, state: &Rc<RefCell<S>>, loader: &Rc<RefCell<Loader<S>>>)
+               -> i32 {
+        let message = {
+            let state = state.borrow();
+            format!("You have scored {} of a possible {}.",
+                    state.score(), state.max_score())
+        };
+
+        loader.borrow_mut().write(&message);
+
+        NOTURN
+    }
+}
+
+/// Built-in command changing how scenarios are described
+///
+/// Usually registered as `verbose`, `brief` and `superbrief`. Works with any
+/// state type.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::command::{DescriptionCommand, GameCommand};
+/// use texture::output::BufferOutput;
+/// use texture::scenario::{DescriptionMode, Loader};
+///
+/// let buffer = BufferOutput::new();
+/// let loader = Rc::new(RefCell::new(Loader::new()));
+/// loader.borrow_mut().set_output(Box::new(buffer.clone()));
+///
+/// let state = Rc::new(RefCell::new(()));
+/// DescriptionCommand::new(DescriptionMode::Brief).execute(&state, &loader);
+///
+/// assert_eq!(loader.borrow().description_mode(), DescriptionMode::Brief);
+/// assert_eq!(buffer.contents(),
+///            "Short descriptions of places you have visited.\n");
+/// ```
+pub struct DescriptionCommand {
+    mode: DescriptionMode,
+}
+
+impl DescriptionCommand {
+    /// Create a command switching to the given mode
+    pub fn new(mode: DescriptionMode) -> DescriptionCommand {
+        DescriptionCommand { mode: mode }
+    }
+}
+
+impl <S> GameCommand <S> for DescriptionCommand {
+    fn execute(&self, _state: &Rc<RefCell<S>>,
+               loader: &Rc<RefCell<Loader<S>>>)
+               -> i32 {
+        let message = match self.mode {
+            DescriptionMode::Verbose => {
+                "Full descriptions of every place."
+            },
+            DescriptionMode::Brief => {
+                "Short descriptions of places you have visited."
+            },
+            DescriptionMode::SuperBrief => {
+                "Short descriptions of every place."
+            }
+        };
+
+        let mut loader = loader.borrow_mut();
+        loader.set_description_mode(self.mode);
+        loader.write(message);
+
+        NOTURN
+    }
+}
+
+/// Built-in command saving the state in a numbered slot (`save <n>`)
+///
+/// Slots are files in the save directory of the loader (see
+/// `Loader::set_save_dir()`). Requires `S: Persistable`. Registered along
+/// `LoadCommand` and `SlotsCommand` by `GameMaster::enable_save_slots()`.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::env;
+/// use std::rc::Rc;
+///
+/// use texture::master::GameMaster;
+/// use texture::output::BufferOutput;
+/// use texture::persist::{Persistable, SaveError};
+/// use texture::scenario::{Loader, Scenario};
+/// use texture::util::TICK;
+///
+/// struct Purse {
+///     gold: i32,
+/// }
+///
+/// impl Persistable for Purse {
+///     fn save(&self) -> Result<String, SaveError> {
+///         Ok(self.gold.to_string())
+///     }
+///
+///     fn restore(data: &str) -> Result<Purse, SaveError> {
+///         data.parse()
+///             .map(|gold| Purse { gold: gold })
+///             .map_err(|_| SaveError::Format(data.to_string()))
+///     }
+/// }
+///
+/// struct Room;
+///
+/// impl <S> Scenario <S> for Room {
+///     fn load(&self, state: &Rc<RefCell<S>>,
+///             loader: &Rc<RefCell<Loader<S>>>)
+///             -> i32 {
+///         TICK
+///     }
+///
+///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+///                  loader: &Rc<RefCell<Loader<S>>>)
+///                  -> i32 {
+///         TICK
+///     }
+/// }
+///
+/// let name = format!("texture-slots-{}", std::process::id());
+/// let dir = env::temp_dir().join(name);
+/// let dir = dir.to_str().unwrap();
+///
+/// let buffer = BufferOutput::new();
+/// let state = Rc::new(RefCell::new(Purse { gold: 10 }));
+///
+/// let mut gm = GameMaster::new(state.clone(), Rc::new(RefCell::new(Room)));
+/// gm.set_output(Box::new(buffer.clone()));
+/// gm.set_save_dir(dir.to_string());
+/// gm.enable_save_slots();
+///
+/// gm.step("save 1");
+/// state.borrow_mut().gold = 0;
+/// gm.step("load 1");
+/// assert_eq!(state.borrow().gold, 10);
+///
+/// gm.step("load 99");
+/// gm.step("save");
+///
+/// assert_eq!(buffer.contents(), "\
+/// Game saved in slot 1.
+/// Game loaded from slot 1.
+/// There is no game saved in slot 99.
+/// Please give a slot number, e.g. 'save 1'.
+/// ");
+///
+/// buffer.clear();
+/// gm.step("slots");
+/// assert!(buffer.contents().starts_with("Saved games:\nslot 1 - "));
+/// # std::fs::remove_dir_all(dir).unwrap();
+/// ```
+pub struct SaveCommand;
+
+impl <S: Persistable> GameCommand <S> for SaveCommand {
+    fn execute(&self, state: &Rc<RefCell<S>>, loader: &Rc<RefCell<Loader<S>>>)
+               -> i32 {
+        self.execute_args(&[], state, loader)
+    }
+
+    fn execute_args(&self, args: &[&str], state: &Rc<RefCell<S>>,
+                    loader: &Rc<RefCell<Loader<S>>>)
+                    -> i32 {
+        let mut loader = loader.borrow_mut();
+
+        let slot = match parse_slot(args) {
+            Some(s) => { s },
+            None => {
+                loader.write("Please give a slot number, e.g. 'save 1'.");
+                return NOTURN;
+            }
+        };
+
+        let result = save_slot(&*state.borrow(), loader.save_dir(), slot);
+
+        match result {
+            Ok(_) => {
+                loader.mark_saved();
+                loader.write(&format!("Game saved in slot {}.", slot));
+            },
+            Err(e) => { loader.write(&format!("Could not save the game: {}", e)) }
+        }
+
+        NOTURN
+    }
+
+    fn takes_args(&self) -> bool {
+        true
+    }
+
+    fn usage(&self) -> Option<&str> {
+        Some("save <slot>")
+    }
+}
+
+/// Built-in command restoring the state from a numbered slot (`load <n>`)
+///
+/// Requires `S: Persistable`. See `SaveCommand`.
+pub struct LoadCommand;
+
+impl <S: Persistable> GameCommand <S> for LoadCommand {
+    fn execute(&self, state: &Rc<RefCell<S>>, loader: &Rc<RefCell<Loader<S>>>)
+               -> i32 {
+        self.execute_args(&[], state, loader)
+    }
+
+    fn execute_args(&self, args: &[&str], state: &Rc<RefCell<S>>,
+                    loader: &Rc<RefCell<Loader<S>>>)
+                    -> i32 {
+        let mut loader = loader.borrow_mut();
+
+        let slot = match parse_slot(args) {
+            Some(s) => { s },
+            None => {
+                loader.write("Please give a slot number, e.g. 'load 1'.");
+                return NOTURN;
+            }
+        };
+
+        let path = slot_path(loader.save_dir(), slot);
+
+        if !path.is_file() {
+            loader.write(&format!("There is no game saved in slot {}.", slot));
+            return NOTURN;
+        }
+
+        match load_slot(&path) {
+            Ok(restored) => {
+                *state.borrow_mut() = restored;
+                loader.mark_loaded();
+                loader.write(&format!("Game loaded from slot {}.", slot));
+            },
+            Err(e) => {
+                loader.write(&format!("Could not load the game: {}", e));
+            }
+        }
+
+        NOTURN
+    }
+
+    fn takes_args(&self) -> bool {
+        true
+    }
+
+    fn usage(&self) -> Option<&str> {
+        Some("load <slot>")
+    }
+}
+
+/// Built-in command listing the saved slots and when they were saved
+///
+/// Works with any state type. See `SaveCommand`.
+pub struct SlotsCommand;
+
+impl <S> GameCommand <S> for SlotsCommand {
+    fn execute(&self, _state: &Rc<RefCell<S>>,
+               loader: &Rc<RefCell<Loader<S>>>)
+               -> i32 {
+        let mut loader = loader.borrow_mut();
+        let slots = list_slots(loader.save_dir());
+
+        if slots.is_empty() {
+            loader.write("There are no saved games.");
+            return NOTURN;
+        }
+
+        loader.write("Saved games:");
+
+        for (slot, saved) in slots {
+            loader.write(&format!("slot {} - {}", slot, format_time(saved)));
+        }
+
+        NOTURN
+    }
+}
+
+/// Built-in command showing the details of an object (`examine <object>`)
+///
+/// The object is looked up in the current scenario (see
+/// `Scenario::examine()`). Usually registered as `examine` and `x`. Works
+/// with any state type.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::command::ExamineCommand;
+/// use texture::data::DataScenario;
+/// use texture::master::GameMaster;
+/// use texture::output::BufferOutput;
+/// use texture::state::{BaseState, BasicState};
+///
+/// let mut study = DataScenario::new("A quiet study.".to_string());
+/// study.describe_object("lamp", "An old brass lamp, still warm.");
+/// study.describe_object("desk", "A sturdy oak desk.");
+///
+/// let buffer = BufferOutput::new();
+/// let state = Rc::new(RefCell::new(BasicState::new()));
+///
+/// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(study)));
+/// gm.set_output(Box::new(buffer.clone()));
+/// gm.add_command("examine".to_string(), Box::new(ExamineCommand));
+/// gm.add_command("x".to_string(), Box::new(ExamineCommand));
+/// gm.add_synonym("lamp", "lantern");
+///
+/// gm.step("examine lamp");
+/// gm.step("x the Lamp");
+/// gm.step("x lantern");
+/// gm.step("x chair");
+/// gm.step("examine");
+///
+/// assert_eq!(buffer.contents(), "\
+/// An old brass lamp, still warm.
+/// An old brass lamp, still warm.
+/// An old brass lamp, still warm.
+/// You see nothing special.
+/// What do you want to examine?
+/// ");
+/// ```
+pub struct ExamineCommand;
+
+impl <S> GameCommand <S> for ExamineCommand {
+    fn execute(&self, state: &Rc<RefCell<S>>, loader: &Rc<RefCell<Loader<S>>>)
+               -> i32 {
+        self.execute_args(&[], state, loader)
+    }
+
+    fn execute_args(&self, args: &[&str], _state: &Rc<RefCell<S>>,
+                    loader: &Rc<RefCell<Loader<S>>>)
+                    -> i32 {
+        let mut loader = loader.borrow_mut();
+
+        let words: Vec<&str> = match args.first() {
+            Some(&"the") => { args[1..].to_vec() },
+            _ => { args.to_vec() }
+        };
+
+        if words.is_empty() {
+            loader.write("What do you want to examine?");
+            return NOTURN;
+        }
+
+        let object = loader.resolve_object(&words.join(" "));
+
+        let details = match loader.current_scenario() {
+            Some(scenario) => { scenario.borrow().examine(&object) },
+            None => { None }
+        };
+
+        match details {
+            Some(text) => { loader.write(&text) },
+            None => { loader.write("You see nothing special.") }
+        }
+
+        NOTURN
+    }
+
+    fn takes_args(&self) -> bool {
+        true
+    }
+
+    fn usage(&self) -> Option<&str> {
+        Some("examine <object>")
+    }
+}
+
+/// Built-in command picking up an item (`take <item>`)
+///
+/// The item must be available in the current scenario (see
+/// `Scenario::can_take()`), and is added to the inventory by its canonical
+/// name (see `Loader::add_synonym()`). Requires `S: BaseState`.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::command::TakeCommand;
+/// use texture::data::DataScenario;
+/// use texture::master::GameMaster;
+/// use texture::output::BufferOutput;
+/// use texture::state::{BaseState, BasicState};
+///
+/// let mut study = DataScenario::new("A quiet study.".to_string());
+/// study.add_item("Lamp");
+///
+/// let buffer = BufferOutput::new();
+/// let state = Rc::new(RefCell::new(BasicState::new()));
+///
+/// let mut gm = GameMaster::new(state.clone(), Rc::new(RefCell::new(study)));
+/// gm.set_output(Box::new(buffer.clone()));
+/// gm.add_command("take".to_string(), Box::new(TakeCommand));
+/// gm.add_synonym("Lamp", "lantern");
+///
+/// gm.step("take the Lantern");
+/// gm.step("take LAMP");
+/// gm.step("take desk");
+///
+/// assert!(state.borrow().has_item("lamp"));
+/// assert!(!state.borrow().has_item("lantern"));
+/// assert_eq!(buffer.contents(), "\
+/// Taken.
+/// You already have that.
+/// You can't take that.
+/// ");
+/// ```
+pub struct TakeCommand;
+
+impl <S: BaseState> GameCommand <S> for TakeCommand {
+    fn execute(&self, state: &Rc<RefCell<S>>, loader: &Rc<RefCell<Loader<S>>>)
+               -> i32 {
+        self.execute_args(&[], state, loader)
+    }
+
+    fn execute_args(&self, args: &[&str], state: &Rc<RefCell<S>>,
+                    loader: &Rc<RefCell<Loader<S>>>)
+                    -> i32 {
+        let mut loader = loader.borrow_mut();
+
+        let words: Vec<&str> = match args.first() {
+            Some(&"the") => { args[1..].to_vec() },
+            _ => { args.to_vec() }
+        };
+
+        if words.is_empty() {
+            loader.write("What do you want to take?");
+            return NOTURN;
+        }
+
+        let item = loader.resolve_object(&words.join(" "));
+
+        if state.borrow().has_item(&item) {
+            loader.write("You already have that.");
+            return NOTURN;
+        }
+
+        let available = match loader.current_scenario() {
+            Some(scenario) => { scenario.borrow().can_take(&item, state) },
+            None => { false }
+        };
+
+        if !available {
+            loader.write("You can't take that.");
+            return NOTURN;
+        }
+
+        state.borrow_mut().add_item(&item, 1);
+        loader.write("Taken.");
+        loader.emit(OutputEvent::ItemGained(item));
+
+        TICK
+    }
+
+    fn takes_args(&self) -> bool {
+        true
+    }
+
+    fn usage(&self) -> Option<&str> {
+        Some("take <item>")
+    }
+}
+
+/// Obtain the slot number from the arguments of a command
+fn parse_slot(args: &[&str]) -> Option<u32> {
+    if args.len() != 1 {
+        return None;
+    }
+
+    args[0].parse().ok()
+}
+
+/// Path of the file storing a slot
+fn slot_path(dir: &str, slot: u32) -> PathBuf {
+    PathBuf::from(dir).join(format!("slot{}.sav", slot))
+}
+
+/// Write the state to a slot, creating the save directory if needed
+fn save_slot<S: Persistable>(state: &S, dir: &str, slot: u32)
+                             -> Result<(), SaveError> {
+    let data = state.save_to_bytes()?;
+
+    fs::create_dir_all(dir)?;
+    File::create(slot_path(dir, slot))?.write_all(&data)?;
+
+    Ok(())
+}
+
+/// Read a state from a slot file
+fn load_slot<S: Persistable>(path: &PathBuf) -> Result<S, SaveError> {
+    let mut data = Vec::new();
+    File::open(path)?.read_to_end(&mut data)?;
+
+    S::restore_from_bytes(&data)
+}
+
+/// Find the saved slots, sorted, with their modification time in seconds
+/// since the epoch
+fn list_slots(dir: &str) -> Vec<(u32, u64)> {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => { e },
+        Err(_) => { return Vec::new() }
+    };
+
+    let mut slots: Vec<(u32, u64)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+
+            if !name.starts_with("slot") || !name.ends_with(".sav") {
+                return None;
+            }
+
+            let slot = name[4..name.len() - 4].parse().ok()?;
+            let saved = entry.metadata().ok()?
+                .modified().ok()?
+                .duration_since(UNIX_EPOCH).ok()?
+                .as_secs();
+
+            Some((slot, saved))
+        })
+        .collect();
+
+    slots.sort();
+
+    slots
+}
+
+/// Format seconds since the epoch as a UTC date and time
+fn format_time(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let time = secs % 86400;
+
+    // Civil date from days since 1970-01-01
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            year, month, day, time / 3600, time % 3600 / 60, time % 60)
+}
+
+/// Built-in command letting time pass (usually `wait` or `z`)
+///
+/// Takes a turn, so scheduled events (see `Loader::schedule()`) fire as
+/// usual.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::command::WaitCommand;
+/// use texture::data::DataScenario;
+/// use texture::master::GameMaster;
+/// use texture::output::BufferOutput;
+/// use texture::state::{BaseState, BasicState};
+/// use texture::util::TICK;
+///
+/// let platform = DataScenario::new("An empty platform.".to_string());
+///
+/// let buffer = BufferOutput::new();
+/// let state = Rc::new(RefCell::new(BasicState::new()));
+///
+/// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(platform)));
+/// gm.set_output(Box::new(buffer.clone()));
+/// gm.add_command("wait".to_string(), Box::new(WaitCommand));
+/// gm.add_command("z".to_string(), Box::new(WaitCommand));
+///
+/// gm.loader().borrow_mut().schedule(2, Box::new(|_, loader| {
+///     loader.borrow_mut().write("The train arrives.");
+///     TICK
+/// }));
+///
+/// gm.step("wait");
+/// gm.step("z");
+///
+/// assert_eq!(gm.turns(), 2);
+/// assert_eq!(buffer.contents(), "\
+/// Time passes.
+/// Time passes.
+/// The train arrives.
+/// ");
+/// ```
+pub struct WaitCommand;
+
+impl <S> GameCommand <S> for WaitCommand {
+    fn execute(&self, _state: &Rc<RefCell<S>>, loader: &Rc<RefCell<Loader<S>>>)
+               -> i32 {
+        loader.borrow_mut().write("Time passes.");
+
+        TICK
+    }
+}
+
+/// Built-in command moving directly to a scenario entered before
+/// (`travel <name>`)
+///
+/// The scenario is looked up by the name it was registered with (see
+/// `Loader::load_by_name()`), and must have been visited and accept the
+/// player (see `Scenario::can_enter()`).
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::command::TravelCommand;
+/// use texture::data::{DataScenario, Exit};
+/// use texture::master::GameMaster;
+/// use texture::output::BufferOutput;
+/// use texture::state::{BaseState, BasicState};
+///
+/// let mut hall = DataScenario::new("A long hall.".to_string());
+/// hall.add_exit("north".to_string(), Exit::new("garden".to_string()));
+///
+/// let garden = DataScenario::new("A walled garden.".to_string());
+/// let cellar = DataScenario::new("A damp cellar.".to_string());
+///
+/// let hall = Rc::new(RefCell::new(hall));
+///
+/// let buffer = BufferOutput::new();
+/// let state = Rc::new(RefCell::new(BasicState::new()));
+///
+/// let mut gm = GameMaster::new(state, hall.clone());
+/// gm.set_output(Box::new(buffer.clone()));
+/// gm.add_command("travel".to_string(), Box::new(TravelCommand));
+///
+/// {
+///     let loader = gm.loader();
+///     let mut loader = loader.borrow_mut();
+///     loader.register("hall".to_string(), hall);
+///     loader.register("garden".to_string(), Rc::new(RefCell::new(garden)));
+///     loader.register("cellar".to_string(), Rc::new(RefCell::new(cellar)));
+/// }
+///
+/// gm.begin();
+/// gm.step("north");
+/// buffer.clear();
+///
+/// gm.step("travel cellar");
+/// gm.step("travel garden");
+/// gm.step("travel hall");
+///
+/// assert_eq!(buffer.contents(), "\
+/// You haven't been there yet.
+/// You are already there.
+///  \n\
+/// A long hall.
+/// ");
+/// ```
+pub struct TravelCommand;
+
+impl <S> GameCommand <S> for TravelCommand {
+    fn execute(&self, state: &Rc<RefCell<S>>, loader: &Rc<RefCell<Loader<S>>>)
+               -> i32 {
+        self.execute_args(&[], state, loader)
+    }
+
+    fn execute_args(&self, args: &[&str], state: &Rc<RefCell<S>>,
+                    loader: &Rc<RefCell<Loader<S>>>)
+                    -> i32 {
+        let mut loader = loader.borrow_mut();
+
+        if args.is_empty() {
+            loader.write("Where do you want to travel?");
+            return NOTURN;
+        }
+
+        let name = args.join(" ");
+
+        if !loader.has_visited(&name) {
+            loader.write("You haven't been there yet.");
+            return NOTURN;
+        }
+
+        if loader.current_scenario_name().as_ref() == Some(&name) {
+            loader.write("You are already there.");
+            return NOTURN;
+        }
+
+        let scenario = match loader.get_by_name(&name) {
+            Some(s) => { s },
+            None => {
+                loader.write("You haven't been there yet.");
+                return NOTURN;
+            }
+        };
+
+        if !scenario.borrow().can_enter(state) {
+            loader.write("You can't go there right now.");
+            return NOTURN;
+        }
+
+        loader.set_scenario(scenario);
+
+        LOAD
+    }
+
+    fn takes_args(&self) -> bool {
+        true
+    }
+
+    fn usage(&self) -> Option<&str> {
+        Some("travel <place>")
+    }
 }
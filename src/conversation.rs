@@ -0,0 +1,244 @@
+// MIT License
+//
+// Copyright (c) 2016 Rafael Medina García <rafamedgar@gmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use input::choose;
+use scenario::{Loader, Scenario};
+use util::{LOAD, NOTURN, TICK};
+
+/// Answer the player can give in a conversation node
+pub struct Response {
+    /// Text shown to the player
+    pub text: String,
+    /// Node the response leads to, or `None` to end the conversation
+    pub next: Option<String>,
+}
+
+/// Line said by a character, followed by the possible answers
+pub struct ConversationNode {
+    /// Text said by the character
+    pub line: String,
+    // Answers, in the order they are shown
+    responses: Vec<Response>,
+}
+
+impl ConversationNode {
+    /// Create a node without answers
+    ///
+    /// A node without answers ends the conversation after showing the line.
+    pub fn new(line: String) -> ConversationNode {
+        ConversationNode {
+            line: line,
+            responses: Vec::new(),
+        }
+    }
+
+    /// Add an answer leading to another node (or ending the conversation
+    /// with `None`)
+    pub fn add_response(&mut self, text: String, next: Option<String>) {
+        self.responses.push(Response { text: text, next: next });
+    }
+}
+
+/// Modal scenario presenting a dialogue tree
+///
+/// Meant to be entered with `Loader::push_scenario()`. Each node shows a
+/// line and numbered answers, chosen like in `Input::menu()`. When an answer
+/// without a next node is chosen, the conversation pops back to the
+/// scenario it was pushed from.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::conversation::{Conversation, ConversationNode};
+/// use texture::master::GameMaster;
+/// use texture::output::BufferOutput;
+/// use texture::scenario::{Loader, Scenario};
+/// use texture::state::{BaseState, BasicState};
+/// use texture::util::{LOAD, TICK};
+///
+/// struct Tavern {
+///     talk: Rc<RefCell<Conversation>>,
+/// }
+///
+/// impl <S> Scenario <S> for Tavern {
+///     fn load(&self, state: &Rc<RefCell<S>>,
+///             loader: &Rc<RefCell<Loader<S>>>)
+///             -> i32 {
+///         loader.borrow_mut().write("A noisy tavern.");
+///         TICK
+///     }
+///
+///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+///                  loader: &Rc<RefCell<Loader<S>>>)
+///                  -> i32 {
+///         if command == "talk" {
+///             loader.borrow_mut().push_scenario(self.talk.clone());
+///             return LOAD;
+///         }
+///
+///         loader.borrow_mut().write("You sip your ale.");
+///         TICK
+///     }
+/// }
+///
+/// let mut greeting = ConversationNode::new("Welcome, traveller!".to_string());
+/// greeting.add_response("Any news?".to_string(), Some("news".to_string()));
+/// greeting.add_response("Goodbye.".to_string(), None);
+///
+/// let mut news = ConversationNode::new("A dragon was seen up north.".to_string());
+/// news.add_response("Thanks.".to_string(), None);
+///
+/// let mut talk = Conversation::new("greeting".to_string());
+/// talk.add_node("greeting".to_string(), greeting);
+/// talk.add_node("news".to_string(), news);
+///
+/// let buffer = BufferOutput::new();
+/// let state = Rc::new(RefCell::new(BasicState::new()));
+/// let tavern = Tavern { talk: Rc::new(RefCell::new(talk)) };
+///
+/// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(tavern)));
+/// gm.set_output(Box::new(buffer.clone()));
+///
+/// gm.step("talk");
+/// gm.step("9");
+/// gm.step("any");
+/// gm.step("1");
+/// gm.step("wait");
+///
+/// assert_eq!(buffer.contents(), " \n\
+/// Welcome, traveller!\n\
+/// 1) Any news?\n\
+/// 2) Goodbye.\n\
+/// Please choose one of the options.\n\
+/// A dragon was seen up north.\n\
+/// 1) Thanks.\n\
+/// You sip your ale.\n");
+/// ```
+pub struct Conversation {
+    // Node shown when the conversation starts
+    start: String,
+    // Nodes by id
+    nodes: HashMap<String, ConversationNode>,
+    // Node being shown
+    current: RefCell<String>,
+}
+
+impl Conversation {
+    /// Create a conversation starting at the given node
+    pub fn new(start: String) -> Conversation {
+        Conversation {
+            current: RefCell::new(start.clone()),
+            start: start,
+            nodes: HashMap::new(),
+        }
+    }
+
+    /// Add a node, replacing any other node with the same id
+    pub fn add_node(&mut self, id: String, node: ConversationNode) {
+        self.nodes.insert(id, node);
+    }
+
+    /// Obtain the node being shown (if it exists)
+    fn current_node(&self) -> Option<&ConversationNode> {
+        self.nodes.get(&*self.current.borrow())
+    }
+
+    /// Return to the scenario the conversation was pushed from
+    fn end<S>(&self, loader: &Rc<RefCell<Loader<S>>>) -> i32 {
+        if loader.borrow_mut().pop_scenario() {
+            LOAD
+        } else {
+            NOTURN
+        }
+    }
+}
+
+impl <S> Scenario <S> for Conversation {
+    fn load(&self, _state: &Rc<RefCell<S>>,
+            _loader: &Rc<RefCell<Loader<S>>>)
+            -> i32 {
+        *self.current.borrow_mut() = self.start.clone();
+
+        TICK
+    }
+
+    fn describe(&self, _state: &Rc<RefCell<S>>,
+                loader: &Rc<RefCell<Loader<S>>>) {
+        let node = match self.current_node() {
+            Some(n) => { n },
+            None => { return }
+        };
+
+        let mut loader = loader.borrow_mut();
+        loader.write(&node.line);
+
+        for (i, response) in node.responses.iter().enumerate() {
+            loader.write(&format!("{}) {}", i + 1, response.text));
+        }
+    }
+
+    fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+                 loader: &Rc<RefCell<Loader<S>>>)
+                 -> i32 {
+        let next = {
+            let node = match self.current_node() {
+                Some(n) => { n },
+                None => { return self.end(loader) }
+            };
+
+            if node.responses.is_empty() {
+                return self.end(loader);
+            }
+
+            let options: Vec<&str> = node.responses.iter()
+                .map(|r| r.text.as_str())
+                .collect();
+
+            match choose(command, &options) {
+                Some(i) => { node.responses[i].next.clone() },
+                None => {
+                    loader.borrow_mut()
+                        .write("Please choose one of the options.");
+                    return NOTURN;
+                }
+            }
+        };
+
+        match next {
+            Some(id) => {
+                *self.current.borrow_mut() = id;
+                self.describe(state, loader);
+
+                TICK
+            },
+            None => { self.end(loader) }
+        }
+    }
+}
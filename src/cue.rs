@@ -0,0 +1,42 @@
+// MIT License
+//
+// Copyright (c) 2016 Rafael Medina García <rafamedgar@gmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+/// Player of asset cues (sounds, music...) requested by scenarios
+///
+/// The engine does not play anything by itself; games with audio implement
+/// this trait on top of their backend of choice and install it with
+/// `GameMaster::set_cue_player()`. Scenarios then request cues through the
+/// loader, e.g. `loader.borrow_mut().play_cue("door_creak")`.
+pub trait CuePlayer {
+    /// Play the cue with the given name
+    fn play(&mut self, cue: &str);
+}
+
+/// Cue player that ignores every cue
+///
+/// Used by default.
+pub struct NullCuePlayer;
+
+impl CuePlayer for NullCuePlayer {
+    fn play(&mut self, _cue: &str) {
+    }
+}
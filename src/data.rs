@@ -0,0 +1,922 @@
+// MIT License
+//
+// Copyright (c) 2016 Rafael Medina García <rafamedgar@gmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::rc::Rc;
+
+#[cfg(feature = "serde")]
+use std::fs::File;
+#[cfg(feature = "serde")]
+use std::io::Read;
+
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+#[cfg(feature = "serde")]
+use serde_json;
+#[cfg(feature = "toml")]
+use toml;
+
+#[cfg(feature = "serde")]
+use command::HelpCommand;
+use eval::{BasicEvaluator, Evaluator};
+use scenario::{Loader, Scenario};
+use state::BaseState;
+use util::{LOAD, NOTURN, TICK};
+
+/// Exit from a data scenario to another registered scenario
+pub struct Exit {
+    /// Name of the scenario the exit leads to
+    pub target: String,
+    /// Flag that must be set for the exit to be traversable
+    pub required_flag: Option<String>,
+    /// Expression that must hold for the exit to be traversable
+    ///
+    /// Evaluated with `BasicEvaluator`, e.g. `flag:has_key && value:gold > 10`
+    /// or `has_item:rope || item_count:gold >= 10`.
+    pub condition: Option<String>,
+    /// Hidden exits can be traversed, but are not listed
+    pub hidden: bool,
+}
+
+impl Exit {
+    /// Create a visible, unlocked exit to the given scenario
+    pub fn new(target: String) -> Exit {
+        Exit {
+            target: target,
+            required_flag: None,
+            condition: None,
+            hidden: false,
+        }
+    }
+
+    /// Check whether the exit can be traversed with the given state
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::data::Exit;
+    /// use texture::state::{BaseState, BasicState};
+    ///
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut cliff = Exit::new("ledge".to_string());
+    /// cliff.condition = Some("has_item:rope".to_string());
+    /// assert!(!cliff.is_open(&state));
+    ///
+    /// state.borrow_mut().add_item("rope", 1);
+    /// assert!(cliff.is_open(&state));
+    /// ```
+    pub fn is_open<S: BaseState>(&self, state: &Rc<RefCell<S>>) -> bool {
+        if let Some(ref flag) = self.required_flag {
+            if !state.borrow().get_flag(flag.clone()) {
+                return false;
+            }
+        }
+
+        match self.condition {
+            Some(ref expr) => { BasicEvaluator.eval(expr, state) },
+            None => { true }
+        }
+    }
+}
+
+/// Scenario defined by data rather than code
+///
+/// Shows a fixed description and moves the player through its exits, which
+/// are used by typing their name (e.g. `north`) or `go <name>`. The `exits`
+/// command lists visible exits, marking the locked ones. Exits lead to
+/// scenarios registered in the loader.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::data::{DataScenario, Exit};
+/// use texture::master::GameMaster;
+/// use texture::output::BufferOutput;
+/// use texture::state::{BaseState, BasicState};
+///
+/// let mut hall = DataScenario::new("A long hall.".to_string());
+///
+/// let mut door = Exit::new("vault".to_string());
+/// door.required_flag = Some("has_key".to_string());
+/// hall.add_exit("north".to_string(), door);
+///
+/// let mut passage = Exit::new("vault".to_string());
+/// passage.hidden = true;
+/// hall.add_exit("down".to_string(), passage);
+///
+/// let mut window = Exit::new("vault".to_string());
+/// window.condition = Some("value:strength >= 10".to_string());
+/// hall.add_exit("west".to_string(), window);
+///
+/// let vault = DataScenario::new("A dusty vault.".to_string());
+///
+/// let buffer = BufferOutput::new();
+/// let state = Rc::new(RefCell::new(BasicState::new()));
+///
+/// let mut gm = GameMaster::new(state.clone(), Rc::new(RefCell::new(hall)));
+/// gm.set_output(Box::new(buffer.clone()));
+/// gm.loader().borrow_mut().register("vault".to_string(),
+///                                   Rc::new(RefCell::new(vault)));
+///
+/// gm.step("exits");
+/// gm.step("north");
+/// assert_eq!(buffer.contents(),
+///            "Exits: north (locked), west (locked)\nThe way north is locked.\n");
+///
+/// state.borrow_mut().set_flag("has_key".to_string(), true);
+/// buffer.clear();
+///
+/// gm.step("go north");
+/// assert!(buffer.contents().contains("A dusty vault."));
+/// ```
+pub struct DataScenario {
+    /// Text shown when the scenario is described
+    pub description: String,
+    // Exits by name
+    exits: BTreeMap<String, Exit>,
+    // Details of the objects in the scenario, by name
+    objects: BTreeMap<String, String>,
+    // Items that can be picked up
+    items: BTreeSet<String>,
+}
+
+impl DataScenario {
+    /// Create a scenario without exits
+    pub fn new(description: String) -> DataScenario {
+        DataScenario {
+            description: description,
+            exits: BTreeMap::new(),
+            objects: BTreeMap::new(),
+            items: BTreeSet::new(),
+        }
+    }
+
+    /// Add an exit, replacing any other exit with the same name
+    pub fn add_exit(&mut self, name: String, exit: Exit) {
+        self.exits.insert(name, exit);
+    }
+
+    /// Obtain an exit by its name
+    pub fn exit(&self, name: &str) -> Option<&Exit> {
+        self.exits.get(name)
+    }
+
+    /// Set the text shown when examining an object in the scenario
+    ///
+    /// Object names are not case sensitive.
+    pub fn describe_object(&mut self, name: &str, text: &str) {
+        self.objects.insert(name.to_lowercase(), text.to_string());
+    }
+
+    /// Place an item that can be picked up in the scenario
+    ///
+    /// Each item can be picked up while the player does not have it (see
+    /// `BaseState::has_item()`). Item names are not case sensitive, and are
+    /// added to the inventory in lowercase.
+    pub fn add_item(&mut self, name: &str) {
+        self.items.insert(name.to_lowercase());
+    }
+
+    /// Write the list of visible exits
+    fn list_exits<S: BaseState>(&self, state: &Rc<RefCell<S>>,
+                                loader: &mut Loader<S>) {
+        let visible: Vec<String> = self.exits.iter()
+            .filter(|&(_, exit)| !exit.hidden)
+            .map(|(name, exit)| {
+                if exit.is_open(state) {
+                    name.clone()
+                } else {
+                    format!("{} (locked)", name)
+                }
+            })
+            .collect();
+
+        if visible.is_empty() {
+            loader.write("There are no obvious exits.");
+        } else {
+            loader.write(&format!("Exits: {}", visible.join(", ")));
+        }
+    }
+}
+
+impl <S: BaseState> Scenario <S> for DataScenario {
+    fn load(&self, _state: &Rc<RefCell<S>>, _loader: &Rc<RefCell<Loader<S>>>)
+            -> i32 {
+        TICK
+    }
+
+    fn describe(&self, _state: &Rc<RefCell<S>>,
+                loader: &Rc<RefCell<Loader<S>>>) {
+        loader.borrow_mut().write(&self.description);
+    }
+
+    fn examine(&self, object: &str) -> Option<String> {
+        self.objects.get(&object.to_lowercase()).cloned()
+    }
+
+    fn can_take(&self, object: &str, state: &Rc<RefCell<S>>) -> bool {
+        let object = object.to_lowercase();
+
+        self.items.contains(&object) && !state.borrow().has_item(&object)
+    }
+
+    fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+                 loader: &Rc<RefCell<Loader<S>>>)
+                 -> i32 {
+        if command == "exits" {
+            self.list_exits(state, &mut *loader.borrow_mut());
+            return NOTURN;
+        }
+
+        let name = match command.strip_prefix("go ") {
+            Some(name) => { name.trim() },
+            None => { command }
+        };
+
+        let exit = match self.exits.get(name) {
+            Some(e) => { e },
+            None => {
+                loader.borrow_mut().write("You can't do that here.");
+                return NOTURN;
+            }
+        };
+
+        if !exit.is_open(state) {
+            loader.borrow_mut().write(&format!("The way {} is locked.", name));
+            return TICK;
+        }
+
+        // Unknown targets are reported by the loader
+        if !loader.borrow_mut().load_by_name(&exit.target) {
+            return NOTURN;
+        }
+
+        LOAD
+    }
+}
+
+/// Check a set of data scenarios for mistakes before playing
+///
+/// Every problem found is reported, rather than only the first:
+///
+/// - the start scenario does not exist
+/// - an exit leads to a scenario that does not exist
+/// - the condition of an exit is not a valid expression
+/// - the same required flag is spelled in different ways (names differing
+///   only in case, `_`, `-` or spaces, e.g. `has_key` and `hasKey`)
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// use texture::data::{validate_world, DataScenario, Exit};
+///
+/// let mut hall = DataScenario::new("A long hall.".to_string());
+/// hall.add_exit("north".to_string(), Exit::new("garden".to_string()));
+/// hall.add_exit("down".to_string(), Exit::new("cellar".to_string()));
+///
+/// let mut world = BTreeMap::new();
+/// world.insert("hall".to_string(), hall);
+/// world.insert("garden".to_string(),
+///              DataScenario::new("A walled garden.".to_string()));
+///
+/// assert_eq!(validate_world(&world, "hall"), Err(vec![
+///     "exit 'down' of 'hall' leads to unknown scenario 'cellar'".to_string(),
+/// ]));
+///
+/// world.remove("hall");
+/// assert!(validate_world(&world, "garden").is_ok());
+/// ```
+pub fn validate_world(scenarios: &BTreeMap<String, DataScenario>, start: &str)
+                      -> Result<(), Vec<String>> {
+    let mut problems = Vec::new();
+
+    if !scenarios.contains_key(start) {
+        problems.push(format!("start scenario '{}' does not exist", start));
+    }
+
+    problems.extend(check_world(scenarios,
+                                &|name| scenarios.contains_key(name)));
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems)
+    }
+}
+
+/// Find the mistakes in a set of data scenarios, except for the start
+/// scenario
+///
+/// Exits may lead to any scenario accepted by `is_known`.
+fn check_world(scenarios: &BTreeMap<String, DataScenario>,
+               is_known: &Fn(&str) -> bool)
+               -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut flags: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+
+    for (name, scenario) in scenarios {
+        for (exit_name, exit) in &scenario.exits {
+            if !is_known(&exit.target) {
+                problems.push(format!(
+                    "exit '{}' of '{}' leads to unknown scenario '{}'",
+                    exit_name, name, exit.target));
+            }
+
+            if let Some(ref expr) = exit.condition {
+                if let Err(e) = BasicEvaluator::validate(expr) {
+                    problems.push(format!(
+                        "exit '{}' of '{}' has an invalid condition: {}",
+                        exit_name, name, e));
+                }
+            }
+
+            if let Some(ref flag) = exit.required_flag {
+                let key: String = flag.to_lowercase()
+                    .chars()
+                    .filter(|&c| c != '_' && c != '-' && c != ' ')
+                    .collect();
+
+                flags.entry(key)
+                    .or_insert_with(BTreeSet::new)
+                    .insert(flag.clone());
+            }
+        }
+    }
+
+    for spellings in flags.values() {
+        if spellings.len() > 1 {
+            let names: Vec<&str> = spellings.iter().map(|s| s.as_str()).collect();
+            problems.push(format!("flag spelled in different ways: {}",
+                                  names.join(", ")));
+        }
+    }
+
+    problems
+}
+
+/// What to do when several data files define the same scenario
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// Report the repeated name as an error
+    Reject,
+    /// Keep the scenario read last, writing a warning
+    LastWins,
+}
+
+/// Error obtained when reading scenarios from a data file
+#[derive(Debug)]
+pub enum DataError {
+    /// The file could not be read
+    Io(io::Error),
+    /// The contents of the file could not be understood
+    Format(String),
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DataError::Io(ref e) => { write!(f, "data I/O error: {}", e) },
+            DataError::Format(ref e) => { write!(f, "invalid data: {}", e) }
+        }
+    }
+}
+
+impl Error for DataError {}
+
+impl From<io::Error> for DataError {
+    fn from(e: io::Error) -> DataError {
+        DataError::Io(e)
+    }
+}
+
+/// Scenarios as written in a data file
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct WorldDef {
+    #[serde(default)]
+    rooms: BTreeMap<String, RoomDef>,
+    #[serde(default)]
+    commands: BTreeMap<String, String>,
+}
+
+/// Scenario as written in a data file
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct RoomDef {
+    description: String,
+    #[serde(default)]
+    exits: BTreeMap<String, ExitDef>,
+    #[serde(default)]
+    objects: BTreeMap<String, String>,
+    #[serde(default)]
+    items: Vec<String>,
+}
+
+/// Exit as written in a data file
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct ExitDef {
+    target: String,
+    #[serde(default)]
+    required_flag: Option<String>,
+    #[serde(default)]
+    condition: Option<String>,
+    #[serde(default)]
+    hidden: bool,
+}
+
+#[cfg(feature = "serde")]
+impl WorldDef {
+    /// Build the scenarios, by name
+    fn build(self) -> BTreeMap<String, DataScenario> {
+        let mut scenarios = BTreeMap::new();
+
+        for (name, room) in self.rooms {
+            let mut scenario = DataScenario::new(room.description);
+
+            for (exit_name, exit) in room.exits {
+                scenario.add_exit(exit_name, Exit {
+                    target: exit.target,
+                    required_flag: exit.required_flag,
+                    condition: exit.condition,
+                    hidden: exit.hidden,
+                });
+            }
+
+            for (object, text) in room.objects {
+                scenario.describe_object(&object, &text);
+            }
+
+            for item in room.items {
+                scenario.add_item(&item);
+            }
+
+            scenarios.insert(name, scenario);
+        }
+
+        scenarios
+    }
+}
+
+#[cfg(feature = "serde")]
+impl WorldDef {
+    /// Build the fixed-text commands, by name
+    fn build_commands(self) -> BTreeMap<String, HelpCommand> {
+        self.commands.into_iter()
+            .map(|(name, text)| (name, HelpCommand::new(text)))
+            .collect()
+    }
+}
+
+/// Read a whole file into a string
+#[cfg(feature = "serde")]
+fn read_file(path: &str) -> Result<String, DataError> {
+    let mut data = String::new();
+    File::open(path)?.read_to_string(&mut data)?;
+
+    Ok(data)
+}
+
+/// Parse scenarios from JSON, by name
+///
+/// Rooms are listed under `rooms`, each with a `description`, optional
+/// `exits` with the same fields as `Exit`, optional `objects` mapping
+/// names to the text shown when examining them and optional `items` that
+/// can be picked up. Requires the `serde` feature.
+///
+/// # Examples
+///
+/// ```
+/// use texture::data::parse_scenarios_from_json;
+///
+/// let scenarios = parse_scenarios_from_json(r#"{
+///     "rooms": {
+///         "hall": {
+///             "description": "A long hall.",
+///             "exits": { "north": { "target": "vault" } }
+///         },
+///         "vault": { "description": "A dusty vault." }
+///     }
+/// }"#).unwrap();
+///
+/// assert_eq!(scenarios["hall"].exit("north").unwrap().target, "vault");
+/// assert!(scenarios["vault"].exit("south").is_none());
+/// ```
+#[cfg(feature = "serde")]
+pub fn parse_scenarios_from_json(data: &str)
+                                 -> Result<BTreeMap<String, DataScenario>,
+                                           DataError> {
+    let world: WorldDef = serde_json::from_str(data)
+        .map_err(|e| DataError::Format(e.to_string()))?;
+
+    Ok(world.build())
+}
+
+/// Read scenarios from a JSON file, by name
+///
+/// See `parse_scenarios_from_json()`.
+#[cfg(feature = "serde")]
+pub fn load_scenarios_from_json(path: &str)
+                                -> Result<BTreeMap<String, DataScenario>,
+                                          DataError> {
+    parse_scenarios_from_json(&read_file(path)?)
+}
+
+/// Parse global commands from JSON, by name
+///
+/// Commands are listed under `commands`, mapping each name to the text it
+/// writes (see `HelpCommand`), in the same file as the rooms. Requires the
+/// `serde` feature.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::data::{parse_commands_from_json, DataScenario};
+/// use texture::master::GameMaster;
+/// use texture::output::BufferOutput;
+/// use texture::state::{BaseState, BasicState};
+///
+/// let commands = parse_commands_from_json(r#"{
+///     "rooms": { "hall": { "description": "A long hall." } },
+///     "commands": { "about": "A tiny adventure, written in JSON." }
+/// }"#).unwrap();
+///
+/// let buffer = BufferOutput::new();
+/// let state = Rc::new(RefCell::new(BasicState::new()));
+/// let hall = DataScenario::new("A long hall.".to_string());
+///
+/// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(hall)));
+/// gm.set_output(Box::new(buffer.clone()));
+///
+/// gm.add_commands(commands);
+///
+/// gm.step("about");
+/// assert_eq!(buffer.contents(), "A tiny adventure, written in JSON.\n");
+/// ```
+#[cfg(feature = "serde")]
+pub fn parse_commands_from_json(data: &str)
+                                -> Result<BTreeMap<String, HelpCommand>,
+                                          DataError> {
+    let world: WorldDef = serde_json::from_str(data)
+        .map_err(|e| DataError::Format(e.to_string()))?;
+
+    Ok(world.build_commands())
+}
+
+/// Read global commands from a JSON file, by name
+///
+/// See `parse_commands_from_json()`.
+#[cfg(feature = "serde")]
+pub fn load_commands_from_json(path: &str)
+                               -> Result<BTreeMap<String, HelpCommand>,
+                                         DataError> {
+    parse_commands_from_json(&read_file(path)?)
+}
+
+/// Parse scenarios from TOML, by name
+///
+/// Uses the same layout as `parse_scenarios_from_json()`, so both formats
+/// produce the same scenarios. Requires the `toml` feature.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::data::parse_scenarios_from_toml;
+/// use texture::master::GameMaster;
+/// use texture::output::BufferOutput;
+/// use texture::state::{BaseState, BasicState};
+///
+/// let mut scenarios = parse_scenarios_from_toml(r#"
+///     [rooms.hall]
+///     description = "A long hall."
+///
+///     [rooms.hall.exits.north]
+///     target = "vault"
+///     required_flag = "has_key"
+///
+///     [rooms.hall.exits.down]
+///     target = "cellar"
+///     hidden = true
+///
+///     [rooms.vault]
+///     description = "A dusty vault."
+///
+///     [rooms.cellar]
+///     description = "A damp cellar."
+/// "#).unwrap();
+///
+/// let hall = scenarios.remove("hall").unwrap();
+///
+/// let buffer = BufferOutput::new();
+/// let state = Rc::new(RefCell::new(BasicState::new()));
+///
+/// let mut gm = GameMaster::new(state.clone(), Rc::new(RefCell::new(hall)));
+/// gm.set_output(Box::new(buffer.clone()));
+///
+/// for (name, scenario) in scenarios {
+///     gm.loader().borrow_mut().register(name, Rc::new(RefCell::new(scenario)));
+/// }
+///
+/// gm.step("exits");
+/// gm.step("down");
+/// assert_eq!(buffer.contents(),
+///            "Exits: north (locked)\n \nA damp cellar.\n");
+/// ```
+#[cfg(feature = "toml")]
+pub fn parse_scenarios_from_toml(data: &str)
+                                 -> Result<BTreeMap<String, DataScenario>,
+                                           DataError> {
+    let world: WorldDef = toml::from_str(data)
+        .map_err(|e| DataError::Format(e.to_string()))?;
+
+    Ok(world.build())
+}
+
+/// Read scenarios from a TOML file, by name
+///
+/// See `parse_scenarios_from_toml()`.
+#[cfg(feature = "toml")]
+pub fn load_scenarios_from_toml(path: &str)
+                                -> Result<BTreeMap<String, DataScenario>,
+                                          DataError> {
+    parse_scenarios_from_toml(&read_file(path)?)
+}
+
+/// Parse global commands from TOML, by name
+///
+/// Uses the same layout as `parse_commands_from_json()`. Requires the
+/// `toml` feature.
+///
+/// # Examples
+///
+/// ```
+/// use texture::data::parse_commands_from_toml;
+///
+/// let commands = parse_commands_from_toml(r#"
+///     [commands]
+///     about = "A tiny adventure, written in TOML."
+///     credits = "Thanks for playing!"
+/// "#).unwrap();
+///
+/// assert_eq!(commands.keys().collect::<Vec<_>>(), vec!["about", "credits"]);
+/// ```
+#[cfg(feature = "toml")]
+pub fn parse_commands_from_toml(data: &str)
+                                -> Result<BTreeMap<String, HelpCommand>,
+                                          DataError> {
+    let world: WorldDef = toml::from_str(data)
+        .map_err(|e| DataError::Format(e.to_string()))?;
+
+    Ok(world.build_commands())
+}
+
+/// Read global commands from a TOML file, by name
+///
+/// See `parse_commands_from_toml()`.
+#[cfg(feature = "toml")]
+pub fn load_commands_from_toml(path: &str)
+                               -> Result<BTreeMap<String, HelpCommand>,
+                                         DataError> {
+    parse_commands_from_toml(&read_file(path)?)
+}
+
+/// Read scenarios from a data file, guessing the format from its extension
+///
+/// Files ending in `.toml` are read as TOML (requiring the `toml` feature)
+/// and everything else as JSON.
+#[cfg(feature = "serde")]
+fn load_scenarios(path: &str)
+                  -> Result<BTreeMap<String, DataScenario>, DataError> {
+    if path.ends_with(".toml") {
+        #[cfg(feature = "toml")]
+        return load_scenarios_from_toml(path);
+
+        #[cfg(not(feature = "toml"))]
+        return Err(DataError::Format(
+            "TOML files require the 'toml' feature".to_string()));
+    }
+
+    load_scenarios_from_json(path)
+}
+
+#[cfg(feature = "serde")]
+impl <S: BaseState + 'static> Loader<S> {
+    /// Read the scenarios in several data files and register all of them
+    ///
+    /// The format of each file is guessed from its extension (see
+    /// `load_scenarios_from_json()` and `load_scenarios_from_toml()`).
+    ///
+    /// A scenario name defined more than once, in the files or already
+    /// registered, is handled according to the merge policy (see
+    /// `set_merge_policy()`): with `MergePolicy::Reject` it is an error, and
+    /// with `MergePolicy::LastWins` the last definition is kept and a warning
+    /// is written.
+    ///
+    /// Nothing is registered if there is any error. All the errors found are
+    /// returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::env;
+    /// use std::fs;
+    ///
+    /// use texture::data::MergePolicy;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::Loader;
+    /// use texture::state::BasicState;
+    ///
+    /// let name = format!("texture-merge-{}", std::process::id());
+    /// let dir = env::temp_dir().join(name);
+    /// fs::create_dir_all(&dir).unwrap();
+    ///
+    /// let house = dir.join("house.json");
+    /// let garden = dir.join("garden.json");
+    /// let other = dir.join("other_garden.json");
+    ///
+    /// fs::write(&house, r#"{ "rooms": { "hall": {
+    ///     "description": "A hall."
+    /// } } }"#).unwrap();
+    /// fs::write(&garden, r#"{ "rooms": { "garden": {
+    ///     "description": "Roses."
+    /// } } }"#).unwrap();
+    /// fs::write(&other, r#"{ "rooms": { "garden": {
+    ///     "description": "Weeds."
+    /// } } }"#).unwrap();
+    ///
+    /// let house = house.to_str().unwrap();
+    /// let garden = garden.to_str().unwrap();
+    /// let other = other.to_str().unwrap();
+    ///
+    /// // No collisions
+    /// let mut loader: Loader<BasicState> = Loader::new();
+    ///
+    /// assert!(loader.load_and_merge(&[house, garden]).is_ok());
+    /// assert!(loader.is_registered("hall"));
+    /// assert!(loader.is_registered("garden"));
+    ///
+    /// // Collisions are rejected by default
+    /// let mut loader: Loader<BasicState> = Loader::new();
+    ///
+    /// let errors = loader.load_and_merge(&[house, garden, other]).unwrap_err();
+    /// assert_eq!(errors.len(), 1);
+    /// assert!(errors[0].contains("'garden'"));
+    /// assert!(!loader.is_registered("hall"));
+    ///
+    /// // Or replaced, with a warning
+    /// let buffer = BufferOutput::new();
+    /// loader.set_output(Box::new(buffer.clone()));
+    /// loader.set_merge_policy(MergePolicy::LastWins);
+    ///
+    /// assert!(loader.load_and_merge(&[house, garden, other]).is_ok());
+    /// assert!(loader.is_registered("garden"));
+    /// assert!(buffer.contents().starts_with("Warning: scenario 'garden'"));
+    ///
+    /// fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    pub fn load_and_merge(&mut self, paths: &[&str]) -> Result<(), Vec<String>> {
+        let mut merged: BTreeMap<String, DataScenario> = BTreeMap::new();
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+
+        for path in paths {
+            let scenarios = match load_scenarios(path) {
+                Ok(s) => { s },
+                Err(e) => {
+                    errors.push(format!("{}: {}", path, e));
+                    continue;
+                }
+            };
+
+            for (name, scenario) in scenarios {
+                if merged.contains_key(&name) || self.is_registered(&name) {
+                    let message = format!("scenario '{}' in {} is already defined",
+                                          name, path);
+
+                    match self.merge_policy() {
+                        MergePolicy::Reject => {
+                            errors.push(message);
+                            continue;
+                        },
+                        MergePolicy::LastWins => { warnings.push(message) }
+                    }
+                }
+
+                merged.insert(name, scenario);
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        for warning in warnings {
+            self.write(&format!("Warning: {}", warning));
+        }
+
+        for (name, scenario) in merged {
+            self.mark_content(name.clone());
+            self.register(name, Rc::new(RefCell::new(scenario)));
+        }
+
+        for path in paths {
+            self.add_content_path(path.to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Read again the data files read with `load_and_merge()`, replacing
+    /// the scenarios they define
+    ///
+    /// Scenarios no longer defined in the files are unregistered, even the
+    /// current one (see `GameMaster::reload_content()`). The files are
+    /// checked like in `validate_world()`, with exits allowed to lead to
+    /// scenarios registered in code too. Nothing changes if there is any
+    /// error, and all the errors found are returned.
+    pub fn reload_content(&mut self) -> Result<(), Vec<String>> {
+        let mut merged: BTreeMap<String, DataScenario> = BTreeMap::new();
+        let mut errors = Vec::new();
+
+        for path in self.content_paths().to_vec() {
+            let scenarios = match load_scenarios(&path) {
+                Ok(s) => { s },
+                Err(e) => {
+                    errors.push(format!("{}: {}", path, e));
+                    continue;
+                }
+            };
+
+            for (name, scenario) in scenarios {
+                if merged.contains_key(&name)
+                    && self.merge_policy() == MergePolicy::Reject {
+                    errors.push(format!(
+                        "scenario '{}' in {} is already defined", name, path));
+                    continue;
+                }
+
+                merged.insert(name, scenario);
+            }
+        }
+
+        errors.extend(check_world(&merged, &|name| {
+            merged.contains_key(name)
+                || (self.is_registered(name) && !self.is_content(name))
+        }));
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        for name in self.take_content() {
+            if !merged.contains_key(&name) {
+                self.forget(&name);
+            }
+        }
+
+        for (name, scenario) in merged {
+            self.mark_content(name.clone());
+            self.register(name, Rc::new(RefCell::new(scenario)));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,322 @@
+// MIT License
+//
+// Copyright (c) 2016 Rafael Medina García <rafamedgar@gmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use state::BaseState;
+
+/// Evaluator of conditions written as text
+///
+/// Allows content defined as data (e.g. exits of a `DataScenario`) to
+/// depend on the game state without writing Rust code.
+pub trait Evaluator<S> {
+    /// Check whether the expression holds for the given state
+    fn eval(&self, expr: &str, state: &Rc<RefCell<S>>) -> bool;
+}
+
+/// Built-in evaluator for states implementing `BaseState`
+///
+/// Expressions are made of:
+///
+/// - `flag:<name>`: value of a flag
+/// - `value:<name> <op> <number>`: comparison of an integer value, where
+///   `<op>` is one of `==`, `!=`, `<`, `<=`, `>` or `>=`. A value alone
+///   (`value:<name>`) holds when it is not zero
+/// - `has_item:<name>`: whether there is at least one unit of an item in the
+///   inventory (see `BaseState::has_item()`)
+/// - `item_count:<name> <op> <number>`: comparison of the units of an item,
+///   like for values
+/// - `true` and `false`
+/// - `!` to negate, `&&` and `||` to combine (`&&` binds tighter) and
+///   parentheses to group
+///
+/// Missing flags are `false` and missing values `0`, as in `BaseState`.
+/// Invalid expressions never hold.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::eval::{BasicEvaluator, Evaluator};
+/// use texture::state::{BaseState, BasicState};
+///
+/// let state = Rc::new(RefCell::new(BasicState::new()));
+/// state.borrow_mut().set_flag("has_key".to_string(), true);
+/// state.borrow_mut().set_value("gold".to_string(), 15);
+///
+/// let eval = BasicEvaluator;
+///
+/// assert!(eval.eval("flag:has_key && value:gold > 10", &state));
+/// assert!(!eval.eval("flag:has_key && value:gold >= 20", &state));
+///
+/// // Inventory
+/// state.borrow_mut().add_item("coin", 12);
+/// assert!(eval.eval("has_item:coin && item_count:coin>=10", &state));
+/// assert!(!eval.eval("has_item:lamp || item_count:coin > 20", &state));
+///
+/// // `&&` binds tighter than `||`
+/// assert!(eval.eval("flag:has_key || flag:missing && false", &state));
+/// assert!(!eval.eval("(flag:has_key || flag:missing) && false", &state));
+///
+/// // Missing keys
+/// assert!(!eval.eval("flag:missing", &state));
+/// assert!(eval.eval("!flag:missing && value:missing == 0", &state));
+///
+/// // Invalid expressions
+/// assert!(!eval.eval("flag:has_key &&", &state));
+/// assert!(BasicEvaluator::validate("value:gold >").is_err());
+/// ```
+pub struct BasicEvaluator;
+
+impl BasicEvaluator {
+    /// Check whether an expression is well formed
+    ///
+    /// Returns a description of the problem otherwise.
+    pub fn validate(expr: &str) -> Result<(), String> {
+        let tokens = tokenize(expr)?;
+
+        let mut parser = Parser {
+            tokens: &tokens,
+            pos: 0,
+            flag: &|_| false,
+            value: &|_| 0,
+            item: &|_| 0,
+        };
+
+        parser.parse().map(|_| ())
+    }
+}
+
+impl <S: BaseState> Evaluator<S> for BasicEvaluator {
+    fn eval(&self, expr: &str, state: &Rc<RefCell<S>>) -> bool {
+        let tokens = match tokenize(expr) {
+            Ok(t) => { t },
+            Err(_) => { return false }
+        };
+
+        let state = state.borrow();
+
+        let mut parser = Parser {
+            tokens: &tokens,
+            pos: 0,
+            flag: &|name| state.get_flag(name.to_string()),
+            value: &|name| state.get_value(name.to_string()),
+            item: &|name| state.item_count(name),
+        };
+
+        parser.parse().unwrap_or(false)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    Op(String),
+    Number(i32),
+    Word(String),
+}
+
+/// Split an expression into tokens
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).cloned();
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        match (c, next) {
+            ('&', Some('&')) => { tokens.push(Token::And); i += 2; },
+            ('|', Some('|')) => { tokens.push(Token::Or); i += 2; },
+            ('=', Some('=')) | ('!', Some('=')) |
+            ('<', Some('=')) | ('>', Some('=')) => {
+                tokens.push(Token::Op(format!("{}=", c)));
+                i += 2;
+            },
+            ('<', _) | ('>', _) => { tokens.push(Token::Op(c.to_string())); i += 1; },
+            ('!', _) => { tokens.push(Token::Not); i += 1; },
+            ('(', _) => { tokens.push(Token::Open); i += 1; },
+            (')', _) => { tokens.push(Token::Close); i += 1; },
+            _ => {
+                let start = i;
+
+                while i < chars.len() && is_word_char(chars[i], i == start) {
+                    i += 1;
+                }
+
+                if i == start {
+                    return Err(format!("unexpected character '{}'", c));
+                }
+
+                let word: String = chars[start..i].iter().collect();
+
+                match word.parse::<i32>() {
+                    Ok(n) => { tokens.push(Token::Number(n)) },
+                    Err(_) => { tokens.push(Token::Word(word)) }
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Characters allowed in names and numbers
+fn is_word_char(c: char, first: bool) -> bool {
+    c.is_alphanumeric() || c == '_' || c == ':' || c == '.' ||
+        (first && c == '-')
+}
+
+/// Recursive descent parser evaluating while parsing
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    flag: &'a Fn(&str) -> bool,
+    value: &'a Fn(&str) -> i32,
+    item: &'a Fn(&str) -> i32,
+}
+
+impl <'a> Parser<'a> {
+    fn parse(&mut self) -> Result<bool, String> {
+        let result = self.or()?;
+
+        match self.tokens.get(self.pos) {
+            Some(t) => { Err(format!("unexpected {:?}", t)) },
+            None => { Ok(result) }
+        }
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+
+        token
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos)
+    }
+
+    // or := and ('||' and)*
+    fn or(&mut self) -> Result<bool, String> {
+        let mut result = self.and()?;
+
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            let rhs = self.and()?;
+            result = result || rhs;
+        }
+
+        Ok(result)
+    }
+
+    // and := unary ('&&' unary)*
+    fn and(&mut self) -> Result<bool, String> {
+        let mut result = self.unary()?;
+
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            let rhs = self.unary()?;
+            result = result && rhs;
+        }
+
+        Ok(result)
+    }
+
+    // unary := '!' unary | '(' or ')' | term
+    fn unary(&mut self) -> Result<bool, String> {
+        match self.next() {
+            Some(&Token::Not) => { Ok(!self.unary()?) },
+            Some(&Token::Open) => {
+                let result = self.or()?;
+
+                match self.next() {
+                    Some(&Token::Close) => { Ok(result) },
+                    _ => { Err("missing ')'".to_string()) }
+                }
+            },
+            Some(&Token::Word(ref word)) => { self.term(word) },
+            Some(t) => { Err(format!("unexpected {:?}", t)) },
+            None => { Err("unexpected end of expression".to_string()) }
+        }
+    }
+
+    // term := 'true' | 'false' | 'flag:'name | 'has_item:'name
+    //       | ('value:' | 'item_count:')name (op number)?
+    fn term(&mut self, word: &str) -> Result<bool, String> {
+        if word == "true" {
+            return Ok(true);
+        }
+
+        if word == "false" {
+            return Ok(false);
+        }
+
+        if let Some(name) = word.strip_prefix("flag:") {
+            return Ok((self.flag)(name));
+        }
+
+        if let Some(name) = word.strip_prefix("has_item:") {
+            return Ok((self.item)(name) > 0);
+        }
+
+        let value = if let Some(name) = word.strip_prefix("value:") {
+            (self.value)(name)
+        } else if let Some(name) = word.strip_prefix("item_count:") {
+            (self.item)(name)
+        } else {
+            return Err(format!("unknown term '{}'", word));
+        };
+
+        let op = match self.peek() {
+            Some(&Token::Op(ref op)) => { self.pos += 1; op },
+            _ => { return Ok(value != 0) }
+        };
+
+        let number = match self.next() {
+            Some(&Token::Number(n)) => { n },
+            _ => { return Err(format!("expected a number after '{}'", op)) }
+        };
+
+        match op.as_str() {
+            "==" => { Ok(value == number) },
+            "!=" => { Ok(value != number) },
+            "<" => { Ok(value < number) },
+            "<=" => { Ok(value <= number) },
+            ">" => { Ok(value > number) },
+            _ => { Ok(value >= number) }
+        }
+    }
+}
@@ -0,0 +1,375 @@
+// MIT License
+//
+// Copyright (c) 2016 Rafael Medina García <rafamedgar@gmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, IsTerminal, Stdin};
+
+use linenoise;
+
+use output::Output;
+
+/// Source of the text typed by the player
+///
+/// The game loop reads input through the source installed in the `Loader`,
+/// so games can be driven by something other than the terminal (e.g. a
+/// script in tests).
+pub trait Input {
+    /// Read a line of input after showing the given prompt
+    ///
+    /// Returns `None` when there is no more input, which ends the game loop.
+    fn read_line(&mut self, prompt: &str) -> Option<String>;
+
+    /// Check whether the player already sees the text read
+    ///
+    /// Terminals show what the player types, while text read from a file or
+    /// socket is not shown unless the game echoes it (see
+    /// `GameMaster::set_echo_input()`). Returns `true` by default.
+    fn shows_input(&self) -> bool {
+        true
+    }
+
+    /// Check whether a person is typing the input
+    ///
+    /// Interactive sources can be asked to press a key (e.g. by the pager,
+    /// see `Loader::set_pager()`), while files and sockets cannot. Returns
+    /// `true` by default.
+    fn is_interactive(&self) -> bool {
+        true
+    }
+
+    /// Ask the player to choose one of the options
+    ///
+    /// The options are written numbered, starting at 1. The player may
+    /// answer with the number or with the beginning of the option text, as
+    /// long as only one option matches; otherwise the question is asked
+    /// again. Returns the index of the chosen option, or `None` if there is
+    /// no more input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::input::{Input, VecInput};
+    /// use texture::output::BufferOutput;
+    ///
+    /// let mut output = BufferOutput::new();
+    /// let mut input = VecInput::new(vec![
+    ///     "7".to_string(),
+    ///     "go".to_string(),
+    ///     "go r".to_string(),
+    /// ]);
+    ///
+    /// let choice = input.menu(&mut output, "Where to?", &["Go left", "Go right"]);
+    ///
+    /// assert_eq!(choice, Some(1));
+    /// assert_eq!(output.contents(),
+    ///            "Where to?\n1) Go left\n2) Go right\n\
+    ///             Please choose one of the options.\n\
+    ///             Please choose one of the options.\n");
+    /// ```
+    fn menu(&mut self, output: &mut Output, prompt: &str, options: &[&str])
+            -> Option<usize> {
+        output.write_line(prompt);
+
+        for (i, option) in options.iter().enumerate() {
+            output.write_line(&format!("{}) {}", i + 1, option));
+        }
+
+        loop {
+            output.flush();
+
+            let answer = match self.read_line("> ") {
+                Some(a) => { a },
+                None => { return None }
+            };
+
+            if let Some(choice) = choose(&answer, options) {
+                return Some(choice);
+            }
+
+            output.write_line("Please choose one of the options.");
+        }
+    }
+
+    /// Read lines until one is accepted by the validator
+    ///
+    /// The error is written after each rejected line. Returns the accepted
+    /// line, or `None` if there is no more input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::input::{Input, VecInput};
+    /// use texture::output::BufferOutput;
+    ///
+    /// let mut output = BufferOutput::new();
+    /// let mut input = VecInput::new(vec![
+    ///     "12".to_string(),
+    ///     "abcd".to_string(),
+    ///     "1234".to_string(),
+    ///     "5678".to_string(),
+    /// ]);
+    ///
+    /// let code = input.read_validated(
+    ///     &mut output, "Code: ", "The code has four digits.",
+    ///     &|line| line.len() == 4 && line.chars().all(|c| c.is_digit(10)));
+    ///
+    /// assert_eq!(code, Some("1234".to_string()));
+    /// assert_eq!(output.contents(),
+    ///            "The code has four digits.\nThe code has four digits.\n");
+    ///
+    /// // The remaining input is left untouched
+    /// assert_eq!(input.read_line("> "), Some("5678".to_string()));
+    /// ```
+    fn read_validated(&mut self, output: &mut Output, prompt: &str, error: &str,
+                      validate: &Fn(&str) -> bool)
+                      -> Option<String> {
+        loop {
+            output.flush();
+
+            let line = self.read_line(prompt)?;
+
+            if validate(&line) {
+                return Some(line);
+            }
+
+            output.write_line(error);
+        }
+    }
+
+    /// Read lines until one is a whole number, within the range if given
+    ///
+    /// The range includes both ends. An error is written after each
+    /// rejected line. Returns the number, or `None` if there is no more
+    /// input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::input::{Input, VecInput};
+    /// use texture::output::BufferOutput;
+    ///
+    /// let mut output = BufferOutput::new();
+    /// let mut input = VecInput::new(vec![
+    ///     "abc".to_string(),
+    ///     "12".to_string(),
+    ///     " 5 ".to_string(),
+    /// ]);
+    ///
+    /// let coins = input.read_int(&mut output, "How many? ", Some((1, 10)));
+    ///
+    /// assert_eq!(coins, Some(5));
+    /// assert_eq!(output.contents(),
+    ///            "Please enter a number.\n\
+    ///             Please enter a number from 1 to 10.\n");
+    /// ```
+    fn read_int(&mut self, output: &mut Output, prompt: &str,
+                range: Option<(i32, i32)>)
+                -> Option<i32> {
+        loop {
+            output.flush();
+
+            let line = self.read_line(prompt)?;
+
+            let number = match line.trim().parse::<i32>() {
+                Ok(n) => { n },
+                Err(_) => {
+                    output.write_line("Please enter a number.");
+                    continue;
+                }
+            };
+
+            match range {
+                Some((min, max)) if number < min || number > max => {
+                    output.write_line(&format!(
+                        "Please enter a number from {} to {}.", min, max));
+                },
+                _ => { return Some(number) }
+            }
+        }
+    }
+}
+
+/// Find the option chosen by an answer
+///
+/// The answer may be the number of the option (starting at 1) or the
+/// beginning of its text, ignoring case, as long as only one option
+/// matches. This is the rule used by `Input::menu()`.
+///
+/// # Examples
+///
+/// ```
+/// use texture::input::choose;
+///
+/// let options = ["Go left", "Go right", "Stay"];
+///
+/// assert_eq!(choose("2", &options), Some(1));
+/// assert_eq!(choose(" stay ", &options), Some(2));
+/// assert_eq!(choose("go", &options), None);
+/// assert_eq!(choose("4", &options), None);
+/// ```
+pub fn choose(answer: &str, options: &[&str]) -> Option<usize> {
+    let answer = answer.trim().to_lowercase();
+
+    if let Ok(number) = answer.parse::<usize>() {
+        if number >= 1 && number <= options.len() {
+            return Some(number - 1);
+        }
+    } else if !answer.is_empty() {
+        let matches: Vec<usize> = options.iter()
+            .enumerate()
+            .filter(|&(_, o)| o.to_lowercase().starts_with(&answer))
+            .map(|(i, _)| i)
+            .collect();
+
+        if matches.len() == 1 {
+            return Some(matches[0]);
+        }
+    }
+
+    None
+}
+
+/// Obtain the input used by default
+///
+/// Reads from the terminal using linenoise when the standard input is a
+/// terminal, and plain lines from the standard input otherwise (e.g. when
+/// input is piped into the game). A specific mode can be forced by
+/// installing `LinenoiseInput` or `ReaderInput::stdin()` instead.
+pub fn default_input() -> Box<Input> {
+    if io::stdin().is_terminal() {
+        Box::new(LinenoiseInput)
+    } else {
+        Box::new(ReaderInput::stdin())
+    }
+}
+
+/// Input read from the terminal using linenoise
+///
+/// Reads interrupted without a line (e.g. with Ctrl-C) are ignored and the
+/// prompt is shown again, so the player cannot end the game by accident;
+/// games end through their own commands instead (e.g. `QuitCommand`).
+pub struct LinenoiseInput;
+
+impl Input for LinenoiseInput {
+    fn read_line(&mut self, prompt: &str) -> Option<String> {
+        loop {
+            if let Some(line) = linenoise::input(prompt) {
+                return Some(line);
+            }
+        }
+    }
+}
+
+/// Input read line by line from any reader, without line editing
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use texture::input::{Input, ReaderInput};
+///
+/// let mut input = ReaderInput::new(Cursor::new("look\r\ntake lamp\n"));
+///
+/// assert_eq!(input.read_line("> "), Some("look".to_string()));
+/// assert_eq!(input.read_line("> "), Some("take lamp".to_string()));
+///
+/// // End of input
+/// assert_eq!(input.read_line("> "), None);
+/// assert_eq!(input.read_line("> "), None);
+/// ```
+pub struct ReaderInput<R: BufRead> {
+    reader: R,
+}
+
+impl <R: BufRead> ReaderInput<R> {
+    /// Read lines from the given reader
+    pub fn new(reader: R) -> ReaderInput<R> {
+        ReaderInput { reader: reader }
+    }
+}
+
+impl ReaderInput<BufReader<Stdin>> {
+    /// Read lines from the standard input
+    pub fn stdin() -> ReaderInput<BufReader<Stdin>> {
+        ReaderInput::new(BufReader::new(io::stdin()))
+    }
+}
+
+impl <R: BufRead> Input for ReaderInput<R> {
+    fn read_line(&mut self, _prompt: &str) -> Option<String> {
+        let mut line = String::new();
+
+        match self.reader.read_line(&mut line) {
+            Ok(0) | Err(_) => { None },
+            Ok(_) => {
+                let len = line.trim_end_matches(|c| c == '\n' || c == '\r').len();
+                line.truncate(len);
+
+                Some(line)
+            }
+        }
+    }
+
+    fn shows_input(&self) -> bool {
+        false
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+/// Input taken from a predefined list of lines
+///
+/// # Examples
+///
+/// ```
+/// use texture::input::{Input, VecInput};
+///
+/// let mut input = VecInput::new(vec!["look".to_string(), "north".to_string()]);
+///
+/// assert_eq!(input.read_line("> "), Some("look".to_string()));
+/// assert_eq!(input.read_line("> "), Some("north".to_string()));
+/// assert_eq!(input.read_line("> "), None);
+/// ```
+pub struct VecInput {
+    lines: VecDeque<String>,
+}
+
+impl VecInput {
+    /// Create an input that returns the given lines in order
+    pub fn new(lines: Vec<String>) -> VecInput {
+        VecInput { lines: lines.into_iter().collect() }
+    }
+
+    /// Add a line at the end of the input
+    pub fn push(&mut self, line: String) {
+        self.lines.push_back(line);
+    }
+}
+
+impl Input for VecInput {
+    fn read_line(&mut self, _prompt: &str) -> Option<String> {
+        self.lines.pop_front()
+    }
+}
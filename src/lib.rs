@@ -21,9 +21,32 @@
 // SOFTWARE.
 
 extern crate linenoise;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+#[cfg(feature = "toml")]
+extern crate toml;
+#[cfg(feature = "unicode")]
+extern crate unicode_segmentation;
+
+#[macro_use]
+mod macros;
 
 pub mod state;
 pub mod command;
 pub mod scenario;
 pub mod master;
 pub mod util;
+pub mod output;
+pub mod input;
+pub mod persist;
+pub mod cue;
+pub mod data;
+pub mod eval;
+pub mod random;
+pub mod conversation;
+pub mod render;
+pub mod clock;
+#[cfg(feature = "threaded")]
+pub mod threaded;
@@ -0,0 +1,148 @@
+// MIT License
+//
+// Copyright (c) 2016 Rafael Medina García <rafamedgar@gmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+/// Define a scenario from its description and the actions it understands
+///
+/// The grammar is:
+///
+/// ```text
+/// scenario! {
+///     [pub] struct Name;
+///     description: "Text written when the scenario is described";
+///     "verb" [| "other verb" ...] => |state, loader| { ... },
+///     ...
+/// }
+/// ```
+///
+/// This expands to a unit struct implementing `Scenario<S>` for any
+/// `S: BaseState`, as it would be written by hand:
+///
+/// - `load()` does nothing and returns `TICK`
+/// - `describe()` writes the description
+/// - `do_action()` runs the body of the first arm matching the input, with
+///   the state and loader bound to the given names. The body must evaluate
+///   to an action code. Inputs that match no arm return `UNKNOWN`.
+///
+/// Arms are patterns, so `_` may be used as the last arm to handle every
+/// other input.
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate texture;
+///
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::master::GameMaster;
+/// use texture::output::BufferOutput;
+/// use texture::state::{BaseState, BasicState};
+/// use texture::util::{LOAD, TICK};
+///
+/// scenario! {
+///     struct Hall;
+///     description: "A long hall. The garden is to the north.";
+///     "north" | "n" => |_state, loader| {
+///         loader.borrow_mut().load_by_name("garden");
+///         LOAD
+///     },
+///     "sing" => |state, loader| {
+///         state.borrow_mut().set_flag("sang".to_string(), true);
+///         loader.borrow_mut().write("Your voice echoes.");
+///         TICK
+///     },
+/// }
+///
+/// scenario! {
+///     struct Garden;
+///     description: "A walled garden.";
+/// }
+///
+/// fn main() {
+///     let buffer = BufferOutput::new();
+///     let state = Rc::new(RefCell::new(BasicState::new()));
+///
+///     let mut gm = GameMaster::new(state.clone(), Rc::new(RefCell::new(Hall)));
+///     gm.set_output(Box::new(buffer.clone()));
+///     gm.loader().borrow_mut()
+///         .register("garden".to_string(), Rc::new(RefCell::new(Garden)));
+///
+///     gm.begin();
+///     gm.step("sing");
+///     gm.step("n");
+///
+///     assert!(state.borrow().get_flag("sang".to_string()));
+///     assert_eq!(buffer.contents(), "\
+/// A long hall. The garden is to the north.
+/// Your voice echoes.
+///  \n\
+/// A walled garden.
+/// ");
+/// }
+/// ```
+#[macro_export]
+macro_rules! scenario {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident;
+        description: $description:expr;
+        $( $($verb:pat)|+ => |$state:ident, $loader:ident| $body:block ),* $(,)*
+    ) => {
+        $(#[$attr])*
+        $vis struct $name;
+
+        impl <S: $crate::state::BaseState> $crate::scenario::Scenario<S> for $name {
+            fn load(&self, _state: &::std::rc::Rc<::std::cell::RefCell<S>>,
+                    _loader: &::std::rc::Rc<::std::cell::RefCell<
+                        $crate::scenario::Loader<S>>>)
+                    -> i32 {
+                $crate::util::TICK
+            }
+
+            fn describe(&self, _state: &::std::rc::Rc<::std::cell::RefCell<S>>,
+                        loader: &::std::rc::Rc<::std::cell::RefCell<
+                            $crate::scenario::Loader<S>>>) {
+                loader.borrow_mut().write($description);
+            }
+
+            #[allow(unused_variables, unreachable_patterns)]
+            fn do_action(&self, command: &str,
+                         state: &::std::rc::Rc<::std::cell::RefCell<S>>,
+                         loader: &::std::rc::Rc<::std::cell::RefCell<
+                             $crate::scenario::Loader<S>>>)
+                         -> i32 {
+                match command {
+                    $(
+                        $($verb)|+ => {
+                            let $state = state;
+                            let $loader = loader;
+                            $body
+                        },
+                    )*
+                    _ => { $crate::util::UNKNOWN }
+                }
+            }
+        }
+    };
+}
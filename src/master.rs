@@ -20,16 +20,195 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use std::collections::HashMap;
-use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use linenoise;
 
-use command::GameCommand;
-use scenario::{Loader, Scenario};
-use util::TICK;
-use util::LOAD;
+use command::{GameCommand, LoadCommand, SaveCommand, SlotsCommand};
+use cue::CuePlayer;
+use input::Input;
+use output::{NullOutput, Output, OutputEvent, OutputFilter};
+use persist::{Persistable, SaveError};
+use scenario::{CommandRequest, DescriptionMode, Loader, MissingScenarioHandler,
+               Scenario, ScenarioFactory};
+use state::{BasicState, Phase};
+use util::{advances_turn, is_known_code, turn_cost, END, LOAD, NOTURN, QUIT,
+           RESPAWN, TICK, UNKNOWN};
+
+/// Function building the status line from the state
+pub type StatusFn<S> = Box<Fn(&Rc<RefCell<S>>) -> String>;
+
+/// Where an input was rejected
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scope {
+    /// A global command matched the input, but returned `UNKNOWN`
+    Global,
+    /// No global command matched and the current scenario returned
+    /// `UNKNOWN`
+    Scenario,
+}
+
+/// Function called when an input is not understood
+///
+/// Receives the loader, the input and where it was rejected.
+pub type UnknownCommandHandler<S> = Box<Fn(&mut Loader<S>, &str, Scope)>;
+
+/// Function called when an input is the same as the previous one (see
+/// `GameMaster::set_dedupe_repeats()`)
+///
+/// Receives the loader and the input.
+pub type RepeatHandler<S> = Box<Fn(&mut Loader<S>, &str)>;
+
+/// Function called when a scenario or command returns an unknown action
+/// code
+///
+/// Receives the loader and the code, and returns the code to use instead.
+pub type InvalidCodeHandler<S> = Box<Fn(&mut Loader<S>, i32) -> i32>;
+
+/// Function called when `LOAD` is returned without setting a scenario to
+/// load
+///
+/// Receives the loader and a message describing the mistake.
+pub type TransitionErrorHandler<S> = Box<Fn(&mut Loader<S>, &str)>;
+
+/// How an input would be dispatched by the game master
+///
+/// Obtained from `GameMaster::classify()`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Dispatch {
+    /// The built-in help (see `GameMaster::set_help_command()`)
+    Help,
+    /// The built-in parse command (see `GameMaster::set_parse_command()`)
+    Parse,
+    /// The built-in history (see `GameMaster::set_history_command()`)
+    History,
+    /// A global command, with its name and arguments
+    Command(String, Vec<String>),
+    /// A command of an active input mode, with the mode, the name of the
+    /// command and its arguments
+    ModeCommand(String, String, Vec<String>),
+    /// A global command that is currently disabled
+    Disabled(String),
+    /// The action of the current scenario, with the input it receives
+    ///
+    /// Prefixes of global commands (see
+    /// `GameMaster::set_prefix_matching()`) are also classified this way,
+    /// since they are only completed when the scenario does not understand
+    /// them.
+    Scenario(String),
+    /// The input asks to repeat the previous one, but there is none
+    NothingToRepeat,
+}
+
+impl fmt::Display for Dispatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Dispatch::Help => { write!(f, "built-in help") },
+            Dispatch::Parse => { write!(f, "built-in parse") },
+            Dispatch::History => { write!(f, "built-in history") },
+            Dispatch::Command(ref name, ref args) => {
+                write!(f, "global command '{}'", name)?;
+
+                if !args.is_empty() {
+                    write!(f, " with arguments: {}", args.join(" "))?;
+                }
+
+                Ok(())
+            },
+            Dispatch::ModeCommand(ref mode, ref name, ref args) => {
+                write!(f, "command '{}' of mode '{}'", name, mode)?;
+
+                if !args.is_empty() {
+                    write!(f, " with arguments: {}", args.join(" "))?;
+                }
+
+                Ok(())
+            },
+            Dispatch::Disabled(ref name) => {
+                write!(f, "disabled global command '{}'", name)
+            },
+            Dispatch::Scenario(ref input) => {
+                write!(f, "action '{}' of the current scenario", input)
+            },
+            Dispatch::NothingToRepeat => { write!(f, "nothing to repeat") }
+        }
+    }
+}
+
+/// Find the command in the table matching an input, with its arguments
+///
+/// The whole input is tried first, then the first word for commands with
+/// arguments.
+fn lookup<'a, S>(table: &HashMap<String, Box<GameCommand<S>>>, command: &'a str)
+                 -> Option<(&'a str, Vec<&'a str>)> {
+    if table.contains_key(command) {
+        return Some((command, Vec::new()));
+    }
+
+    let mut words = command.split_whitespace();
+    let first = words.next().unwrap_or("");
+
+    match table.get(first) {
+        Some(c) if c.takes_args() => { Some((first, words.collect())) },
+        _ => { None }
+    }
+}
+
+/// Outcome of processing one input
+///
+/// Obtained from `GameMaster::step_result()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepResult {
+    /// Code obtained from the command or scenario
+    pub action: i32,
+    /// Name of the scenario entered, if the input changed the current
+    /// scenario and the new one is registered
+    pub transitioned_to: Option<String>,
+}
+
+/// Replace the first word of an input with the full name of a command
+fn complete_prefix(command: &str, name: &str) -> String {
+    let mut parts = command.trim().splitn(2, ' ');
+    parts.next();
+
+    match parts.next() {
+        Some(rest) => { format!("{} {}", name, rest) },
+        None => { name.to_string() }
+    }
+}
+
+/// Result of looking up and executing a global command
+enum CommandResult {
+    /// No global command matches the input
+    NotFound,
+    /// A global command was executed, returning the given code
+    Handled(i32),
+}
+
+/// Function called once when the game starts
+pub type StartHook<S> = Box<Fn(&Rc<RefCell<S>>, &Rc<RefCell<Loader<S>>>)>;
+
+/// Function called once when the game ends
+///
+/// Receives the final state, the loader and the number of turns played.
+pub type EndHook<S> = Box<Fn(&Rc<RefCell<S>>, &Rc<RefCell<Loader<S>>>, u64)>;
+
+/// Function called when the game reaches an ending
+///
+/// Receives the state and the name of the ending, if it was given one.
+pub type GameOverHook<S> = Box<Fn(&Rc<RefCell<S>>, Option<&str>)>;
+
+/// Condition for a global command to be available
+pub type CommandGuard<S> = Box<Fn(&Rc<RefCell<S>>) -> bool>;
+
+/// Rewrite of the input typed by the player, before it is dispatched
+pub type InputTransform = Box<Fn(String) -> String>;
 
 pub struct GameMaster<S> {
     // Current scenario
@@ -40,6 +219,96 @@ pub struct GameMaster<S> {
     state: Rc<RefCell<S>>,
     // Global game commands
     commands: HashMap<String, Box<GameCommand<S>>>,
+    // Active input modes, innermost last
+    modes: Vec<String>,
+    // Commands only available in an input mode, by mode
+    mode_commands: HashMap<String, HashMap<String, Box<GameCommand<S>>>>,
+    // Number of times each global command was executed
+    usage: HashMap<String, u64>,
+    // Last non-empty input, repeated by the "again" commands
+    last_input: Option<String>,
+    // Inputs that repeat the last one
+    again_commands: Vec<String>,
+    // Whether inputs identical to the previous one are not executed
+    dedupe_repeats: bool,
+    // Inputs that can always be repeated
+    repeatable: HashSet<String>,
+    // Called when an input is not executed for being repeated
+    repeat_handler: Option<RepeatHandler<S>>,
+    // Turns played
+    turns: u64,
+    // Turns spent in the current scenario
+    dwell: u64,
+    // Short forms of the first word of the input
+    abbreviations: HashMap<String, String>,
+    // Rewrites of the input, applied in order before anything else
+    input_transforms: Vec<InputTransform>,
+    // In-memory snapshots of the state, current scenario, turns and dwell
+    checkpoints: VecDeque<(S, Rc<RefCell<Scenario<S>>>, u64, u64)>,
+    // Maximum number of snapshots kept
+    checkpoint_limit: usize,
+    // Scenario the game started in
+    start: Rc<RefCell<Scenario<S>>>,
+    // Scenario where the player respawns
+    respawn_point: Rc<RefCell<Scenario<S>>>,
+    // State when the respawn point was entered
+    respawn_state: Option<S>,
+    // Copies the state when entering a respawn point, if enabled
+    respawn_snapshot: Option<fn(&S) -> S>,
+    // Registered commands that cannot be executed for now
+    disabled: HashSet<String>,
+    // Message shown when trying to execute a disabled command
+    disabled_message: Option<String>,
+    // Conditions for commands to be available
+    guards: HashMap<String, CommandGuard<S>>,
+    // Commands left out of listings
+    hidden: HashSet<String>,
+    // Builds the status line shown before each prompt
+    status_fn: Option<StatusFn<S>>,
+    // Text shown before the game starts
+    intro: Vec<String>,
+    // Prompt shown to wait for the player after the intro
+    intro_pause: Option<String>,
+    // Called when the game starts
+    start_hook: Option<StartHook<S>>,
+    // Called when the game ends
+    end_hook: Option<EndHook<S>>,
+    // Called when an ending is reached
+    game_over_hook: Option<GameOverHook<S>>,
+    // Called after each step to show the changes to the state
+    diff_hook: Option<Box<Fn(&Rc<RefCell<S>>, &Rc<RefCell<Loader<S>>>)>>,
+    // Whether changes to the state since the last save are tracked
+    unsaved_tracking: bool,
+    // Called when an input is not understood
+    unknown_handler: Option<UnknownCommandHandler<S>>,
+    // Input showing the built-in help
+    help_command: Option<String>,
+    // Input showing how another input would be dispatched
+    parse_command: Option<String>,
+    // Input showing the recent inputs
+    history_command: Option<String>,
+    // Recent non-empty inputs, oldest first
+    history: VecDeque<String>,
+    // Maximum number of inputs kept in the history
+    history_size: usize,
+    // Whether inputs read by the game loop are written, if forced
+    echo_input: Option<bool>,
+    // Whether global commands can be typed partially
+    prefix_matching: bool,
+    // Whether the input of a global command also reaches the scenario
+    command_fallthrough: bool,
+    // Maximum number of queued inputs processed in a row
+    queued_input_limit: usize,
+    // Called when an unknown action code is returned
+    invalid_code_handler: Option<InvalidCodeHandler<S>>,
+    // Called when there is no scenario to load
+    transition_error_handler: Option<TransitionErrorHandler<S>>,
+    // Whether calls to scenarios and commands are timed
+    profiling: bool,
+    // Total time spent in each scenario method and command
+    timings: BTreeMap<String, Duration>,
+    // Part of the game being run, shared with state observers
+    phase: Rc<Cell<Phase>>,
 }
 
 impl <S> GameMaster <S> {
@@ -105,15 +374,72 @@ impl <S> GameMaster <S> {
                -> GameMaster<S> {
         let mut loader = Loader::new();
         loader.set_scenario(start.clone());
+        loader.promote();
 
         GameMaster {
-            current: start,
+            current: start.clone(),
             loader: Rc::new(RefCell::new(loader)),
             state: state,
             commands: HashMap::new(),
+            modes: Vec::new(),
+            mode_commands: HashMap::new(),
+            usage: HashMap::new(),
+            last_input: None,
+            again_commands: vec!["again".to_string(), "g".to_string()],
+            dedupe_repeats: false,
+            repeatable: ["wait", "z"].iter().map(|c| c.to_string()).collect(),
+            repeat_handler: None,
+            turns: 0,
+            dwell: 0,
+            abbreviations: default_abbreviations(),
+            input_transforms: Vec::new(),
+            checkpoints: VecDeque::new(),
+            checkpoint_limit: 10,
+            respawn_point: start.clone(),
+            start: start,
+            respawn_state: None,
+            respawn_snapshot: None,
+            disabled: HashSet::new(),
+            disabled_message: Some("You can't do that right now.".to_string()),
+            guards: HashMap::new(),
+            hidden: HashSet::new(),
+            status_fn: None,
+            intro: Vec::new(),
+            intro_pause: None,
+            start_hook: None,
+            end_hook: None,
+            game_over_hook: None,
+            diff_hook: None,
+            unsaved_tracking: false,
+            unknown_handler: None,
+            help_command: None,
+            parse_command: None,
+            history_command: None,
+            history: VecDeque::new(),
+            history_size: 20,
+            echo_input: None,
+            prefix_matching: false,
+            command_fallthrough: false,
+            queued_input_limit: 20,
+            invalid_code_handler: None,
+            transition_error_handler: None,
+            profiling: false,
+            timings: BTreeMap::new(),
+            phase: Rc::new(Cell::new(Phase::Idle)),
         }
     }
 
+    /// Obtain the cell holding the part of the game being run
+    ///
+    /// The master sets the phase while loading scenarios, performing their
+    /// actions and executing global commands, and sets it back to
+    /// `Phase::Idle` afterwards. Share it with the state (see
+    /// `BasicState::set_phase_cell()`) for observers to know why the state
+    /// changed.
+    pub fn phase_cell(&self) -> Rc<Cell<Phase>> {
+        self.phase.clone()
+    }
+
     /// Insert a new global command in the map
     ///
     /// # Examples
@@ -193,79 +519,4265 @@ impl <S> GameMaster <S> {
     /// gm.add_command("test".to_string(), Box::new(command));
     /// ```
     pub fn add_command(&mut self, name: String, command: Box<GameCommand<S>>) {
+        self.guards.remove(&name);
+        self.hidden.remove(&name);
         self.commands.insert(name, command);
     }
 
-    /// Start a new game by calling the main loop
-    pub fn start_game(&mut self) {
-        self.main_loop();
+    /// Insert several global commands, by name
+    ///
+    /// Meant for the commands read from data files (see
+    /// `data::parse_commands_from_json()`). Works like `add_command()` for
+    /// each of them.
+    pub fn add_commands<C, I>(&mut self, commands: I)
+        where C: GameCommand<S> + 'static,
+              I: IntoIterator<Item = (String, C)> {
+        for (name, command) in commands {
+            self.add_command(name, Box::new(command));
+        }
     }
 
-    /// Load scenario from the loader and call `load()` method
-    fn load_scenario(&mut self) -> i32 {
-        self.current = self.loader.borrow().get_scenario();
+    /// Insert a new global command that is not listed anywhere
+    ///
+    /// The command is executed as usual, but is left out of
+    /// `command_names()` and the built-in help, which behaves as if there
+    /// was no such command. Useful for secrets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::{GameCommand, WaitCommand};
+    /// use texture::data::DataScenario;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::Loader;
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::NOTURN;
+    ///
+    /// struct Xyzzy;
+    ///
+    /// impl <S> GameCommand <S> for Xyzzy {
+    ///     fn execute(&self, state: &Rc<RefCell<S>>,
+    ///                loader: &Rc<RefCell<Loader<S>>>)
+    ///                -> i32 {
+    ///         loader.borrow_mut().write("A hollow voice says \"Fool.\"");
+    ///         NOTURN
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    /// let house = DataScenario::new("A white house.".to_string());
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(house)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.set_help_command(Some("help".to_string()));
+    /// gm.add_command("wait".to_string(), Box::new(WaitCommand));
+    /// gm.add_hidden_command("xyzzy".to_string(), Box::new(Xyzzy));
+    ///
+    /// gm.step("xyzzy");
+    /// gm.step("help");
+    /// gm.step("help xyzzy");
+    ///
+    /// assert_eq!(buffer.contents(), "\
+    /// A hollow voice says \"Fool.\"
+    /// Commands: wait
+    /// There is no command named 'xyzzy'.
+    /// ");
+    /// assert_eq!(gm.command_names(), vec!["wait".to_string()]);
+    /// ```
+    pub fn add_hidden_command(&mut self, name: String,
+                              command: Box<GameCommand<S>>) {
+        self.add_command(name.clone(), command);
+        self.hidden.insert(name);
+    }
+
+    /// Insert a new global command only available when a condition holds
+    ///
+    /// While the guard returns `false`, the command behaves as if it was not
+    /// registered: the input reaches the current scenario and the command
+    /// is not listed in `command_names()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::GameCommand;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         loader.borrow_mut().write("You can't do that.");
+    ///
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// struct Unlock;
+    ///
+    /// impl <S> GameCommand <S> for Unlock {
+    ///     fn execute(&self, state: &Rc<RefCell<S>>,
+    ///                loader: &Rc<RefCell<Loader<S>>>)
+    ///                -> i32 {
+    ///         loader.borrow_mut().write("The door is unlocked.");
+    ///
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state.clone(), Rc::new(RefCell::new(Room)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.add_command_if("unlock".to_string(), Box::new(Unlock), Box::new(|s| {
+    ///     s.borrow().get_flag("has_key".to_string())
+    /// }));
+    ///
+    /// gm.step("unlock");
+    /// assert_eq!(buffer.contents(), "You can't do that.\n");
+    /// assert!(gm.command_names().is_empty());
+    ///
+    /// state.borrow_mut().set_flag("has_key".to_string(), true);
+    /// buffer.clear();
+    ///
+    /// gm.step("unlock");
+    /// assert_eq!(buffer.contents(), "The door is unlocked.\n");
+    /// assert_eq!(gm.command_names(), vec!["unlock".to_string()]);
+    /// ```
+    pub fn add_command_if(&mut self, name: String, command: Box<GameCommand<S>>,
+                          guard: CommandGuard<S>) {
+        self.hidden.remove(&name);
+        self.commands.insert(name.clone(), command);
+        self.guards.insert(name, guard);
+    }
 
-        println!(" ");
+    /// Names of the global commands currently available, sorted
+    ///
+    /// Hidden commands (see `add_hidden_command()`) are not included.
+    pub fn command_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.commands.keys()
+            .filter(|name| self.is_available(name))
+            .filter(|name| !self.hidden.contains(*name))
+            .cloned()
+            .collect();
+
+        names.sort();
 
-        self.current.borrow().load(&self.state, &self.loader)
+        names
     }
 
-    /// Execute a global game command (if any)
-    fn exec_game_command(&mut self, command: &str) -> i32 {
-        let game_command = match self.commands.get(command) {
-            Some(f) => { f },
-            None => return TICK
+    /// Enable the built-in help under the given name
+    ///
+    /// Typing the name alone lists the available global commands, while
+    /// following it with a command name shows the usage of that command
+    /// (see `GameCommand::usage()`). The help takes precedence over global
+    /// commands and scenarios, and does not take a turn. `None` (the
+    /// default) disables it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::GameCommand;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::{NOTURN, TICK};
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// struct Save;
+    ///
+    /// impl <S> GameCommand <S> for Save {
+    ///     fn execute(&self, state: &Rc<RefCell<S>>,
+    ///                loader: &Rc<RefCell<Loader<S>>>)
+    ///                -> i32 {
+    ///         NOTURN
+    ///     }
+    ///
+    ///     fn usage(&self) -> Option<&str> {
+    ///         Some("save <slot>")
+    ///     }
+    /// }
+    ///
+    /// struct Inventory;
+    ///
+    /// impl <S> GameCommand <S> for Inventory {
+    ///     fn execute(&self, state: &Rc<RefCell<S>>,
+    ///                loader: &Rc<RefCell<Loader<S>>>)
+    ///                -> i32 {
+    ///         NOTURN
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Room)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.add_command("save".to_string(), Box::new(Save));
+    /// gm.add_command("inventory".to_string(), Box::new(Inventory));
+    /// gm.set_help_command(Some("help".to_string()));
+    ///
+    /// assert_eq!(gm.step("help"), NOTURN);
+    /// assert_eq!(gm.step("help save"), NOTURN);
+    /// gm.step("help inventory");
+    /// gm.step("help fly");
+    ///
+    /// assert_eq!(buffer.contents(), "\
+    /// Commands: inventory, save
+    /// Usage: save <slot>
+    /// Usage: inventory
+    /// There is no command named 'fly'.
+    /// ");
+    /// assert_eq!(gm.turns(), 0);
+    /// ```
+    pub fn set_help_command(&mut self, name: Option<String>) {
+        self.help_command = name;
+    }
+
+    /// Show the built-in help if the input asks for it
+    ///
+    /// Returns `false` if the input is not a help request.
+    fn exec_help(&mut self, command: &str) -> bool {
+        let topic = {
+            let name = match self.help_command {
+                Some(ref name) => { name },
+                None => { return false }
+            };
+
+            let mut words = command.split_whitespace();
+
+            if words.next() != Some(name.as_str()) {
+                return false;
+            }
+
+            words.next().map(|w| w.to_string())
         };
 
-        game_command.execute(&self.state, &self.loader)
+        let message = match topic {
+            None => {
+                format!("Commands: {}", self.command_names().join(", "))
+            },
+            Some(topic) => {
+                let usage = match self.commands.get(&topic) {
+                    Some(c) if self.is_available(&topic)
+                        && !self.hidden.contains(&topic) => { c.usage() },
+                    _ => {
+                        let message = format!(
+                            "There is no command named '{}'.", topic);
+                        self.loader.borrow_mut().write(&message);
+                        return true;
+                    }
+                };
+
+                format!("Usage: {}", usage.unwrap_or(&topic))
+            }
+        };
+
+        self.loader.borrow_mut().write(&message);
+
+        true
     }
 
-    /// Execute the action of the current scenario
-    fn exec_current_scenario(&mut self, command: &str) -> i32 {
-        let result = self.current.borrow().do_action(
-            &command.trim(),
-            &self.state,
-            &self.loader
-        );
+    /// Enable a built-in command reporting how an input would be
+    /// dispatched, without executing it
+    ///
+    /// With the name `parse`, `parse x lamp` writes what `x lamp` would do
+    /// (see `classify()`). Does not take a turn. Disabled by default.
+    pub fn set_parse_command(&mut self, name: Option<String>) {
+        self.parse_command = name;
+    }
 
-        result
+    /// Show how an input would be dispatched if the input asks for it
+    ///
+    /// Returns `false` if the input is not a parse request.
+    fn exec_parse(&mut self, command: &str) -> bool {
+        let rest = {
+            let name = match self.parse_command {
+                Some(ref name) => { name },
+                None => { return false }
+            };
+
+            let mut parts = command.splitn(2, ' ');
+
+            if parts.next() != Some(name.as_str()) {
+                return false;
+            }
+
+            parts.next().unwrap_or("").to_string()
+        };
+
+        let message = self.classify(&rest).to_string();
+        self.loader.borrow_mut().write(&message);
+
+        true
     }
 
-    /// Main game loop
-    fn main_loop(&mut self) {
-        // Setup linenoise
-        linenoise::set_multiline(0);
+    /// Change the number of recent inputs kept (20 by default)
+    ///
+    /// The history holds the non-empty inputs as typed by the player, oldest
+    /// first, whatever the source of the input. It is unrelated to the
+    /// line editing history of the terminal. A size of 0 disables it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::data::DataScenario;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::state::{BaseState, BasicState};
+    ///
+    /// let cave = DataScenario::new("A damp cave.".to_string());
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(cave)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.set_history_size(3);
+    /// gm.set_history_command(Some("history".to_string()));
+    ///
+    /// for input in &["look", "north", "", "take torch", "light torch"] {
+    ///     gm.step(input);
+    /// }
+    ///
+    /// assert_eq!(gm.recent_inputs(), vec!["north", "take torch", "light torch"]);
+    ///
+    /// buffer.clear();
+    /// gm.step("history");
+    ///
+    /// assert_eq!(buffer.contents(), "\
+    /// 1. take torch
+    /// 2. light torch
+    /// 3. history
+    /// ");
+    /// ```
+    pub fn set_history_size(&mut self, size: usize) {
+        self.history_size = size;
 
-        // Infinite game loop
-        let mut input = String::new();
-        let mut command;
+        while self.history.len() > size {
+            self.history.pop_front();
+        }
+    }
 
-        // Load starting scenario
-        self.current.borrow().load(&self.state, &self.loader);
+    /// Obtain the recent inputs, oldest first
+    pub fn recent_inputs(&self) -> Vec<String> {
+        self.history.iter().cloned().collect()
+    }
 
-        loop {
-            // Get input
-            input = match linenoise::input("\n> ") {
-                Some(i) => { i },
-                None => { continue }
-            };
+    /// Enable a built-in command listing the recent inputs
+    ///
+    /// The inputs are numbered, oldest first, and include the history
+    /// request itself. Does not take a turn. Disabled by default.
+    ///
+    /// The command takes no arguments, so only an input consisting of its
+    /// name alone is recognized; anything else goes on to global commands
+    /// and the current scenario.
+    pub fn set_history_command(&mut self, name: Option<String>) {
+        self.history_command = name;
+    }
+
+    /// Remember an input in the history, forgetting the oldest if full
+    fn record_input(&mut self, input: &str) {
+        if input.is_empty() || self.history_size == 0 {
+            return;
+        }
 
-            command = input.clone();
+        if self.history.len() == self.history_size {
+            self.history.pop_front();
+        }
 
-            println!(" ");
+        self.history.push_back(input.to_string());
+    }
 
-            // Try to execute global game commands
-            self.exec_game_command(&command.trim());
-            match self.exec_game_command(&command.trim()) {
-                LOAD => { self.load_scenario(); continue },
-                _ => {}
-            };
+    /// Show the recent inputs if the input asks for them
+    ///
+    /// Returns `false` if the input is not a history request.
+    fn exec_history(&mut self, command: &str) -> bool {
+        match self.history_command {
+            Some(ref name) if name == command => {},
+            _ => { return false }
+        }
 
-            // No global command found, execute scenario
-            match self.exec_current_scenario(&command.trim()) {
-                LOAD => { self.load_scenario(); },
-                _ => {}
-            };
+        for (i, input) in self.history.iter().enumerate() {
+            let line = format!("{}. {}", i + 1, input);
+            self.loader.borrow_mut().write(&line);
         }
+
+        true
     }
+
+    /// Find out how an input would be dispatched, without executing anything
+    ///
+    /// Follows the same rules as `step()`: the input is transformed (see
+    /// `add_input_transform()`), abbreviations are expanded,
+    /// "again" inputs are replaced by the previous input, and global
+    /// commands are tried before the current scenario.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::ExamineCommand;
+    /// use texture::data::DataScenario;
+    /// use texture::master::{Dispatch, GameMaster};
+    /// use texture::output::BufferOutput;
+    /// use texture::state::{BaseState, BasicState};
+    ///
+    /// let study = DataScenario::new("A quiet study.".to_string());
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(study)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.add_command("examine".to_string(), Box::new(ExamineCommand));
+    /// gm.add_abbreviation("x".to_string(), "examine".to_string());
+    /// gm.set_parse_command(Some("parse".to_string()));
+    /// gm.set_history_command(Some("history".to_string()));
+    ///
+    /// assert_eq!(gm.classify("history"), Dispatch::History);
+    /// assert_eq!(gm.classify("history lesson"),
+    ///            Dispatch::Scenario("history lesson".to_string()));
+    /// assert_eq!(gm.classify("examine"),
+    ///            Dispatch::Command("examine".to_string(), vec![]));
+    /// assert_eq!(gm.classify("x lamp"),
+    ///            Dispatch::Command("examine".to_string(),
+    ///                              vec!["lamp".to_string()]));
+    /// assert_eq!(gm.classify("xyzzy"), Dispatch::Scenario("xyzzy".to_string()));
+    ///
+    /// gm.step("parse x lamp");
+    /// gm.step("parse xyzzy");
+    ///
+    /// assert_eq!(buffer.contents(), "\
+    /// global command 'examine' with arguments: lamp
+    /// action 'xyzzy' of the current scenario
+    /// ");
+    /// assert_eq!(gm.turns(), 0);
+    /// ```
+    pub fn classify(&self, input: &str) -> Dispatch {
+        let input = self.transform_input(input.trim());
+        let mut command = self.expand_abbreviation(input.trim());
+
+        if self.again_commands.contains(&command) {
+            command = match self.last_input {
+                Some(ref last) => { last.clone() },
+                None => { return Dispatch::NothingToRepeat }
+            };
+        }
+
+        let first = command.split_whitespace().next().unwrap_or("");
+
+        if self.help_command.as_ref().map_or(false, |name| name == first) {
+            return Dispatch::Help;
+        }
+
+        if self.parse_command.as_ref().map_or(false, |name| name == first) {
+            return Dispatch::Parse;
+        }
+
+        if self.history_command.as_ref().map_or(false, |name| *name == command) {
+            return Dispatch::History;
+        }
+
+        if let Some((mode, name, args)) = self.find_mode_command(&command) {
+            return Dispatch::ModeCommand(
+                mode.to_string(),
+                name.to_string(),
+                args.iter().map(|a| a.to_string()).collect());
+        }
+
+        match self.find_command(&command) {
+            Some((name, _)) if !self.is_available(name) => {
+                Dispatch::Scenario(command.clone())
+            },
+            Some((name, _)) if self.disabled.contains(name) => {
+                Dispatch::Disabled(name.to_string())
+            },
+            Some((name, args)) => {
+                Dispatch::Command(name.to_string(),
+                                  args.iter().map(|a| a.to_string()).collect())
+            },
+            None => { Dispatch::Scenario(command.clone()) }
+        }
+    }
+
+    /// Allow typing only the beginning of the name of global commands
+    ///
+    /// When the first word of the input matches no command but is the
+    /// beginning of the name of exactly one, that command is executed (e.g.
+    /// `inv` for `inventory`). When it is the beginning of several, they are
+    /// listed and nothing is executed. Hidden commands are never matched.
+    /// Disabled by default.
+    ///
+    /// The current scenario always receives the input first, so its own
+    /// verbs and exits are never taken over by a command they are the
+    /// beginning of; prefixes are only completed when it returns `UNKNOWN`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::{GameCommand, WaitCommand};
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::{NOTURN, TICK, UNKNOWN};
+    ///
+    /// struct Shop;
+    ///
+    /// impl <S> Scenario <S> for Shop {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         if command == "in" {
+    ///             loader.borrow_mut().write("The back room is locked.");
+    ///             return NOTURN;
+    ///         }
+    ///
+    ///         UNKNOWN
+    ///     }
+    /// }
+    ///
+    /// struct Inventory;
+    ///
+    /// impl <S> GameCommand <S> for Inventory {
+    ///     fn execute(&self, state: &Rc<RefCell<S>>,
+    ///                loader: &Rc<RefCell<Loader<S>>>)
+    ///                -> i32 {
+    ///         loader.borrow_mut().write("You are empty-handed.");
+    ///         NOTURN
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Shop)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.add_command("inventory".to_string(), Box::new(Inventory));
+    /// gm.add_command("wait".to_string(), Box::new(WaitCommand));
+    /// gm.add_command("wave".to_string(), Box::new(WaitCommand));
+    /// gm.set_prefix_matching(true);
+    ///
+    /// gm.step("inv");
+    /// assert_eq!(gm.step("wa"), NOTURN);
+    ///
+    /// // Understood by the scenario, so not completed to `inventory`
+    /// gm.step("in");
+    ///
+    /// assert_eq!(buffer.contents(), "\
+    /// You are empty-handed.
+    /// Did you mean: wait, wave?
+    /// The back room is locked.
+    /// ");
+    /// assert_eq!(gm.turns(), 0);
+    /// ```
+    pub fn set_prefix_matching(&mut self, enabled: bool) {
+        self.prefix_matching = enabled;
+    }
+
+    /// Names of the global commands starting with the first word of an
+    /// input that matches no command, sorted
+    fn prefix_matches(&self, command: &str) -> Vec<String> {
+        if !self.prefix_matching || self.find_command(command).is_some() {
+            return Vec::new();
+        }
+
+        let first = match command.split_whitespace().next() {
+            Some(first) => { first },
+            None => { return Vec::new() }
+        };
+
+        self.command_names()
+            .into_iter()
+            .filter(|name| name.starts_with(first))
+            .collect()
+    }
+
+    /// Find the global command matching an input, with its arguments
+    ///
+    /// The whole input is tried first, then the first word for commands
+    /// with arguments.
+    fn find_command<'a>(&self, command: &'a str)
+                        -> Option<(&'a str, Vec<&'a str>)> {
+        lookup(&self.commands, command)
+    }
+
+    /// Find the command of an active input mode matching an input, with the
+    /// mode and its arguments
+    ///
+    /// The innermost mode is tried first.
+    fn find_mode_command<'a>(&self, command: &'a str)
+                             -> Option<(&str, &'a str, Vec<&'a str>)> {
+        for mode in self.modes.iter().rev() {
+            let table = match self.mode_commands.get(mode) {
+                Some(t) => { t },
+                None => { continue }
+            };
+
+            if let Some((name, args)) = lookup(table, command) {
+                return Some((mode, name, args));
+            }
+        }
+
+        None
+    }
+
+    /// Enter an input mode
+    ///
+    /// While a mode is active, its commands (see `add_mode_command()`) are
+    /// tried before the global commands. Modes nest: the commands of the
+    /// innermost mode are tried first, then those of the outer ones. The
+    /// prompt shows the innermost mode.
+    ///
+    /// Scenarios and commands can enter and leave modes through the loader
+    /// (see `Loader::queue_push_mode()`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::GameCommand;
+    /// use texture::data::DataScenario;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::Loader;
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::{NOTURN, TICK};
+    ///
+    /// struct Talk;
+    ///
+    /// impl <S> GameCommand <S> for Talk {
+    ///     fn execute(&self, state: &Rc<RefCell<S>>,
+    ///                loader: &Rc<RefCell<Loader<S>>>)
+    ///                -> i32 {
+    ///         loader.borrow_mut().write("The wizard looks at you.");
+    ///         loader.borrow_mut().queue_push_mode("talk".to_string());
+    ///         NOTURN
+    ///     }
+    /// }
+    ///
+    /// struct Say;
+    ///
+    /// impl <S> GameCommand <S> for Say {
+    ///     fn execute(&self, state: &Rc<RefCell<S>>,
+    ///                loader: &Rc<RefCell<Loader<S>>>)
+    ///                -> i32 {
+    ///         self.execute_args(&[], state, loader)
+    ///     }
+    ///
+    ///     fn execute_args(&self, args: &[&str], state: &Rc<RefCell<S>>,
+    ///                     loader: &Rc<RefCell<Loader<S>>>)
+    ///                     -> i32 {
+    ///         let reply = format!("The wizard ponders '{}'.", args.join(" "));
+    ///         loader.borrow_mut().write(&reply);
+    ///         TICK
+    ///     }
+    ///
+    ///     fn takes_args(&self) -> bool {
+    ///         true
+    ///     }
+    /// }
+    ///
+    /// struct Bye;
+    ///
+    /// impl <S> GameCommand <S> for Bye {
+    ///     fn execute(&self, state: &Rc<RefCell<S>>,
+    ///                loader: &Rc<RefCell<Loader<S>>>)
+    ///                -> i32 {
+    ///         loader.borrow_mut().queue_pop_mode();
+    ///         NOTURN
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    /// let tower = DataScenario::new("A tall tower.".to_string());
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(tower)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.add_command("talk".to_string(), Box::new(Talk));
+    /// gm.add_mode_command("talk", "say".to_string(), Box::new(Say));
+    /// gm.add_mode_command("talk", "bye".to_string(), Box::new(Bye));
+    ///
+    /// gm.step("say hello");
+    /// gm.step("talk");
+    /// assert_eq!(gm.active_mode(), Some("talk"));
+    ///
+    /// gm.step("say hello");
+    /// gm.step("bye");
+    /// assert_eq!(gm.active_mode(), None);
+    ///
+    /// gm.step("say hello");
+    ///
+    /// assert_eq!(buffer.contents(), "\
+    /// You can't do that here.
+    /// The wizard looks at you.
+    /// The wizard ponders 'hello'.
+    /// You can't do that here.
+    /// ");
+    /// ```
+    pub fn push_mode(&mut self, name: &str) {
+        self.modes.push(name.to_string());
+    }
+
+    /// Leave the innermost input mode, returning its name
+    pub fn pop_mode(&mut self) -> Option<String> {
+        self.modes.pop()
+    }
+
+    /// Obtain the innermost input mode (if any)
+    pub fn active_mode(&self) -> Option<&str> {
+        self.modes.last().map(|mode| mode.as_str())
+    }
+
+    /// Add a command only available while the given input mode is active
+    ///
+    /// Like global commands, mode commands can be disabled, are counted in
+    /// `command_usage()` and are timed while profiling.
+    pub fn add_mode_command(&mut self, mode: &str, name: String,
+                            command: Box<GameCommand<S>>) {
+        self.mode_commands.entry(mode.to_string())
+            .or_insert_with(HashMap::new)
+            .insert(name, command);
+    }
+
+    /// Check the number of arguments given to a command, writing its usage
+    /// if wrong
+    fn check_arity(&self, name: &str, command: &GameCommand<S>, args: &[&str])
+                   -> bool {
+        let (min, max) = command.arity();
+
+        if args.len() < min || max.map_or(false, |max| args.len() > max) {
+            let message = format!("Usage: {}", command.usage().unwrap_or(name));
+            self.loader.borrow_mut().write(&message);
+
+            return false;
+        }
+
+        true
+    }
+
+    /// Check whether the guard of a command (if any) allows executing it
+    fn is_available(&self, name: &str) -> bool {
+        match self.guards.get(name) {
+            Some(guard) => { guard(&self.state) },
+            None => { true }
+        }
+    }
+
+    /// Temporarily prevent a command from being executed
+    ///
+    /// The command stays registered, but trying to execute it only shows
+    /// the disabled message (if any) without taking a turn. Commands of
+    /// input modes are disabled by name too.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::GameCommand;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// struct Jump;
+    ///
+    /// impl <S> GameCommand <S> for Jump {
+    ///     fn execute(&self, state: &Rc<RefCell<S>>,
+    ///                loader: &Rc<RefCell<Loader<S>>>)
+    ///                -> i32 {
+    ///         loader.borrow_mut().write("You jump");
+    ///
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Room)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.add_command("jump".to_string(), Box::new(Jump));
+    ///
+    /// gm.disable_command("jump");
+    /// gm.step("jump");
+    /// assert_eq!(buffer.contents(), "You can't do that right now.\n");
+    ///
+    /// buffer.clear();
+    /// gm.enable_command("jump");
+    /// gm.step("jump");
+    /// assert_eq!(buffer.contents(), "You jump\n");
+    /// ```
+    pub fn disable_command(&mut self, name: &str) {
+        self.disabled.insert(name.to_string());
+    }
+
+    /// Allow a previously disabled command to be executed again
+    pub fn enable_command(&mut self, name: &str) {
+        self.disabled.remove(name);
+    }
+
+    /// Set the message shown when trying to execute a disabled command
+    ///
+    /// `None` disables the message.
+    pub fn set_disabled_message(&mut self, message: Option<String>) {
+        self.disabled_message = message;
+    }
+
+    /// Number of times each command has been executed
+    ///
+    /// Commands of input modes (see `add_mode_command()`) are counted by
+    /// name along with the global ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::GameCommand;
+    /// use texture::master::GameMaster;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// struct Score;
+    ///
+    /// impl <S> GameCommand <S> for Score {
+    ///     fn execute(&self, state: &Rc<RefCell<S>>,
+    ///                loader: &Rc<RefCell<Loader<S>>>)
+    ///                -> i32 {
+    ///         println!("No score yet");
+    ///
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Room)));
+    /// gm.add_command("score".to_string(), Box::new(Score));
+    ///
+    /// gm.add_mode_command("combat", "flee".to_string(), Box::new(Score));
+    ///
+    /// gm.step("score");
+    /// gm.step("score");
+    /// gm.step("jump");
+    ///
+    /// gm.push_mode("combat");
+    /// gm.step("flee");
+    ///
+    /// let usage = gm.command_usage();
+    /// assert_eq!(usage.get("score"), Some(&2));
+    /// assert_eq!(usage.get("flee"), Some(&1));
+    /// assert_eq!(usage.get("jump"), None);
+    /// ```
+    pub fn command_usage(&self) -> HashMap<String, u64> {
+        self.usage.clone()
+    }
+
+    /// Pass the input of global commands on to the current scenario too
+    ///
+    /// This is how input was dispatched before usage counts were added:
+    /// the scenario receives the input after the command ran, unless the
+    /// command returned `LOAD`, `RESPAWN`, `QUIT` or `END`, and the code it
+    /// returns is the one used. `classify()` still reports the command.
+    /// Disabled by default, so a global command is the only one to see its
+    /// input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::GameCommand;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::util::{NOTURN, TICK};
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         loader.borrow_mut().write(&format!("Room saw '{}'", command));
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// struct Score;
+    ///
+    /// impl <S> GameCommand <S> for Score {
+    ///     fn execute(&self, state: &Rc<RefCell<S>>,
+    ///                loader: &Rc<RefCell<Loader<S>>>)
+    ///                -> i32 {
+    ///         loader.borrow_mut().write("No score yet");
+    ///         NOTURN
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let mut gm = GameMaster::new(Rc::new(RefCell::new(())),
+    ///                              Rc::new(RefCell::new(Room)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.add_command("score".to_string(), Box::new(Score));
+    ///
+    /// gm.step("score");
+    /// gm.set_command_fallthrough(true);
+    /// assert_eq!(gm.step("score"), TICK);
+    ///
+    /// assert_eq!(buffer.contents(), "\
+    /// No score yet
+    /// No score yet
+    /// Room saw 'score'
+    /// ");
+    /// assert_eq!(gm.command_usage().get("score"), Some(&2));
+    /// ```
+    pub fn set_command_fallthrough(&mut self, enabled: bool) {
+        self.command_fallthrough = enabled;
+    }
+
+    /// Check whether the input of a global command returning the given
+    /// code goes on to the current scenario
+    fn falls_through(&self, result: i32) -> bool {
+        self.command_fallthrough
+            && result != LOAD && result != RESPAWN
+            && result != QUIT && result != END
+    }
+
+    /// Process a single line of input, telling whether the scenario changed
+    ///
+    /// Same as `step()`, but the result also names the scenario the player
+    /// ended up in when the input changed the current scenario.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::data::{DataScenario, Exit};
+    /// use texture::master::{GameMaster, StepResult};
+    /// use texture::output::NullOutput;
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::{LOAD, NOTURN};
+    ///
+    /// let mut porch = DataScenario::new("A creaky porch.".to_string());
+    /// porch.add_exit("in".to_string(), Exit::new("parlor".to_string()));
+    /// let parlor = DataScenario::new("A dim parlor.".to_string());
+    ///
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(porch)));
+    /// gm.set_output(Box::new(NullOutput));
+    /// gm.register_scenario("parlor".to_string(), Rc::new(RefCell::new(parlor)));
+    ///
+    /// assert_eq!(gm.step_result("in"), StepResult {
+    ///     action: LOAD,
+    ///     transitioned_to: Some("parlor".to_string()),
+    /// });
+    /// assert_eq!(gm.step_result("dance"), StepResult {
+    ///     action: NOTURN,
+    ///     transitioned_to: None,
+    /// });
+    /// ```
+    pub fn step_result(&mut self, input: &str) -> StepResult {
+        let before = self.current.clone();
+        let action = self.step(input);
+
+        let transitioned_to = if Rc::ptr_eq(&before, &self.current) {
+            None
+        } else {
+            self.loader.borrow().name_of(&self.current)
+        };
+
+        StepResult {
+            action: action,
+            transitioned_to: transitioned_to,
+        }
+    }
+
+    /// Process a single line of input
+    ///
+    /// Global commands take precedence: the input only reaches the current
+    /// scenario when no global command matches it. Returns the code obtained
+    /// from the command or scenario.
+    ///
+    /// Together with `begin()`, this lets an external driver (a chat bot, a
+    /// web server...) run the game one message at a time instead of using
+    /// `start_game()`, with the output captured in a `BufferOutput`. The
+    /// game master is not `Send`, so it must stay on the thread that created
+    /// it; see `GameThread` (`threaded` feature) for running it on a thread of
+    /// its own.
+    ///
+    /// Inputs queued meanwhile (see `Loader::queue_input()`) are processed
+    /// right after the input, up to the limit set with
+    /// `set_queued_input_limit()`, and the code of the last one is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::{QUIT, TICK};
+    ///
+    /// struct Echo;
+    ///
+    /// impl <S> Scenario <S> for Echo {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         loader.borrow_mut().write("Say something.");
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         if command == "bye" {
+    ///             return QUIT;
+    ///         }
+    ///
+    ///         loader.borrow_mut().write(&format!("You said '{}'.", command));
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Echo)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.begin();
+    ///
+    /// // Messages received from somewhere else
+    /// let messages = vec!["hello", "  how are you ", "bye"];
+    /// let mut replies = Vec::new();
+    ///
+    /// for message in messages {
+    ///     buffer.clear();
+    ///     let code = gm.step(message);
+    ///     replies.push((code, buffer.contents()));
+    /// }
+    ///
+    /// assert_eq!(replies, vec![
+    ///     (TICK, "You said 'hello'.\n".to_string()),
+    ///     (TICK, "You said 'how are you'.\n".to_string()),
+    ///     (QUIT, "".to_string()),
+    /// ]);
+    /// assert_eq!(gm.turns(), 2);
+    /// ```
+    pub fn step(&mut self, input: &str) -> i32 {
+        let result = self.exec_input(input);
+
+        self.exec_queued_inputs(result)
+    }
+
+    /// Process the queued inputs after an input with the given code,
+    /// returning the code of the last one
+    fn exec_queued_inputs(&mut self, mut result: i32) -> i32 {
+        let mut processed = 0;
+
+        while result != QUIT && result != END {
+            if processed == self.queued_input_limit {
+                // Inputs keep queueing more inputs, stop feeding them
+                self.loader.borrow_mut().clear_queued_inputs();
+                break;
+            }
+
+            let input = match self.loader.borrow_mut().take_queued_input() {
+                Some(i) => { i },
+                None => { break }
+            };
+
+            processed += 1;
+            result = self.exec_input(&input);
+        }
+
+        result
+    }
+
+    /// Process a single line of input, leaving queued inputs for later
+    fn exec_input(&mut self, input: &str) -> i32 {
+        self.loader.borrow_mut().take_missing_reported();
+        self.record_input(input.trim());
+
+        let input = self.transform_input(input.trim());
+        let mut command = self.expand_abbreviation(input.trim());
+
+        // Repeat last input, which is never an "again" command itself
+        if self.again_commands.contains(&command) {
+            command = match self.last_input.clone() {
+                Some(last) => { last },
+                None => {
+                    self.loader.borrow_mut().write("There is nothing to repeat.");
+                    return NOTURN;
+                }
+            };
+        } else if !command.is_empty() {
+            if self.is_blocked_repeat(&command) {
+                self.repeated_command(&command);
+                return NOTURN;
+            }
+
+            self.last_input = Some(command.clone());
+        }
+
+        self.phase.set(Phase::Command);
+        let (mut result, scope) = match self.exec_game_command(&command) {
+            CommandResult::Handled(result) if !self.falls_through(result) => {
+                (result, Scope::Global)
+            },
+            _ => {
+                self.phase.set(Phase::Action);
+                let result = self.exec_current_scenario(&command);
+
+                if result != UNKNOWN {
+                    (result, Scope::Scenario)
+                } else {
+                    self.phase.set(Phase::Command);
+
+                    match self.exec_prefix_command(&command) {
+                        CommandResult::Handled(r) => { (r, Scope::Global) },
+                        CommandResult::NotFound => { (result, Scope::Scenario) }
+                    }
+                }
+            }
+        };
+        self.phase.set(Phase::Idle);
+
+        if !is_known_code(result) {
+            result = self.invalid_code(result);
+        }
+
+        if result == UNKNOWN {
+            self.unknown_command(&command, scope);
+        }
+
+        if result == END {
+            self.game_over();
+        }
+
+        if result == LOAD {
+            self.load_scenario();
+        } else if result == RESPAWN {
+            self.respawn();
+        } else if advances_turn(result) {
+            for _ in 0..turn_cost(result) {
+                let before = self.current.clone();
+                self.end_turn();
+
+                // The rest of the turns belong to what happened
+                if !Rc::ptr_eq(&before, &self.current)
+                    || self.loader.borrow().has_ending() {
+                    break;
+                }
+            }
+        }
+
+        self.fire_timed_events();
+
+        self.apply_command_requests();
+
+        if let Some(ref hook) = self.diff_hook {
+            hook(&self.state, &self.loader);
+        }
+
+        result
+    }
+
+    /// Apply changes to global commands requested through the loader
+    fn apply_command_requests(&mut self) {
+        let requests = self.loader.borrow_mut().take_command_requests();
+
+        for request in requests {
+            match request {
+                CommandRequest::Add(name, command) => {
+                    self.add_command(name, command);
+                },
+                CommandRequest::Remove(name) => {
+                    self.commands.remove(&name);
+                    self.guards.remove(&name);
+                    self.hidden.remove(&name);
+                },
+                CommandRequest::PushMode(name) => { self.push_mode(&name) },
+                CommandRequest::PopMode => { self.pop_mode(); }
+            }
+        }
+    }
+
+    /// Replace an unknown action code
+    fn invalid_code(&mut self, code: i32) -> i32 {
+        let mut loader = self.loader.borrow_mut();
+
+        match self.invalid_code_handler {
+            Some(ref handler) => { handler(&mut *loader, code) },
+            None => {
+                loader.write(&format!("Warning: unknown action code {}", code));
+                TICK
+            }
+        }
+    }
+
+    /// Set the function called when an unknown action code is returned
+    ///
+    /// Only codes that are neither one of the constants in `util` nor built
+    /// with `tick_n()` (see `util::is_known_code()`) reach the function;
+    /// they usually mean a bug in a scenario or command. The function
+    /// receives the code and returns the one to use instead. By default, a
+    /// warning is written and the code is treated as `TICK`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::util::{NOTURN, TICK};
+    ///
+    /// struct Buggy;
+    ///
+    /// impl <S> Scenario <S> for Buggy {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         42
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let mut gm = GameMaster::new(Rc::new(RefCell::new(())),
+    ///                              Rc::new(RefCell::new(Buggy)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    ///
+    /// assert_eq!(gm.step("jump"), TICK);
+    /// assert_eq!(buffer.contents(), "Warning: unknown action code 42\n");
+    /// assert_eq!(gm.turns(), 1);
+    ///
+    /// let seen = Rc::new(RefCell::new(Vec::new()));
+    /// let record = seen.clone();
+    ///
+    /// gm.set_invalid_code_handler(Box::new(move |_, code| {
+    ///     record.borrow_mut().push(code);
+    ///     NOTURN
+    /// }));
+    ///
+    /// assert_eq!(gm.step("jump"), NOTURN);
+    /// assert_eq!(*seen.borrow(), vec![42]);
+    /// assert_eq!(gm.turns(), 1);
+    /// ```
+    pub fn set_invalid_code_handler(&mut self,
+                                    handler: InvalidCodeHandler<S>) {
+        self.invalid_code_handler = Some(handler);
+    }
+
+    /// Report a `LOAD` without a scenario to load
+    fn transition_error(&mut self) {
+        let message = format!("LOAD returned in '{}' without setting a \
+                               scenario to load", self.current_name());
+        let mut loader = self.loader.borrow_mut();
+
+        match self.transition_error_handler {
+            Some(ref handler) => { handler(&mut *loader, &message) },
+            None => { loader.write(&format!("Warning: {}", message)) }
+        }
+    }
+
+    /// Set the function called when `LOAD` is returned but no scenario was
+    /// set to be loaded
+    ///
+    /// This usually means a scenario forgot to call `set_scenario()` (or
+    /// similar) on the loader. The player stays in the current scenario,
+    /// which is not loaded again. By default, a warning is written.
+    ///
+    /// When the scenario tried to load a name that was never registered,
+    /// only the missing scenario handler reports it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::util::{LOAD, TICK};
+    ///
+    /// struct Door;
+    ///
+    /// impl <S> Scenario <S> for Door {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         loader.borrow_mut().write("A door.");
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         if command == "knock" {
+    ///             // No scenario was registered with this name
+    ///             loader.borrow_mut().load_by_name("hall");
+    ///         }
+    ///
+    ///         // Forgot to set the scenario behind the door
+    ///         LOAD
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let mut gm = GameMaster::new(Rc::new(RefCell::new(())),
+    ///                              Rc::new(RefCell::new(Door)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    ///
+    /// gm.step("open door");
+    /// assert_eq!(buffer.contents(), "Warning: LOAD returned in '<unnamed>' \
+    ///                                without setting a scenario to load\n");
+    ///
+    /// let errors = Rc::new(RefCell::new(Vec::new()));
+    /// let record = errors.clone();
+    ///
+    /// gm.set_transition_error_handler(Box::new(move |_, message| {
+    ///     record.borrow_mut().push(message.to_string());
+    /// }));
+    ///
+    /// buffer.clear();
+    /// gm.step("open door");
+    ///
+    /// assert_eq!(errors.borrow().len(), 1);
+    /// assert_eq!(buffer.contents(), "");
+    ///
+    /// gm.step("knock");
+    ///
+    /// assert_eq!(errors.borrow().len(), 1);
+    /// assert_eq!(buffer.contents(), "Error: there is no scenario named \
+    ///                                'hall'\n");
+    /// ```
+    pub fn set_transition_error_handler(&mut self,
+                                        handler: TransitionErrorHandler<S>) {
+        self.transition_error_handler = Some(handler);
+    }
+
+    /// Report input that nobody understood
+    fn unknown_command(&mut self, command: &str, scope: Scope) {
+        let mut loader = self.loader.borrow_mut();
+
+        match self.unknown_handler {
+            Some(ref handler) => { handler(&mut *loader, command, scope) },
+            None => { loader.write("I don't understand that.") }
+        }
+    }
+
+    /// Set the function called when an input is not understood
+    ///
+    /// The function is called when a global command or the current
+    /// scenario returns `UNKNOWN`, and receives the input and where it was
+    /// rejected. By default, a generic message is written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::GameCommand;
+    /// use texture::master::{GameMaster, Scope};
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::{TICK, UNKNOWN};
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         match command {
+    ///             "wait" => TICK,
+    ///             _ => UNKNOWN
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// // Global command that is not usable yet
+    /// struct Fly;
+    ///
+    /// impl <S> GameCommand <S> for Fly {
+    ///     fn execute(&self, state: &Rc<RefCell<S>>,
+    ///                loader: &Rc<RefCell<Loader<S>>>)
+    ///                -> i32 {
+    ///         UNKNOWN
+    ///     }
+    /// }
+    ///
+    /// let probe = Rc::new(RefCell::new(Vec::new()));
+    /// let record = probe.clone();
+    ///
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Room)));
+    /// gm.add_command("fly".to_string(), Box::new(Fly));
+    /// gm.set_unknown_command_handler(Box::new(move |_, command, scope| {
+    ///     record.borrow_mut().push((command.to_string(), scope));
+    /// }));
+    ///
+    /// gm.step("wait");
+    /// gm.step("fly");
+    /// gm.step("dance");
+    ///
+    /// assert_eq!(*probe.borrow(), vec![
+    ///     ("fly".to_string(), Scope::Global),
+    ///     ("dance".to_string(), Scope::Scenario),
+    /// ]);
+    /// ```
+    pub fn set_unknown_command_handler(&mut self,
+                                       handler: UnknownCommandHandler<S>) {
+        self.unknown_handler = Some(handler);
+    }
+
+    /// Advance the turn counters, fire scheduled events and notify the
+    /// current scenario
+    fn end_turn(&mut self) {
+        self.turns += 1;
+        self.dwell += 1;
+
+        self.loader.borrow_mut().emit(OutputEvent::TurnEnded(self.turns));
+
+        let due = self.loader.borrow_mut().advance_turn(self.turns);
+        let mut load = false;
+
+        for event in due {
+            if event(&self.state, &self.loader) == LOAD {
+                load = true;
+            }
+        }
+
+        let result = self.current.borrow().on_dwell(
+            self.dwell,
+            &self.state,
+            &self.loader
+        );
+
+        if load || result == LOAD {
+            self.load_scenario();
+        }
+    }
+
+    /// Execute right away every scheduled event that is due
+    ///
+    /// Events scheduled for the current turn or before, as well as those
+    /// scheduled after a real time that already elapsed, are fired without
+    /// waiting for the end of a turn. Useful after loading a game or
+    /// skipping time. The codes they return are honored: `LOAD` loads the
+    /// scenario set and `RESPAWN` goes back to the last checkpoint. `END`
+    /// ends the game right away: the remaining due events are discarded and
+    /// no scenario is loaded.
+    ///
+    /// Returns the number of events fired and the code honored, which is
+    /// `END`, `LOAD` or `RESPAWN`, or `NOTURN` if nothing else happened.
+    /// Drivers should stop the game when it is `END`, as the game loop
+    /// does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::WaitCommand;
+    /// use texture::data::DataScenario;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::{END, LOAD, NOTURN};
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    /// let vault = DataScenario::new("A sealed vault.".to_string());
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(vault)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.add_command("wait".to_string(), Box::new(WaitCommand));
+    ///
+    /// gm.step("wait");
+    /// gm.step("wait");
+    /// gm.step("wait");
+    /// buffer.clear();
+    ///
+    /// // Rebuilt after loading a game at turn 3, the alarm was meant to
+    /// // ring at turn 1
+    /// let loader = gm.loader();
+    /// loader.borrow_mut().schedule_at(1, Box::new(|_, loader| {
+    ///     loader.borrow_mut().write("An alarm rings.");
+    ///     NOTURN
+    /// }));
+    /// loader.borrow_mut().schedule_at(5, Box::new(|_, loader| {
+    ///     loader.borrow_mut().write("The guards arrive.");
+    ///     NOTURN
+    /// }));
+    ///
+    /// assert_eq!(gm.process_due_events(), (1, NOTURN));
+    /// assert_eq!(gm.process_due_events(), (0, NOTURN));
+    ///
+    /// assert_eq!(buffer.contents(), "An alarm rings.\n");
+    /// assert_eq!(loader.borrow().pending_events().len(), 1);
+    /// assert_eq!(gm.turns(), 3);
+    ///
+    /// // Nothing happens after the game ends
+    /// loader.borrow_mut().schedule_at(2, Box::new(|_, _| END));
+    /// loader.borrow_mut().schedule_at(3, Box::new(|_, loader| {
+    ///     loader.borrow_mut().load_by_name("cell");
+    ///     LOAD
+    /// }));
+    ///
+    /// assert_eq!(gm.process_due_events(), (1, END));
+    /// assert_eq!(loader.borrow().pending_events().len(), 1);
+    /// ```
+    pub fn process_due_events(&mut self) -> (usize, i32) {
+        let mut due = self.loader.borrow_mut().advance_turn(self.turns);
+        due.extend(self.loader.borrow_mut().due_timed_events());
+
+        let mut fired = 0;
+        let mut next = NOTURN;
+
+        for event in due {
+            fired += 1;
+
+            match event(&self.state, &self.loader) {
+                END => {
+                    self.game_over();
+                    return (fired, END);
+                },
+                code @ LOAD | code @ RESPAWN => { next = code },
+                _ => {}
+            }
+        }
+
+        match next {
+            LOAD => { self.load_scenario(); },
+            RESPAWN => { self.respawn() },
+            _ => {}
+        }
+
+        (fired, next)
+    }
+
+    /// Execute the events scheduled after a real time that are due
+    fn fire_timed_events(&mut self) {
+        let due = self.loader.borrow_mut().due_timed_events();
+        let mut load = false;
+
+        for event in due {
+            if event(&self.state, &self.loader) == LOAD {
+                load = true;
+            }
+        }
+
+        if load {
+            self.load_scenario();
+        }
+    }
+
+    /// Number of turns played so far
+    pub fn turns(&self) -> u64 {
+        self.turns
+    }
+
+    /// Number of turns spent in the current scenario
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::{LOAD, TICK};
+    ///
+    /// // Room that collapses on the third turn
+    /// struct Tunnel;
+    /// struct Pit;
+    ///
+    /// impl <S> Scenario <S> for Tunnel {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn on_dwell(&self, turns: u64, state: &Rc<RefCell<S>>,
+    ///                 loader: &Rc<RefCell<Loader<S>>>)
+    ///                 -> i32 {
+    ///         if turns < 3 {
+    ///             return TICK;
+    ///         }
+    ///
+    ///         loader.borrow_mut().write("The tunnel collapses!");
+    ///         loader.borrow_mut().set_scenario(Rc::new(RefCell::new(Pit)));
+    ///
+    ///         LOAD
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// impl <S> Scenario <S> for Pit {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         loader.borrow_mut().write("You fall into a pit");
+    ///
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Tunnel)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    ///
+    /// gm.step("wait");
+    /// gm.step("wait");
+    /// assert_eq!(gm.dwell_turns(), 2);
+    /// assert_eq!(buffer.contents(), "");
+    ///
+    /// gm.step("wait");
+    /// assert!(buffer.contents().contains("The tunnel collapses!"));
+    /// assert!(buffer.contents().contains("You fall into a pit"));
+    ///
+    /// // Counter is reset when entering the new scenario
+    /// assert_eq!(gm.dwell_turns(), 0);
+    /// assert_eq!(gm.turns(), 3);
+    /// ```
+    pub fn dwell_turns(&self) -> u64 {
+        self.dwell
+    }
+
+    /// Set the inputs that repeat the last action (`again` and `g` by
+    /// default)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         if command == "look" {
+    ///             loader.borrow_mut().write("An empty room");
+    ///         }
+    ///
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Room)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    ///
+    /// gm.step("look");
+    /// gm.step("again");
+    /// gm.step("g");
+    /// assert_eq!(buffer.contents(), "An empty room\n".repeat(3));
+    ///
+    /// // Custom repeat commands
+    /// gm.set_again_commands(vec!["repeat".to_string()]);
+    /// buffer.clear();
+    ///
+    /// gm.step("repeat");
+    /// gm.step("g");
+    /// assert_eq!(buffer.contents(), "An empty room\n");
+    /// ```
+    pub fn set_again_commands(&mut self, commands: Vec<String>) {
+        self.again_commands = commands;
+    }
+
+    /// Stop executing an input identical to the previous one
+    ///
+    /// When enabled, the repeated input is given to the repeat handler (see
+    /// `on_repeat()`) instead, without taking a turn. Inputs allowed with
+    /// `allow_repeat()` (`wait` and `z` by default) and "again" commands
+    /// are always executed. Disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         loader.borrow_mut().write("You knock on the door.");
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Room)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    ///
+    /// // Disabled
+    /// gm.step("knock");
+    /// gm.step("knock");
+    /// assert_eq!(buffer.contents(), "You knock on the door.\n".repeat(2));
+    ///
+    /// // Enabled
+    /// gm.set_dedupe_repeats(true);
+    /// gm.on_repeat(Box::new(|loader, input| {
+    ///     loader.write(&format!("You already tried to {}.", input));
+    /// }));
+    /// buffer.clear();
+    ///
+    /// gm.step("knock");
+    /// assert_eq!(buffer.contents(), "You already tried to knock.\n");
+    /// assert_eq!(gm.turns(), 2);
+    /// ```
+    pub fn set_dedupe_repeats(&mut self, enabled: bool) {
+        self.dedupe_repeats = enabled;
+    }
+
+    /// Set the function called when an input is not executed for being the
+    /// same as the previous one
+    ///
+    /// By default, "You already did that." is written.
+    pub fn on_repeat(&mut self, handler: RepeatHandler<S>) {
+        self.repeat_handler = Some(handler);
+    }
+
+    /// Always execute the given input, even if repeated
+    pub fn allow_repeat(&mut self, input: String) {
+        self.repeatable.insert(input);
+    }
+
+    /// Check whether an input must not be executed for being repeated
+    fn is_blocked_repeat(&self, command: &str) -> bool {
+        self.dedupe_repeats
+            && self.last_input.as_ref().map_or(false, |last| last == command)
+            && !self.repeatable.contains(command)
+    }
+
+    /// Report an input that is not executed for being repeated
+    fn repeated_command(&mut self, command: &str) {
+        let mut loader = self.loader.borrow_mut();
+
+        match self.repeat_handler {
+            Some(ref handler) => { handler(&mut loader, command) },
+            None => { loader.write("You already did that.") }
+        }
+    }
+
+    /// Add an abbreviation for the first word of the input
+    ///
+    /// The word is replaced before dispatching the input, keeping the rest
+    /// of it. Compass directions (`n`, `s`, `e`, `w`...) and `u`/`d` are
+    /// abbreviated by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// // Echo the received command
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         loader.borrow_mut().write(command);
+    ///
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Room)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.add_abbreviation("x".to_string(), "examine".to_string());
+    ///
+    /// gm.step("n door");
+    /// gm.step("x lamp");
+    /// gm.step("nothing");
+    ///
+    /// gm.clear_abbreviations();
+    /// gm.step("n door");
+    ///
+    /// assert_eq!(buffer.contents(),
+    ///            "north door\nexamine lamp\nnothing\nn door\n");
+    /// ```
+    pub fn add_abbreviation(&mut self, short: String, long: String) {
+        self.abbreviations.insert(short, long);
+    }
+
+    /// Remove all abbreviations, including the default ones
+    pub fn clear_abbreviations(&mut self) {
+        self.abbreviations.clear();
+    }
+
+    /// Add a rewrite of the input typed by the player
+    ///
+    /// Transforms are applied in the order they were added to the trimmed
+    /// input, each one receiving the result of the previous one, before
+    /// abbreviations, "again" inputs and commands are resolved. The recent
+    /// inputs (see `recent_inputs()`) keep what was typed, while repeating
+    /// the last input does not transform it again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::TakeCommand;
+    /// use texture::data::DataScenario;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::state::{BaseState, BasicState};
+    ///
+    /// let mut study = DataScenario::new("A quiet study.".to_string());
+    /// study.add_item("lamp");
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state.clone(), Rc::new(RefCell::new(study)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.add_command("take".to_string(), Box::new(TakeCommand));
+    ///
+    /// gm.add_input_transform(Box::new(|input| input.to_lowercase()));
+    /// gm.add_input_transform(Box::new(|input| {
+    ///     if input.starts_with("pick up ") {
+    ///         format!("take {}", &input[8..])
+    ///     } else {
+    ///         input
+    ///     }
+    /// }));
+    ///
+    /// gm.step("Pick up lamp");
+    ///
+    /// assert!(state.borrow().has_item("lamp"));
+    /// assert_eq!(buffer.contents(), "Taken.\n");
+    /// ```
+    pub fn add_input_transform(&mut self, transform: InputTransform) {
+        self.input_transforms.push(transform);
+    }
+
+    /// Apply the input transforms in order
+    fn transform_input(&self, input: &str) -> String {
+        self.input_transforms.iter()
+            .fold(input.to_string(), |input, transform| transform(input))
+    }
+
+    /// Replace the first word of the input if it is an abbreviation
+    fn expand_abbreviation(&self, command: &str) -> String {
+        let mut parts = command.splitn(2, ' ');
+        let verb = parts.next().unwrap_or("");
+
+        let long = match self.abbreviations.get(verb) {
+            Some(l) => { l },
+            None => { return command.to_string() }
+        };
+
+        match parts.next() {
+            Some(rest) => { format!("{} {}", long, rest) },
+            None => { long.clone() }
+        }
+    }
+
+    /// Set the function building the status line shown before each prompt
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::input::VecInput;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// struct Mine;
+    ///
+    /// impl <S: BaseState> Scenario <S> for Mine {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         if command == "dig" {
+    ///             state.borrow_mut().increment("gold".to_string(), 5);
+    ///         }
+    ///
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Mine)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.set_input(Box::new(VecInput::new(vec!["dig".to_string()])));
+    /// gm.set_status_fn(Box::new(|s| {
+    ///     format!("[Gold: {}]", s.borrow().get_value("gold".to_string()))
+    /// }));
+    ///
+    /// gm.start_game();
+    ///
+    /// assert_eq!(buffer.contents(), "[Gold: 0]\n \n[Gold: 5]\n");
+    /// ```
+    pub fn set_status_fn(&mut self, f: StatusFn<S>) {
+        self.status_fn = Some(f);
+    }
+
+    /// Set the text shown when the game starts, before the first scenario
+    /// is loaded (title, prologue...)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::input::VecInput;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn describe(&self, state: &Rc<RefCell<S>>,
+    ///                 loader: &Rc<RefCell<Loader<S>>>) {
+    ///         loader.borrow_mut().write("You wake up in a cell.");
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Room)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.set_input(Box::new(VecInput::new(vec!["".to_string()])));
+    /// gm.set_intro(vec!["ESCAPE".to_string(), "A tale of walls".to_string()]);
+    /// gm.set_intro_pause(Some("Press enter to begin".to_string()));
+    ///
+    /// gm.start_game();
+    ///
+    /// assert_eq!(buffer.contents(),
+    ///            "ESCAPE\nA tale of walls\nYou wake up in a cell.\n");
+    /// ```
+    pub fn set_intro(&mut self, lines: Vec<String>) {
+        self.intro = lines;
+    }
+
+    /// Wait for the player to press enter after the intro, showing the
+    /// given prompt
+    ///
+    /// `None` (the default) starts the game right away.
+    pub fn set_intro_pause(&mut self, prompt: Option<String>) {
+        self.intro_pause = prompt;
+    }
+
+    /// Start a new game by calling the main loop
+    ///
+    /// The intro (if any) is shown first. Returns when the player quits
+    /// (`QUIT`), an ending is reached (`END`) or the input source has no
+    /// more input. The output is
+    /// flushed before reading each input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::input::Input;
+    /// use texture::master::GameMaster;
+    /// use texture::output::Output;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         loader.borrow_mut().write("A room.");
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         loader.borrow_mut().write("Time passes.");
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// // Output and input sharing a log of events
+    /// struct LogOutput(Rc<RefCell<Vec<String>>>);
+    ///
+    /// impl Output for LogOutput {
+    ///     fn write(&mut self, text: &str) {
+    ///         self.0.borrow_mut().push("write".to_string());
+    ///     }
+    ///
+    ///     fn flush(&mut self) {
+    ///         self.0.borrow_mut().push("flush".to_string());
+    ///     }
+    /// }
+    ///
+    /// struct LogInput(Rc<RefCell<Vec<String>>>, u32);
+    ///
+    /// impl Input for LogInput {
+    ///     fn read_line(&mut self, prompt: &str) -> Option<String> {
+    ///         self.0.borrow_mut().push("read".to_string());
+    ///
+    ///         if self.1 == 0 {
+    ///             return None;
+    ///         }
+    ///
+    ///         self.1 -= 1;
+    ///         Some("wait".to_string())
+    ///     }
+    /// }
+    ///
+    /// let log = Rc::new(RefCell::new(Vec::new()));
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Room)));
+    /// gm.set_output(Box::new(LogOutput(log.clone())));
+    /// gm.set_input(Box::new(LogInput(log.clone(), 2)));
+    ///
+    /// gm.start_game();
+    ///
+    /// let log = log.borrow();
+    /// let reads: Vec<usize> = (0..log.len())
+    ///     .filter(|&i| log[i] == "read")
+    ///     .collect();
+    ///
+    /// assert_eq!(reads.len(), 3);
+    ///
+    /// for i in reads {
+    ///     assert_eq!(log[i - 1], "flush");
+    /// }
+    /// ```
+    pub fn start_game(&mut self) {
+        if let Some(ref hook) = self.start_hook {
+            hook(&self.state, &self.loader);
+        }
+
+        self.show_intro();
+        self.main_loop();
+
+        if let Some(ref hook) = self.end_hook {
+            hook(&self.state, &self.loader, self.turns);
+        }
+
+        self.loader.borrow_mut().flush();
+    }
+
+    /// Write the text of the ending reached and report it
+    fn game_over(&mut self) {
+        let ending = self.loader.borrow_mut().take_ending();
+
+        let id = match ending {
+            Some((id, text)) => {
+                if let Some(text) = text {
+                    self.loader.borrow_mut().write(&text);
+                }
+
+                Some(id)
+            },
+            None => { None }
+        };
+
+        if let Some(ref hook) = self.game_over_hook {
+            hook(&self.state, id.as_ref().map(|id| id.as_str()));
+        }
+    }
+
+    /// Set the function called when a scenario or command returns `END`
+    ///
+    /// Called after the text of the ending (see `Loader::set_ending()`) is
+    /// written. The main loop stops afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::input::VecInput;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::{END, TICK};
+    ///
+    /// struct Throne;
+    ///
+    /// impl <S> Scenario <S> for Throne {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         if command == "sit" {
+    ///             loader.borrow_mut().set_ending(
+    ///                 "crowned".to_string(),
+    ///                 Some("You rule the land. THE END".to_string()));
+    ///
+    ///             return END;
+    ///         }
+    ///
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Throne)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.set_input(Box::new(VecInput::new(vec![
+    ///     "wave".to_string(),
+    ///     "sit".to_string(),
+    ///     "wave".to_string(),
+    /// ])));
+    ///
+    /// let endings = Rc::new(RefCell::new(Vec::new()));
+    /// let record = endings.clone();
+    ///
+    /// gm.on_game_over(Box::new(move |_, id| {
+    ///     record.borrow_mut().push(id.map(|id| id.to_string()));
+    /// }));
+    ///
+    /// gm.start_game();
+    ///
+    /// assert_eq!(*endings.borrow(), vec![Some("crowned".to_string())]);
+    /// assert!(buffer.contents().ends_with("You rule the land. THE END\n"));
+    /// assert_eq!(gm.turns(), 1);
+    /// ```
+    pub fn on_game_over(&mut self, f: GameOverHook<S>) {
+        self.game_over_hook = Some(f);
+    }
+
+    /// Set the function called by `start_game()` before anything else
+    pub fn on_start(&mut self, f: StartHook<S>) {
+        self.start_hook = Some(f);
+    }
+
+    /// Set the function called right before `start_game()` returns
+    ///
+    /// Called once, whether the game ended by quitting or because the
+    /// input ran out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::{Cell, RefCell};
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::{QuitCommand, WaitCommand};
+    /// use texture::data::DataScenario;
+    /// use texture::input::VecInput;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::state::{BaseState, BasicState};
+    ///
+    /// let hall = DataScenario::new("A long hall.".to_string());
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(hall)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.set_input(Box::new(VecInput::new(vec![
+    ///     "wait".to_string(),
+    ///     "quit".to_string(),
+    ///     "wait".to_string(),
+    /// ])));
+    /// gm.add_command("wait".to_string(), Box::new(WaitCommand));
+    /// gm.add_command("quit".to_string(), Box::new(QuitCommand::new(None)));
+    ///
+    /// let starts = Rc::new(Cell::new(0));
+    /// let ends = Rc::new(Cell::new(0));
+    ///
+    /// let counter = starts.clone();
+    /// gm.on_start(Box::new(move |_, loader| {
+    ///     counter.set(counter.get() + 1);
+    ///     loader.borrow_mut().write("Welcome!");
+    /// }));
+    ///
+    /// let counter = ends.clone();
+    /// gm.on_end(Box::new(move |_, loader, turns| {
+    ///     counter.set(counter.get() + 1);
+    ///     loader.borrow_mut().write(&format!("Thanks for playing ({} turns).",
+    ///                                        turns));
+    /// }));
+    ///
+    /// gm.start_game();
+    ///
+    /// assert_eq!(starts.get(), 1);
+    /// assert_eq!(ends.get(), 1);
+    ///
+    /// let contents = buffer.contents();
+    /// assert!(contents.starts_with("Welcome!\n"));
+    /// assert!(contents.ends_with("Thanks for playing (1 turns).\n"));
+    /// ```
+    pub fn on_end(&mut self, f: EndHook<S>) {
+        self.end_hook = Some(f);
+    }
+
+    /// Load and describe the starting scenario
+    ///
+    /// Called by `start_game()`. Drivers feeding input through `step()`
+    /// must call it once before the first step.
+    pub fn begin(&mut self) {
+        self.enter_current(true);
+        self.apply_command_requests();
+    }
+
+    /// Write the intro and wait for the player (if requested)
+    fn show_intro(&mut self) {
+        let mut loader = self.loader.borrow_mut();
+
+        for line in &self.intro {
+            loader.write(line);
+        }
+
+        if let Some(ref prompt) = self.intro_pause {
+            loader.flush();
+            loader.read_line(prompt);
+        }
+    }
+
+    /// Obtain the scenario loader shared with scenarios and commands
+    pub fn loader(&self) -> Rc<RefCell<Loader<S>>> {
+        self.loader.clone()
+    }
+
+    /// Check whether the state changed since the game was last saved or
+    /// loaded
+    ///
+    /// Only tracked when enabled (see `track_unsaved_changes()`).
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.loader.borrow().has_unsaved_changes()
+    }
+
+    /// Register a scenario in the loader under a name
+    ///
+    /// Same as `Loader::register()`, returning `true` if the name was
+    /// already taken.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::data::{DataScenario, Exit};
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::state::{BaseState, BasicState};
+    ///
+    /// let mut hall = DataScenario::new("A long hall.".to_string());
+    /// hall.add_exit("north".to_string(), Exit::new("library".to_string()));
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(hall)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    ///
+    /// let library = DataScenario::new("Dusty shelves.".to_string());
+    /// assert!(!gm.register_scenario("library".to_string(),
+    ///                               Rc::new(RefCell::new(library))));
+    ///
+    /// gm.step("north");
+    ///
+    /// assert!(buffer.contents().contains("Dusty shelves."));
+    /// assert_eq!(gm.loader().borrow().current_scenario_name(),
+    ///            Some("library".to_string()));
+    /// ```
+    pub fn register_scenario(&mut self, name: String,
+                             scenario: Rc<RefCell<Scenario<S>>>) -> bool {
+        self.loader.borrow_mut().register(name, scenario)
+    }
+
+    /// Register a function building a scenario the first time it is needed
+    ///
+    /// Same as `Loader::register_factory()`, returning `true` if the name
+    /// was already taken.
+    pub fn register_factory(&mut self, name: String,
+                            factory: ScenarioFactory<S>) -> bool {
+        self.loader.borrow_mut().register_factory(name, factory)
+    }
+
+    /// Replace the output used to show text to the player
+    pub fn set_output(&mut self, output: Box<Output>) {
+        self.loader.borrow_mut().set_output(output);
+    }
+
+    /// Wait for the player after every given number of lines written
+    ///
+    /// See `Loader::set_pager()`. 0 disables it.
+    pub fn set_pager(&mut self, lines: usize) {
+        self.loader.borrow_mut().set_pager(lines);
+    }
+
+    /// Add a transformation applied to every line before it is written
+    ///
+    /// Filters are applied in the order they were added, each receiving the
+    /// result of the previous one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         loader.borrow_mut().write("Stop right there");
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Room)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.add_output_filter(Box::new(|line| line.to_uppercase()));
+    /// gm.add_output_filter(Box::new(|line| format!("{}!", line)));
+    ///
+    /// gm.step("wait");
+    ///
+    /// assert_eq!(buffer.contents(), "STOP RIGHT THERE!\n");
+    /// ```
+    pub fn add_output_filter(&mut self, filter: OutputFilter) {
+        self.loader.borrow_mut().add_output_filter(filter);
+    }
+
+    /// Make a word refer to an object by another name
+    ///
+    /// See `Loader::add_synonym()`.
+    pub fn add_synonym(&mut self, canonical: &str, synonym: &str) {
+        self.loader.borrow_mut().add_synonym(canonical, synonym);
+    }
+
+    /// Replace the source of player input used by the game loop
+    pub fn set_input(&mut self, input: Box<Input>) {
+        self.loader.borrow_mut().set_input(input);
+    }
+
+    /// Set the maximum number of queued inputs processed in a row (20 by
+    /// default)
+    ///
+    /// Inputs queued by scenarios and commands (see `Loader::queue_input()`)
+    /// are processed before reading from the player, or right after the
+    /// input given to `step()`. Once this many were processed without the
+    /// player typing anything, the rest of the queue is discarded, so inputs
+    /// that keep queueing themselves cannot hang the game.
+    pub fn set_queued_input_limit(&mut self, limit: usize) {
+        self.queued_input_limit = limit;
+    }
+
+    /// Write each input read by the game loop, after its prompt
+    ///
+    /// Makes transcripts readable when the input does not come from a
+    /// terminal. By default, inputs are echoed only when the source does not
+    /// show them already (see `Input::shows_input()`): never for the
+    /// terminal, always for `ReaderInput`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::io::Cursor;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::WaitCommand;
+    /// use texture::data::DataScenario;
+    /// use texture::input::{ReaderInput, VecInput};
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::state::{BaseState, BasicState};
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    /// let field = DataScenario::new("An open field.".to_string());
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(field)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.add_command("wait".to_string(), Box::new(WaitCommand));
+    /// gm.set_input(Box::new(ReaderInput::new(Cursor::new("wait\n"))));
+    ///
+    /// gm.start_game();
+    /// assert!(buffer.contents().contains("> wait\n \nTime passes.\n"));
+    ///
+    /// // Scripted input is not echoed unless asked to
+    /// buffer.clear();
+    /// gm.set_input(Box::new(VecInput::new(vec!["wait".to_string()])));
+    /// gm.set_echo_input(true);
+    ///
+    /// gm.start_game();
+    /// assert!(buffer.contents().contains("> wait\n"));
+    /// ```
+    pub fn set_echo_input(&mut self, echo: bool) {
+        self.echo_input = Some(echo);
+    }
+
+    /// Check whether inputs read by the game loop are written
+    fn echoes_input(&self) -> bool {
+        match self.echo_input {
+            Some(echo) => { echo },
+            None => { !self.loader.borrow().shows_input() }
+        }
+    }
+
+    /// Replace the player used for asset cues requested by scenarios
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::cue::CuePlayer;
+    /// use texture::master::GameMaster;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// // Remember requested cues
+    /// struct Recorder {
+    ///     played: Rc<RefCell<Vec<String>>>
+    /// }
+    ///
+    /// impl CuePlayer for Recorder {
+    ///     fn play(&mut self, cue: &str) {
+    ///         self.played.borrow_mut().push(cue.to_string());
+    ///     }
+    /// }
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         loader.borrow_mut().play_cue("door_creak");
+    ///
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let played = Rc::new(RefCell::new(Vec::new()));
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Room)));
+    /// gm.set_cue_player(Box::new(Recorder { played: played.clone() }));
+    /// gm.loader().borrow_mut().register(
+    ///     "hall".to_string(), Rc::new(RefCell::new(Room)));
+    ///
+    /// gm.load_by_name_quiet("hall");
+    ///
+    /// assert_eq!(*played.borrow(), vec!["door_creak".to_string()]);
+    /// ```
+    pub fn set_cue_player(&mut self, cues: Box<CuePlayer>) {
+        self.loader.borrow_mut().set_cue_player(cues);
+    }
+
+    /// Set the function called when a scenario name cannot be resolved
+    ///
+    /// See `Loader::set_missing_scenario_handler()`.
+    pub fn set_missing_scenario_handler(&mut self,
+                                        handler: MissingScenarioHandler<S>) {
+        self.loader.borrow_mut().set_missing_scenario_handler(handler);
+    }
+
+    /// Transition to a registered scenario by its name
+    ///
+    /// Returns `false` if no scenario was registered with that name, in
+    /// which case the missing scenario handler is called and the current
+    /// scenario is kept.
+    pub fn load_by_name(&mut self, name: &str) -> bool {
+        if !self.loader.borrow_mut().load_by_name(name) {
+            return false;
+        }
+
+        self.load_scenario();
+
+        true
+    }
+
+    /// Transition to a registered scenario by its name without describing it
+    ///
+    /// The `load()` method of the scenario is still called, but
+    /// `describe()` is skipped and nothing else is written. Useful after
+    /// restoring a saved game.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// struct Room {
+    ///     name: &'static str
+    /// }
+    ///
+    /// impl <S: BaseState> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         state.borrow_mut().set_flag(format!("seen_{}", self.name), true);
+    ///
+    ///         TICK
+    ///     }
+    ///
+    ///     fn describe(&self, state: &Rc<RefCell<S>>,
+    ///                 loader: &Rc<RefCell<Loader<S>>>) {
+    ///         loader.borrow_mut().write(&format!("You are in the {}", self.name));
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         loader.borrow_mut().write(&format!("Nothing happens in the {}", self.name));
+    ///
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    /// let start = Rc::new(RefCell::new(Room { name: "porch" }));
+    ///
+    /// let mut gm = GameMaster::new(state.clone(), start);
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.loader().borrow_mut().register(
+    ///     "hall".to_string(),
+    ///     Rc::new(RefCell::new(Room { name: "hall" })));
+    ///
+    /// assert!(gm.load_by_name_quiet("hall"));
+    ///
+    /// // Setup was performed, but nothing was described
+    /// assert!(state.borrow().get_flag("seen_hall".to_string()));
+    /// assert_eq!(buffer.contents(), "");
+    ///
+    /// // Input now reaches the new scenario
+    /// gm.step("jump");
+    /// assert_eq!(buffer.contents(), "Nothing happens in the hall\n");
+    /// ```
+    pub fn load_by_name_quiet(&mut self, name: &str) -> bool {
+        if !self.loader.borrow_mut().load_by_name(name) {
+            return false;
+        }
+
+        self.current = self.loader.borrow_mut().promote();
+        self.enter_current(false);
+
+        true
+    }
+
+    /// Describe the current scenario again without reloading it
+    ///
+    /// Only the `describe()` method of the scenario is called, so any setup
+    /// performed in `load()` is not repeated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::{Cell, RefCell};
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// struct Room {
+    ///     described: Rc<Cell<u32>>
+    /// }
+    ///
+    /// impl <S: BaseState> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         // One-time setup
+    ///         let visits = state.borrow().get_value("visits".to_string());
+    ///         state.borrow_mut().set_value("visits".to_string(), visits + 1);
+    ///
+    ///         TICK
+    ///     }
+    ///
+    ///     fn describe(&self, state: &Rc<RefCell<S>>,
+    ///                 loader: &Rc<RefCell<Loader<S>>>) {
+    ///         println!("A dark room");
+    ///         self.described.set(self.described.get() + 1);
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let described = Rc::new(Cell::new(0));
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    /// let start = Rc::new(RefCell::new(Room { described: described.clone() }));
+    ///
+    /// let mut gm = GameMaster::new(state.clone(), start);
+    ///
+    /// gm.redescribe();
+    /// gm.redescribe();
+    ///
+    /// assert_eq!(described.get(), 2);
+    /// assert_eq!(state.borrow().get_value("visits".to_string()), 0);
+    /// ```
+    pub fn redescribe(&mut self) {
+        self.current.borrow().describe(&self.state, &self.loader);
+    }
+
+    /// Load scenario from the loader and call `load()` and `describe()`
+    /// methods
+    fn load_scenario(&mut self) -> i32 {
+        // An unknown name was already reported by the missing scenario path
+        let reported = self.loader.borrow_mut().take_missing_reported();
+
+        if !self.loader.borrow().has_pending_scenario() {
+            if !reported {
+                self.transition_error();
+            }
+
+            return NOTURN;
+        }
+
+        let returning = self.loader.borrow_mut().take_returning();
+        self.current = self.loader.borrow_mut().promote();
+
+        // Back from a modal scenario, which already took care of the text
+        if returning {
+            self.dwell = 0;
+            self.announce_room();
+            return TICK;
+        }
+
+        self.loader.borrow_mut().write(" ");
+
+        self.enter_current(true)
+    }
+
+    /// Send the player back to the last checkpoint entered
+    ///
+    /// A checkpoint is a scenario whose `is_checkpoint()` returns `true`.
+    /// The player goes back to the start scenario if no checkpoint was
+    /// entered yet. The scenario is loaded again, and the state is restored
+    /// to how it was when entering the checkpoint if enabled with
+    /// `set_respawn_snapshots()`.
+    ///
+    /// Called by `step()` when the action code is `RESPAWN`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::{LOAD, NOTURN, RESPAWN, TICK};
+    ///
+    /// struct Room {
+    ///     name: &'static str,
+    ///     next: Option<&'static str>,
+    ///     checkpoint: bool,
+    /// }
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         NOTURN
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         match (command, self.next) {
+    ///             ("go", Some(next)) => {
+    ///                 loader.borrow_mut().load_by_name(next);
+    ///                 LOAD
+    ///             },
+    ///             ("jump", _) => {
+    ///                 loader.borrow_mut().write("You fall to your death.");
+    ///                 RESPAWN
+    ///             },
+    ///             _ => { TICK }
+    ///         }
+    ///     }
+    ///
+    ///     fn describe(&self, state: &Rc<RefCell<S>>,
+    ///                 loader: &Rc<RefCell<Loader<S>>>) {
+    ///         loader.borrow_mut().write(self.name);
+    ///     }
+    ///
+    ///     fn is_checkpoint(&self) -> bool {
+    ///         self.checkpoint
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    /// let gate = Room { name: "Gate", next: Some("camp"), checkpoint: false };
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(gate)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.register_scenario(
+    ///     "camp".to_string(),
+    ///     Rc::new(RefCell::new(Room { name: "Camp", next: Some("cliff"),
+    ///                                 checkpoint: true })));
+    /// gm.register_scenario(
+    ///     "cliff".to_string(),
+    ///     Rc::new(RefCell::new(Room { name: "Cliff", next: None,
+    ///                                 checkpoint: false })));
+    ///
+    /// gm.step("go");
+    /// gm.step("go");
+    /// buffer.clear();
+    ///
+    /// gm.step("jump");
+    ///
+    /// assert_eq!(buffer.contents(), "You fall to your death.\n \nCamp\n");
+    /// ```
+    pub fn respawn(&mut self) {
+        if let (Some(copy), Some(ref saved)) = (self.respawn_snapshot,
+                                                &self.respawn_state) {
+            *self.state.borrow_mut() = copy(saved);
+        }
+
+        self.loader.borrow_mut().set_scenario(self.respawn_point.clone());
+        self.load_scenario();
+    }
+
+    /// Call `load()` method of the current scenario, followed by
+    /// `describe()` if requested
+    fn enter_current(&mut self, describe: bool) -> i32 {
+        self.dwell = 0;
+        self.announce_room();
+
+        let start = self.start_timing();
+        self.phase.set(Phase::Load);
+        let result = self.current.borrow().load(&self.state, &self.loader);
+        self.phase.set(Phase::Idle);
+        self.loader.borrow_mut().clear_arg();
+
+        if let Some(start) = start {
+            let key = format!("load {}", self.current_name());
+            self.record_timing(key, start);
+        }
+
+        if self.current.borrow().is_checkpoint() {
+            self.respawn_point = self.current.clone();
+
+            if let Some(copy) = self.respawn_snapshot {
+                self.respawn_state = Some(copy(&*self.state.borrow()));
+            }
+        }
+
+        let name = self.loader.borrow().name_of(&self.current);
+        let visited = match name {
+            Some(name) => { !self.loader.borrow_mut().mark_visited(name) },
+            None => { false }
+        };
+
+        if describe {
+            self.describe_current(visited);
+        }
+
+        result
+    }
+
+    /// Emit a `RoomChanged` event for the current scenario, if it has a name
+    fn announce_room(&mut self) {
+        let name = self.loader.borrow().name_of(&self.current);
+
+        if let Some(name) = name {
+            self.loader.borrow_mut().emit(OutputEvent::RoomChanged(name));
+        }
+    }
+
+    /// Describe the current scenario according to the description mode
+    fn describe_current(&mut self, visited: bool) {
+        let short = match self.description_mode() {
+            DescriptionMode::Verbose => { false },
+            DescriptionMode::Brief => { visited },
+            DescriptionMode::SuperBrief => { true }
+        };
+
+        let text = if short {
+            self.current.borrow().short_describe()
+        } else {
+            None
+        };
+
+        match text {
+            Some(text) => { self.loader.borrow_mut().write(&text) },
+            None => {
+                self.current.borrow().describe(&self.state, &self.loader)
+            }
+        }
+    }
+
+    /// Obtain how scenarios are described when entered
+    pub fn description_mode(&self) -> DescriptionMode {
+        self.loader.borrow().description_mode()
+    }
+
+    /// Change how scenarios are described when entered
+    ///
+    /// In `Brief` mode, scenarios registered in the loader show their short
+    /// description (see `Scenario::short_describe()`) when entered again.
+    /// Visits are recorded by name (see `Loader::visited()`), so scenarios
+    /// that were never registered count as new every time and always show
+    /// their full description. Explicitly redescribing the scenario always
+    /// shows the full description.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{DescriptionMode, Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::{LOAD, TICK};
+    ///
+    /// // Room leading to the other one
+    /// struct Room(&'static str, &'static str);
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn describe(&self, state: &Rc<RefCell<S>>,
+    ///                 loader: &Rc<RefCell<Loader<S>>>) {
+    ///         loader.borrow_mut().write(&format!("The {}, long.", self.0));
+    ///     }
+    ///
+    ///     fn short_describe(&self) -> Option<String> {
+    ///         Some(format!("The {}.", self.0))
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         loader.borrow_mut().load_by_name(self.1);
+    ///         LOAD
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    /// let hall = Rc::new(RefCell::new(Room("hall", "garden")));
+    ///
+    /// let mut gm = GameMaster::new(state, hall.clone());
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.set_description_mode(DescriptionMode::Brief);
+    ///
+    /// let loader = gm.loader();
+    /// loader.borrow_mut().register("hall".to_string(), hall);
+    /// loader.borrow_mut().register(
+    ///     "garden".to_string(), Rc::new(RefCell::new(Room("garden", "hall"))));
+    ///
+    /// gm.step("go");
+    /// gm.step("go");
+    /// gm.step("go");
+    ///
+    /// assert_eq!(buffer.contents(),
+    ///            " \nThe garden, long.\n \nThe hall, long.\n \nThe garden.\n");
+    /// ```
+    pub fn set_description_mode(&mut self, mode: DescriptionMode) {
+        self.loader.borrow_mut().set_description_mode(mode);
+    }
+
+    /// Execute a global game command (if any)
+    fn exec_game_command(&mut self, command: &str) -> CommandResult {
+        if self.exec_help(command) || self.exec_parse(command)
+            || self.exec_history(command) {
+            return CommandResult::Handled(NOTURN);
+        }
+
+        // Commands of the active input modes are tried first
+        let (mode, name, args) = match self.find_mode_command(command) {
+            Some((mode, name, args)) => {
+                (Some(mode.to_string()), name, args)
+            },
+            None => {
+                match self.find_command(command) {
+                    Some((name, args)) if self.is_available(name) => {
+                        (None, name, args)
+                    },
+                    _ => { return CommandResult::NotFound }
+                }
+            }
+        };
+
+        if self.disabled.contains(name) {
+            if let Some(ref message) = self.disabled_message {
+                self.loader.borrow_mut().write(message);
+            }
+
+            return CommandResult::Handled(NOTURN);
+        }
+
+        let game_command = match mode {
+            Some(ref mode) => { &*self.mode_commands[mode][name] },
+            None => { &*self.commands[name] }
+        };
+
+        if !self.check_arity(name, game_command, &args) {
+            return CommandResult::Handled(NOTURN);
+        }
+
+        *self.usage.entry(name.to_string()).or_insert(0) += 1;
+
+        let start = self.start_timing();
+
+        let result = game_command.execute_args(&args, &self.state,
+                                               &self.loader);
+
+        if let Some(start) = start {
+            self.record_timing(format!("command {}", name), start);
+        }
+
+        CommandResult::Handled(result)
+    }
+
+    /// Execute the global command an input not understood by the current
+    /// scenario is the beginning of (if any)
+    fn exec_prefix_command(&mut self, command: &str) -> CommandResult {
+        let candidates = self.prefix_matches(command);
+
+        if candidates.len() > 1 {
+            let message = format!("Did you mean: {}?", candidates.join(", "));
+            self.loader.borrow_mut().write(&message);
+
+            return CommandResult::Handled(NOTURN);
+        }
+
+        match candidates.first() {
+            Some(name) => {
+                self.exec_game_command(&complete_prefix(command, name))
+            },
+            None => { CommandResult::NotFound }
+        }
+    }
+
+    /// Execute the action of the current scenario
+    fn exec_current_scenario(&mut self, command: &str) -> i32 {
+        let start = self.start_timing();
+
+        let result = self.current.borrow().do_action(
+            &command.trim(),
+            &self.state,
+            &self.loader
+        );
+
+        if let Some(start) = start {
+            let key = format!("action {}", self.current_name());
+            self.record_timing(key, start);
+        }
+
+        result
+    }
+
+    /// Enable or disable timing calls to scenarios and commands
+    ///
+    /// Nothing is measured while disabled (the default). Enabling it again
+    /// keeps the times measured so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::QuitCommand;
+    /// use texture::master::GameMaster;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::util::{LOAD, TICK};
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         if command == "rest" {
+    ///             loader.borrow_mut().load_by_name("room");
+    ///             return LOAD;
+    ///         }
+    ///
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let room = Rc::new(RefCell::new(Room));
+    /// let mut gm = GameMaster::new(Rc::new(RefCell::new(())), room.clone());
+    /// gm.set_output(Box::new(texture::output::BufferOutput::new()));
+    /// gm.loader().borrow_mut().register("room".to_string(), room);
+    /// gm.add_command("quit".to_string(), Box::new(QuitCommand::new(None)));
+    ///
+    /// gm.step("wait");
+    /// assert!(gm.profiling_report().is_empty());
+    ///
+    /// gm.set_profiling(true);
+    /// gm.step("wait");
+    /// gm.step("rest");
+    /// gm.step("quit");
+    ///
+    /// let names: Vec<String> = gm.profiling_report()
+    ///     .into_iter()
+    ///     .map(|(name, _)| name)
+    ///     .collect();
+    ///
+    /// assert_eq!(names, vec!["action room", "command quit", "load room"]);
+    /// ```
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profiling = enabled;
+    }
+
+    /// Total time spent in each scenario method and command, sorted by name
+    ///
+    /// Names are `load <scenario>`, `action <scenario>` and
+    /// `command <name>`, where unregistered scenarios are `<unnamed>`.
+    pub fn profiling_report(&self) -> Vec<(String, Duration)> {
+        self.timings.iter()
+            .map(|(name, time)| (name.clone(), *time))
+            .collect()
+    }
+
+    /// Summary of the settings of the game, for bug reports
+    ///
+    /// Lists one `name: value` setting per line, always in the same order.
+    /// Handlers and hooks are shown as `set` or `unset`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::QuitCommand;
+    /// use texture::data::DataScenario;
+    /// use texture::master::GameMaster;
+    /// use texture::random::Rng;
+    /// use texture::state::{BaseState, BasicState};
+    ///
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    /// let room = DataScenario::new("A room.".to_string());
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(room)));
+    /// gm.add_command("quit".to_string(), Box::new(QuitCommand::new(None)));
+    /// gm.set_history_size(5);
+    /// gm.set_pager(24);
+    /// gm.loader().borrow_mut().set_rng(Rng::new(42));
+    ///
+    /// let report = gm.config_report();
+    ///
+    /// assert!(report.contains("commands: quit\n"));
+    /// assert!(report.contains("history size: 5\n"));
+    /// assert!(report.contains("page size: 24\n"));
+    /// assert!(report.contains("rng seed: 42\n"));
+    /// assert!(report.contains("status line: unset\n"));
+    /// assert_eq!(report, gm.config_report());
+    /// ```
+    pub fn config_report(&self) -> String {
+        fn list<'a, I: Iterator<Item = &'a String>>(items: I) -> String {
+            let mut items: Vec<&String> = items.collect();
+            items.sort();
+
+            if items.is_empty() {
+                "none".to_string()
+            } else {
+                items.iter()
+                    .map(|s| s.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            }
+        }
+
+        fn name(name: &Option<String>) -> String {
+            name.clone().unwrap_or_else(|| "none".to_string())
+        }
+
+        fn set(is_set: bool) -> &'static str {
+            if is_set { "set" } else { "unset" }
+        }
+
+        let mut abbreviations: Vec<String> = self.abbreviations.iter()
+            .map(|(short, long)| format!("{}={}", short, long))
+            .collect();
+        abbreviations.sort();
+
+        let echo = match self.echo_input {
+            Some(echo) => { echo.to_string() },
+            None => { "auto".to_string() }
+        };
+
+        let loader = self.loader.borrow();
+        let mut lines = Vec::new();
+
+        lines.push(format!("commands: {}", list(self.commands.keys())));
+        lines.push(format!("hidden commands: {}", list(self.hidden.iter())));
+        lines.push(format!("disabled commands: {}",
+                           list(self.disabled.iter())));
+        lines.push(format!("guarded commands: {}",
+                           list(self.guards.keys())));
+        lines.push(format!("modes: {}", list(self.mode_commands.keys())));
+        lines.push(format!("abbreviations: {}",
+                           list(abbreviations.iter())));
+        lines.push(format!("input transforms: {}",
+                           self.input_transforms.len()));
+        lines.push(format!("again commands: {}",
+                           list(self.again_commands.iter())));
+        lines.push(format!("help command: {}", name(&self.help_command)));
+        lines.push(format!("parse command: {}", name(&self.parse_command)));
+        lines.push(format!("history command: {}",
+                           name(&self.history_command)));
+        lines.push(format!("history size: {}", self.history_size));
+        lines.push(format!("dedupe repeats: {}", self.dedupe_repeats));
+        lines.push(format!("repeatable inputs: {}",
+                           list(self.repeatable.iter())));
+        lines.push(format!("prefix matching: {}", self.prefix_matching));
+        lines.push(format!("echo input: {}", echo));
+        lines.push(format!("queued input limit: {}",
+                           self.queued_input_limit));
+        lines.push(format!("checkpoint limit: {}", self.checkpoint_limit));
+        lines.push(format!("disabled message: {}",
+                           name(&self.disabled_message)));
+        lines.push(format!("intro lines: {}", self.intro.len()));
+        lines.push(format!("intro pause: {}", name(&self.intro_pause)));
+        lines.push(format!("profiling: {}", self.profiling));
+        lines.push(format!("description mode: {:?}",
+                           loader.description_mode()));
+        lines.push(format!("page size: {}", loader.page_size()));
+        lines.push(format!("save dir: {}", loader.save_dir()));
+        lines.push(format!("merge policy: {:?}", loader.merge_policy()));
+        lines.push(format!("rng seed: {}", loader.rng_seed()));
+        lines.push(format!("status line: {}", set(self.status_fn.is_some())));
+        lines.push(format!("start hook: {}", set(self.start_hook.is_some())));
+        lines.push(format!("end hook: {}", set(self.end_hook.is_some())));
+        lines.push(format!("game over hook: {}",
+                           set(self.game_over_hook.is_some())));
+        lines.push(format!("state diff: {}", set(self.diff_hook.is_some())));
+        lines.push(format!("unsaved tracking: {}",
+                           set(self.unsaved_tracking)));
+        lines.push(format!("respawn snapshots: {}",
+                           set(self.respawn_snapshot.is_some())));
+        lines.push(format!("repeat handler: {}",
+                           set(self.repeat_handler.is_some())));
+        lines.push(format!("unknown command handler: {}",
+                           set(self.unknown_handler.is_some())));
+        lines.push(format!("invalid code handler: {}",
+                           set(self.invalid_code_handler.is_some())));
+        lines.push(format!("transition error handler: {}",
+                           set(self.transition_error_handler.is_some())));
+        lines.push(format!("missing scenario handler: {}",
+                           set(loader.has_missing_scenario_handler())));
+
+        let mut report = lines.join("\n");
+        report.push('\n');
+
+        report
+    }
+
+    /// Run the inputs as fast as possible, returning the time taken
+    ///
+    /// Each input goes through `step()` with all the text discarded, so
+    /// there are no pauses (e.g. from a `TypewriterOutput`) nor flushes. The
+    /// output is restored afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         loader.borrow_mut().write("Nothing happens.");
+    ///
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Room)));
+    ///
+    /// let buffer = BufferOutput::new();
+    /// gm.set_output(Box::new(buffer.clone()));
+    ///
+    /// let inputs = vec!["jump".to_string(); 1000];
+    /// gm.bench(&inputs);
+    ///
+    /// assert_eq!(gm.turns(), 1000);
+    /// assert_eq!(buffer.contents(), "");
+    /// ```
+    pub fn bench(&mut self, inputs: &[String]) -> Duration {
+        let previous = self.loader.borrow_mut()
+            .replace_output(Box::new(NullOutput));
+
+        let start = Instant::now();
+
+        for input in inputs {
+            self.step(input);
+        }
+
+        let elapsed = start.elapsed();
+
+        self.loader.borrow_mut().replace_output(previous);
+
+        elapsed
+    }
+
+    /// Start measuring a call, if profiling
+    fn start_timing(&self) -> Option<Instant> {
+        if self.profiling {
+            Some(Instant::now())
+        } else {
+            None
+        }
+    }
+
+    /// Add the time elapsed since `start` to the given entry
+    fn record_timing(&mut self, key: String, start: Instant) {
+        let elapsed = start.elapsed();
+
+        *self.timings.entry(key).or_insert_with(Duration::default) += elapsed;
+    }
+
+    /// Name of the current scenario, as shown in reports
+    fn current_name(&self) -> String {
+        self.loader.borrow().name_of(&self.current)
+            .unwrap_or_else(|| "<unnamed>".to_string())
+    }
+
+    /// Write the status line (if any)
+    fn render_status(&mut self) {
+        let status = match self.status_fn {
+            Some(ref f) => { f(&self.state) },
+            None => { return }
+        };
+
+        self.loader.borrow_mut().write(&status);
+    }
+
+    /// Main game loop
+    fn main_loop(&mut self) {
+        // Setup linenoise
+        linenoise::set_multiline(0);
+
+        self.begin();
+
+        // Queued inputs processed since the player last typed
+        let mut queued_in_a_row = 0;
+
+        // Game loop, until input runs out
+        loop {
+            self.render_status();
+
+            // Pending text must be visible before the player types
+            self.loader.borrow_mut().flush();
+
+            // Get input
+            let prompt = match self.active_mode() {
+                Some(mode) => { format!("\n{}> ", mode) },
+                None => { "\n> ".to_string() }
+            };
+
+            let queued = if queued_in_a_row < self.queued_input_limit {
+                self.loader.borrow_mut().take_queued_input()
+            } else {
+                // Inputs keep queueing more inputs, stop feeding them
+                self.loader.borrow_mut().clear_queued_inputs();
+                None
+            };
+
+            let input = match queued {
+                Some(i) => {
+                    queued_in_a_row += 1;
+                    i
+                },
+                None => {
+                    queued_in_a_row = 0;
+
+                    match self.loader.borrow_mut().read_line(&prompt) {
+                        Some(i) => { i },
+                        None => { break }
+                    }
+                }
+            };
+
+            if queued_in_a_row == 0 && self.echoes_input() {
+                let line = format!("{}{}", prompt.trim_start(), input);
+                self.loader.borrow_mut().write(&line);
+            }
+
+            self.loader.borrow_mut().write(" ");
+
+            let result = self.exec_input(&input);
+
+            if result == QUIT || result == END {
+                break;
+            }
+        }
+    }
+}
+
+impl <S: Persistable> GameMaster <S> {
+    /// Store numbered save slots in the given directory
+    ///
+    /// See `Loader::set_save_dir()` and `enable_save_slots()`.
+    pub fn set_save_dir(&mut self, dir: String) {
+        self.loader.borrow_mut().set_save_dir(dir);
+    }
+
+    /// Register the `save <n>`, `load <n>` and `slots` commands
+    ///
+    /// Any commands already registered with those names are replaced. See
+    /// `SaveCommand`, `LoadCommand` and `SlotsCommand`, which may also be
+    /// registered under other names.
+    pub fn enable_save_slots(&mut self) {
+        self.add_command("save".to_string(), Box::new(SaveCommand));
+        self.add_command("load".to_string(), Box::new(LoadCommand));
+        self.add_command("slots".to_string(), Box::new(SlotsCommand));
+    }
+
+    /// Save the game state to the given file
+    ///
+    /// See `save_to_bytes()`.
+    pub fn save_game(&self, path: &str) -> Result<(), SaveError> {
+        let data = self.state.borrow().save_to_bytes()?;
+
+        let mut file = File::create(path)?;
+        file.write_all(&data)?;
+
+        self.loader.borrow_mut().mark_saved();
+
+        Ok(())
+    }
+
+    /// Restore the game state from the given file
+    ///
+    /// See `load_from_bytes()`.
+    pub fn load_game(&mut self, path: &str) -> Result<(), SaveError> {
+        let mut data = Vec::new();
+
+        let mut file = File::open(path)?;
+        file.read_to_end(&mut data)?;
+
+        self.load_from_bytes(&data)
+    }
+
+    /// Save the game state into bytes, without touching the filesystem
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::persist::{Persistable, SaveError};
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::util::TICK;
+    ///
+    /// struct Purse {
+    ///     gold: i32,
+    /// }
+    ///
+    /// impl Persistable for Purse {
+    ///     fn save(&self) -> Result<String, SaveError> {
+    ///         Ok(self.gold.to_string())
+    ///     }
+    ///
+    ///     fn restore(data: &str) -> Result<Purse, SaveError> {
+    ///         data.parse()
+    ///             .map(|gold| Purse { gold: gold })
+    ///             .map_err(|_| SaveError::Format(data.to_string()))
+    ///     }
+    /// }
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let state = Rc::new(RefCell::new(Purse { gold: 10 }));
+    /// let mut gm = GameMaster::new(state.clone(), Rc::new(RefCell::new(Room)));
+    ///
+    /// let data = gm.save_to_bytes().unwrap();
+    /// state.borrow_mut().gold = 0;
+    ///
+    /// gm.load_from_bytes(&data).unwrap();
+    /// assert_eq!(state.borrow().gold, 10);
+    ///
+    /// assert!(gm.load_from_bytes(b"lots").is_err());
+    /// assert!(gm.load_from_bytes(&[0xff, 0xfe]).is_err());
+    /// assert_eq!(state.borrow().gold, 10);
+    /// ```
+    pub fn save_to_bytes(&self) -> Result<Vec<u8>, SaveError> {
+        let data = self.state.borrow().save_to_bytes()?;
+
+        self.loader.borrow_mut().mark_saved();
+
+        Ok(data)
+    }
+
+    /// Restore the game state from bytes obtained with `save_to_bytes()`
+    ///
+    /// The current state is replaced in place, so scenarios and commands
+    /// holding the state keep seeing the restored values. Nothing changes
+    /// if the data is not valid.
+    pub fn load_from_bytes(&mut self, data: &[u8]) -> Result<(), SaveError> {
+        let restored = S::restore_from_bytes(data)?;
+        *self.state.borrow_mut() = restored;
+        self.loader.borrow_mut().mark_loaded();
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl <S: ::state::BaseState + 'static> GameMaster <S> {
+    /// Read the data files again, to see changes without restarting the
+    /// game
+    ///
+    /// See `Loader::reload_content()`. When the current scenario was read
+    /// from the files, the player is moved to its new version without
+    /// loading or describing it again. If it is no longer in the files, the
+    /// player goes back to the start scenario. Nothing changes if there is
+    /// any error, and all the errors found are returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::env;
+    /// use std::fs;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::data::DataScenario;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::state::{BaseState, BasicState};
+    ///
+    /// let name = format!("texture-reload-{}.json", std::process::id());
+    /// let path = env::temp_dir().join(name);
+    /// fs::write(&path, r#"{ "rooms": { "hall": {
+    ///     "description": "A hall."
+    /// } } }"#).unwrap();
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    /// let gate = DataScenario::new("An iron gate.".to_string());
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(gate)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.loader().borrow_mut()
+    ///     .load_and_merge(&[path.to_str().unwrap()])
+    ///     .unwrap();
+    /// gm.load_by_name("hall");
+    ///
+    /// // The author edits the description
+    /// fs::write(&path, r#"{ "rooms": { "hall": {
+    ///     "description": "A grand hall."
+    /// } } }"#).unwrap();
+    ///
+    /// assert!(gm.reload_content().is_ok());
+    ///
+    /// buffer.clear();
+    /// gm.redescribe();
+    /// assert_eq!(buffer.contents(), "A grand hall.\n");
+    ///
+    /// // Broken files are reported, keeping everything as it was
+    /// fs::write(&path, "{ oops").unwrap();
+    /// assert!(gm.reload_content().is_err());
+    /// assert!(gm.loader().borrow().is_registered("hall"));
+    ///
+    /// fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn reload_content(&mut self) -> Result<(), Vec<String>> {
+        let name = self.loader.borrow().current_scenario_name();
+
+        self.loader.borrow_mut().reload_content()?;
+
+        let name = match name {
+            Some(ref name) if self.loader.borrow().is_content(name) => {
+                name
+            },
+            Some(ref name) if !self.loader.borrow().is_registered(name) => {
+                self.loader.borrow_mut().write(
+                    "The current scenario no longer exists, going back to \
+                     the start.");
+                self.loader.borrow_mut().set_scenario(self.start.clone());
+                self.load_scenario();
+
+                return Ok(());
+            },
+            _ => { return Ok(()) }
+        };
+
+        let scenario = self.loader.borrow_mut().get_by_name(name);
+
+        if let Some(scenario) = scenario {
+            self.loader.borrow_mut().set_scenario(scenario);
+            self.current = self.loader.borrow_mut().promote();
+        }
+
+        Ok(())
+    }
+}
+
+/// Debugging of `BasicState` games
+impl GameMaster <BasicState> {
+    /// Check that the state has every key required by the scenarios
+    ///
+    /// The current scenario and every registered one (except those whose
+    /// factory was not called yet) are asked for their required keys (see
+    /// `Scenario::required_keys()`), which must be set as a flag, a value
+    /// or a list. Returns a description of each missing key, empty when
+    /// nothing is missing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// struct Crossroads;
+    ///
+    /// impl <S> Scenario <S> for Crossroads {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn required_keys(&self) -> Vec<String> {
+    ///         vec!["has_map".to_string(), "gold".to_string()]
+    ///     }
+    /// }
+    ///
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    /// state.borrow_mut().set_value("gold".to_string(), 0);
+    ///
+    /// let mut gm = GameMaster::new(state.clone(),
+    ///                              Rc::new(RefCell::new(Crossroads)));
+    /// gm.register_scenario("crossroads".to_string(),
+    ///                      Rc::new(RefCell::new(Crossroads)));
+    ///
+    /// assert_eq!(gm.validate_state_requirements(), vec![
+    ///     "scenario '<unnamed>' requires 'has_map', which is not set",
+    ///     "scenario 'crossroads' requires 'has_map', which is not set",
+    /// ]);
+    ///
+    /// state.borrow_mut().set_flag("has_map".to_string(), false);
+    /// assert!(gm.validate_state_requirements().is_empty());
+    /// ```
+    pub fn validate_state_requirements(&self) -> Vec<String> {
+        let mut scenarios = self.loader.borrow().registered_scenarios();
+
+        if !scenarios.iter().any(|&(_, ref s)| Rc::ptr_eq(s, &self.current)) {
+            scenarios.insert(0, (self.current_name(), self.current.clone()));
+        }
+
+        let state = self.state.borrow();
+        let mut problems = Vec::new();
+
+        for (name, scenario) in scenarios {
+            for key in scenario.borrow().required_keys() {
+                if state.contains_flag(&key) || state.contains_value(&key)
+                    || state.contains_list(&key) {
+                    continue;
+                }
+
+                problems.push(format!(
+                    "scenario '{}' requires '{}', which is not set", name, key));
+            }
+        }
+
+        problems
+    }
+
+    /// Start over from the start scenario, keeping some of the state
+    ///
+    /// Everything in the state is removed except the given flags, values
+    /// and lists (see `BasicState::clear_except()`), e.g. for a "new game
+    /// plus" keeping the name of the player and what they unlocked. The
+    /// turn counters, visited scenarios, modal scenarios, checkpoints and
+    /// scheduled events are reset too. The start scenario is then loaded
+    /// and described.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::WaitCommand;
+    /// use texture::data::{DataScenario, Exit};
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::state::{BaseState, BasicState};
+    ///
+    /// let mut village = DataScenario::new("A quiet village.".to_string());
+    /// village.add_exit("east".to_string(), Exit::new("forest".to_string()));
+    /// let forest = DataScenario::new("A dark forest.".to_string());
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state.clone(),
+    ///                              Rc::new(RefCell::new(village)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.add_command("wait".to_string(), Box::new(WaitCommand));
+    /// gm.register_scenario("forest".to_string(), Rc::new(RefCell::new(forest)));
+    ///
+    /// gm.step("east");
+    /// gm.step("wait");
+    /// state.borrow_mut().set_flag("hard_mode".to_string(), true);
+    /// state.borrow_mut().set_flag("has_sword".to_string(), true);
+    ///
+    /// buffer.clear();
+    /// gm.soft_reset(&["hard_mode"]);
+    ///
+    /// assert!(state.borrow().get_flag("hard_mode".to_string()));
+    /// assert!(!state.borrow().get_flag("has_sword".to_string()));
+    /// assert_eq!(gm.turns(), 0);
+    /// assert!(!gm.loader().borrow().has_visited("forest"));
+    /// assert!(buffer.contents().contains("A quiet village."));
+    /// ```
+    pub fn soft_reset(&mut self, keep: &[&str]) {
+        self.state.borrow_mut().clear_except(keep);
+        self.loader.borrow_mut().reset_progress();
+
+        self.turns = 0;
+        self.dwell = 0;
+        self.last_input = None;
+        self.modes.clear();
+        self.checkpoints.clear();
+        self.respawn_point = self.start.clone();
+        self.respawn_state = None;
+
+        self.loader.borrow_mut().set_scenario(self.start.clone());
+        self.load_scenario();
+    }
+
+    /// Write the changes to the state after each step (see
+    /// `BasicState::diff()`)
+    ///
+    /// Nothing is written for steps that do not change the state. Disabled
+    /// by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// struct Room;
+    ///
+    /// impl <S: BaseState> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         if command == "open door" {
+    ///             state.borrow_mut().set_flag("door_open".to_string(), true);
+    ///         }
+    ///
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Room)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.set_state_diff(true);
+    ///
+    /// gm.step("jump");
+    /// gm.step("open door");
+    ///
+    /// assert_eq!(buffer.contents(), "State changes:\n+ flag door_open\n");
+    /// ```
+    pub fn set_state_diff(&mut self, enabled: bool) {
+        if !enabled {
+            self.diff_hook = None;
+            return;
+        }
+
+        let previous = RefCell::new(self.state.borrow().snapshot());
+
+        self.diff_hook = Some(Box::new(move |state, loader| {
+            let current = state.borrow().snapshot();
+            let diff = previous.borrow().diff(&current);
+
+            if !diff.is_empty() {
+                let text = format!("State changes:\n{}", diff);
+                loader.borrow_mut().write(text.trim_end());
+            }
+
+            *previous.borrow_mut() = current;
+        }));
+    }
+
+    /// Keep track of whether the state changed since the game was last
+    /// saved or loaded
+    ///
+    /// A change observer (see `BasicState::on_change()`) marks the game as
+    /// having unsaved changes as soon as a flag or value changes, whether
+    /// during a step or not. Lists of text are not observed. Saving and loading (through the
+    /// built-in commands or `save_game()`/`load_game()`) mark it as saved,
+    /// and the observer is registered again on loaded states. See
+    /// `has_unsaved_changes()` and `QuitCommand::set_confirmation()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::{GameCommand, QuitCommand};
+    /// use texture::input::VecInput;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::{NOTURN, QUIT, TICK};
+    ///
+    /// struct Room;
+    ///
+    /// impl <S: BaseState> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         state.borrow_mut().set_flag("lamp_lit".to_string(), true);
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let mut quit = QuitCommand::new(None);
+    /// quit.set_confirmation(Some("Really quit? ".to_string()));
+    ///
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state.clone(), Rc::new(RefCell::new(Room)));
+    /// gm.set_output(Box::new(BufferOutput::new()));
+    /// gm.set_input(Box::new(VecInput::new(vec!["no".to_string()])));
+    /// gm.add_command("quit".to_string(), Box::new(quit));
+    /// gm.track_unsaved_changes();
+    ///
+    /// // Clean: quits right away
+    /// assert_eq!(gm.step("quit"), QUIT);
+    ///
+    /// // Dirty: asks, and the player changes their mind
+    /// gm.step("light lamp");
+    /// assert!(gm.has_unsaved_changes());
+    /// assert_eq!(gm.step("quit"), NOTURN);
+    ///
+    /// #[cfg(feature = "serde")]
+    /// {
+    ///     let name = format!("texture-unsaved-{}.json", std::process::id());
+    ///     let path = std::env::temp_dir().join(name);
+    ///     let path = path.to_str().unwrap();
+    ///
+    ///     gm.save_game(path).unwrap();
+    ///     assert!(!gm.has_unsaved_changes());
+    ///
+    ///     // Changes made outside a step are noticed right away
+    ///     state.borrow_mut().set_flag("lamp_lit".to_string(), false);
+    ///     assert!(gm.has_unsaved_changes());
+    ///
+    ///     // Also after loading a game
+    ///     gm.load_game(path).unwrap();
+    ///     assert!(!gm.has_unsaved_changes());
+    ///     state.borrow_mut().set_value("gold".to_string(), 3);
+    ///     assert!(gm.has_unsaved_changes());
+    /// #   std::fs::remove_file(path).unwrap();
+    /// }
+    /// ```
+    pub fn track_unsaved_changes(&mut self) {
+        let unsaved = self.loader.borrow().unsaved_flag();
+        watch_changes(&self.state, unsaved.clone());
+
+        // Loaded states come without observers
+        let state = self.state.clone();
+
+        self.loader.borrow_mut().set_load_observer(Box::new(move || {
+            watch_changes(&state, unsaved.clone());
+        }));
+
+        self.unsaved_tracking = true;
+    }
+}
+
+/// Mark the game as having unsaved changes whenever the state changes
+fn watch_changes(state: &Rc<RefCell<BasicState>>, unsaved: Rc<Cell<bool>>) {
+    state.borrow_mut().on_change(Box::new(move |_, _| unsaved.set(true)));
+}
+
+/// In-memory checkpoints
+///
+/// Snapshots are full copies of the state, hence the `S: Clone` bound.
+impl <S: Clone> GameMaster <S> {
+    /// Take a snapshot of the state, current scenario and turn counters
+    ///
+    /// Only the most recent snapshots are kept (10 by default), older ones
+    /// are discarded.
+    pub fn checkpoint(&mut self) {
+        if self.checkpoint_limit == 0 {
+            return;
+        }
+
+        while self.checkpoints.len() >= self.checkpoint_limit {
+            self.checkpoints.pop_front();
+        }
+
+        let snapshot = self.state.borrow().clone();
+        self.checkpoints.push_back(
+            (snapshot, self.current.clone(), self.turns, self.dwell));
+    }
+
+    /// Restore the most recent snapshot, removing it
+    ///
+    /// The state is replaced in place and the scenario is not loaded again.
+    /// The number of turns played and spent in the scenario go back to
+    /// their values at the time of the snapshot. Returns `false` if there
+    /// was nothing to restore.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::util::TICK;
+    ///
+    /// #[derive(Clone)]
+    /// struct MyState {
+    ///     door_open: bool
+    /// }
+    ///
+    /// struct Room;
+    ///
+    /// impl Scenario <MyState> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<MyState>>,
+    ///             loader: &Rc<RefCell<Loader<MyState>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<MyState>>,
+    ///                  loader: &Rc<RefCell<Loader<MyState>>>)
+    ///                  -> i32 {
+    ///         if command == "open door" {
+    ///             state.borrow_mut().door_open = true;
+    ///         }
+    ///
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let state = Rc::new(RefCell::new(MyState { door_open: false }));
+    /// let mut gm = GameMaster::new(state.clone(), Rc::new(RefCell::new(Room)));
+    ///
+    /// gm.checkpoint();
+    /// gm.step("open door");
+    /// assert!(state.borrow().door_open);
+    /// assert_eq!(gm.turns(), 1);
+    ///
+    /// assert!(gm.undo());
+    /// assert!(!state.borrow().door_open);
+    /// assert_eq!(gm.turns(), 0);
+    /// assert_eq!(gm.dwell_turns(), 0);
+    ///
+    /// // Nothing left to undo
+    /// assert!(!gm.undo());
+    /// ```
+    pub fn undo(&mut self) -> bool {
+        let (snapshot, scenario, turns, dwell) =
+            match self.checkpoints.pop_back() {
+                Some(c) => { c },
+                None => { return false }
+            };
+
+        *self.state.borrow_mut() = snapshot;
+        self.loader.borrow_mut().set_scenario(scenario);
+        self.current = self.loader.borrow_mut().promote();
+        self.turns = turns;
+        self.dwell = dwell;
+
+        true
+    }
+
+    /// Restore the state when respawning (see `respawn()`)
+    ///
+    /// When enabled, a copy of the state is taken each time the player
+    /// enters a checkpoint, and respawning restores it. Disabled by default.
+    pub fn set_respawn_snapshots(&mut self, enabled: bool) {
+        self.respawn_snapshot = if enabled {
+            Some(S::clone)
+        } else {
+            None
+        };
+
+        if !enabled {
+            self.respawn_state = None;
+        }
+    }
+
+    /// Set the maximum number of snapshots kept
+    pub fn set_checkpoint_limit(&mut self, limit: usize) {
+        self.checkpoint_limit = limit;
+
+        while self.checkpoints.len() > limit {
+            self.checkpoints.pop_front();
+        }
+    }
+}
+
+/// Abbreviations known by default
+fn default_abbreviations() -> HashMap<String, String> {
+    let defaults = [
+        ("n", "north"), ("s", "south"), ("e", "east"), ("w", "west"),
+        ("ne", "northeast"), ("nw", "northwest"),
+        ("se", "southeast"), ("sw", "southwest"),
+        ("u", "up"), ("d", "down"),
+    ];
+
+    defaults.iter()
+        .map(|&(short, long)| (short.to_string(), long.to_string()))
+        .collect()
 }
@@ -0,0 +1,494 @@
+// MIT License
+//
+// Copyright (c) 2016 Rafael Medina García <rafamedgar@gmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::cell::RefCell;
+use std::env;
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "unicode")]
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Destination of the text shown to the player
+///
+/// The engine and scenarios write through the output installed in the
+/// `Loader`, so games can be displayed in something other than the terminal
+/// (or captured in tests).
+pub trait Output {
+    /// Write a piece of text as is
+    fn write(&mut self, text: &str);
+
+    /// Write a line of text
+    fn write_line(&mut self, text: &str) {
+        self.write(text);
+        self.write("\n");
+    }
+
+    /// Write a line of text centered in the given width
+    ///
+    /// The width is limited to the terminal width (see `terminal_width()`).
+    /// Text wider than that is written as is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::output::{BufferOutput, Output};
+    ///
+    /// let mut output = BufferOutput::new();
+    /// output.write_centered("ZORK", 10);
+    ///
+    /// assert_eq!(output.contents(), "   ZORK\n");
+    /// ```
+    fn write_centered(&mut self, text: &str, width: usize) {
+        let width = width.min(terminal_width());
+        let length = char_units(text).len();
+        let padding = width.saturating_sub(length) / 2;
+
+        self.write(&" ".repeat(padding));
+        self.write_line(text);
+    }
+
+    /// Write the lines inside a box
+    ///
+    /// The box is as wide as the longest line, but never wider than the
+    /// terminal (see `terminal_width()`); longer lines are split.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::output::{BufferOutput, Output};
+    ///
+    /// let mut output = BufferOutput::new();
+    /// output.write_boxed(&["West of House", "Score: 0"]);
+    ///
+    /// assert_eq!(output.contents(), "\
+    /// +---------------+
+    /// | West of House |
+    /// | Score: 0      |
+    /// +---------------+
+    /// ");
+    /// ```
+    fn write_boxed(&mut self, lines: &[&str]) {
+        let max = terminal_width().saturating_sub(4).max(1);
+
+        let rows: Vec<String> = lines.iter()
+            .flat_map(|line| {
+                let units = char_units(line);
+
+                if units.is_empty() {
+                    return vec![String::new()];
+                }
+
+                units.chunks(max)
+                    .map(|chunk| chunk.concat())
+                    .collect::<Vec<String>>()
+            })
+            .collect();
+
+        let width = rows.iter()
+            .map(|row| char_units(row).len())
+            .max()
+            .unwrap_or(0);
+
+        let border = format!("+{}+", "-".repeat(width + 2));
+
+        self.write_line(&border);
+
+        for row in &rows {
+            let padding = width - char_units(row).len();
+            self.write_line(&format!("| {}{} |", row, " ".repeat(padding)));
+        }
+
+        self.write_line(&border);
+    }
+
+    /// Make sure everything written so far is shown
+    ///
+    /// Called by the game loop right before reading input, so prompts are
+    /// not hidden in a buffer. Does nothing by default.
+    fn flush(&mut self) {
+    }
+}
+
+/// Function transforming a line of text before it is written
+pub type OutputFilter = Box<Fn(String) -> String>;
+
+/// Obtain the width of the terminal, in characters
+///
+/// Taken from the `COLUMNS` environment variable, or 80 if it is not set.
+pub fn terminal_width() -> usize {
+    match env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        Some(width) if width > 0 => { width },
+        _ => { 80 }
+    }
+}
+
+/// Output printing to the standard output
+pub struct StdoutOutput;
+
+impl Output for StdoutOutput {
+    fn write(&mut self, text: &str) {
+        print!("{}", text);
+    }
+
+    fn flush(&mut self) {
+        let _ = io::stdout().flush();
+    }
+}
+
+/// Output storing all the text in memory
+///
+/// Clones share the same buffer, so a clone can be installed in the game
+/// while the original is used to inspect what was written.
+///
+/// # Examples
+///
+/// ```
+/// use texture::output::{BufferOutput, Output};
+///
+/// let buffer = BufferOutput::new();
+/// let mut output = buffer.clone();
+///
+/// output.write_line("Hello");
+/// output.write_line("World");
+///
+/// assert_eq!(buffer.contents(), "Hello\nWorld\n");
+/// ```
+#[derive(Clone)]
+pub struct BufferOutput {
+    buffer: Rc<RefCell<String>>,
+}
+
+impl Default for BufferOutput {
+    fn default() -> BufferOutput {
+        BufferOutput::new()
+    }
+}
+
+impl BufferOutput {
+    /// Create an empty buffer
+    pub fn new() -> BufferOutput {
+        BufferOutput { buffer: Rc::new(RefCell::new(String::new())) }
+    }
+
+    /// Obtain a copy of everything written so far
+    pub fn contents(&self) -> String {
+        self.buffer.borrow().clone()
+    }
+
+    /// Remove everything written so far
+    pub fn clear(&self) {
+        self.buffer.borrow_mut().clear();
+    }
+}
+
+impl Output for BufferOutput {
+    fn write(&mut self, text: &str) {
+        self.buffer.borrow_mut().push_str(text);
+    }
+}
+
+/// Output discarding all the text
+///
+/// Useful when only the effects of the input matter, e.g. when
+/// benchmarking.
+pub struct NullOutput;
+
+impl Output for NullOutput {
+    fn write(&mut self, _text: &str) {
+    }
+}
+
+/// Output forwarding all the text to several outputs
+///
+/// Useful to show text to the player while also keeping a log.
+///
+/// # Examples
+///
+/// ```
+/// use texture::output::{BufferOutput, Output, TeeOutput};
+///
+/// let screen = BufferOutput::new();
+/// let log = BufferOutput::new();
+///
+/// let mut output = TeeOutput::new(vec![Box::new(screen.clone()),
+///                                      Box::new(log.clone())]);
+/// output.write_line("You are in a maze.");
+///
+/// assert_eq!(screen.contents(), "You are in a maze.\n");
+/// assert_eq!(log.contents(), "You are in a maze.\n");
+/// ```
+pub struct TeeOutput {
+    outputs: Vec<Box<Output>>,
+}
+
+impl TeeOutput {
+    /// Forward text to the given outputs, in order
+    pub fn new(outputs: Vec<Box<Output>>) -> TeeOutput {
+        TeeOutput { outputs: outputs }
+    }
+
+    /// Forward text to one more output
+    pub fn push(&mut self, output: Box<Output>) {
+        self.outputs.push(output);
+    }
+}
+
+impl Output for TeeOutput {
+    fn write(&mut self, text: &str) {
+        for output in &mut self.outputs {
+            output.write(text);
+        }
+    }
+
+    fn flush(&mut self) {
+        for output in &mut self.outputs {
+            output.flush();
+        }
+    }
+}
+
+/// Output showing text one character at a time, like a typewriter
+///
+/// Text is split into characters (grapheme clusters with the `unicode`
+/// feature), never into bytes, so multi-byte characters are written whole.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use texture::output::{BufferOutput, Output, TypewriterOutput};
+///
+/// let buffer = BufferOutput::new();
+/// let mut output = TypewriterOutput::new(buffer.clone(), Duration::from_millis(0));
+///
+/// output.write_line("Café «ñandú» ☕ 👩‍👩‍👧");
+///
+/// assert_eq!(buffer.contents(), "Café «ñandú» ☕ 👩‍👩‍👧\n");
+/// ```
+pub struct TypewriterOutput<O: Output> {
+    // Output receiving each character
+    inner: O,
+    // Pause after each character
+    delay: Duration,
+}
+
+impl <O: Output> TypewriterOutput<O> {
+    /// Wrap an output, pausing for `delay` after each character
+    pub fn new(inner: O, delay: Duration) -> TypewriterOutput<O> {
+        TypewriterOutput {
+            inner: inner,
+            delay: delay,
+        }
+    }
+}
+
+impl <O: Output> Output for TypewriterOutput<O> {
+    fn write(&mut self, text: &str) {
+        for unit in char_units(text) {
+            self.inner.write(unit);
+
+            if self.delay > Duration::from_millis(0) {
+                // Each character has to be visible before pausing
+                self.inner.flush();
+                thread::sleep(self.delay);
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        self.inner.flush();
+    }
+}
+
+/// Something that happened in the game, for frontends that do not show
+/// plain text
+#[derive(Clone, Debug, PartialEq)]
+pub enum OutputEvent {
+    /// A line of text was written (after the output filters)
+    Message(String),
+    /// A scenario was entered, with the name it was registered with
+    RoomChanged(String),
+    /// An item was added to the inventory by `TakeCommand`
+    ItemGained(String),
+    /// A turn went by, with the number of turns played so far
+    TurnEnded(u64),
+}
+
+/// Destination of structured events
+///
+/// Installed in the `Loader` alongside the plain text output (see
+/// `Loader::set_structured_output()`). To receive only events, install a
+/// `NullOutput` as the text output.
+pub trait StructuredOutput {
+    /// Receive an event
+    fn emit(&mut self, event: OutputEvent);
+}
+
+/// Structured output storing all the events in memory
+///
+/// Clones share the same list, like `BufferOutput`.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::data::{DataScenario, Exit};
+/// use texture::master::GameMaster;
+/// use texture::output::{EventBuffer, NullOutput, OutputEvent};
+/// use texture::state::{BaseState, BasicState};
+///
+/// let mut hall = DataScenario::new("A long hall.".to_string());
+/// hall.add_exit("north".to_string(), Exit::new("garden".to_string()));
+///
+/// let garden = DataScenario::new("A walled garden.".to_string());
+///
+/// let events = EventBuffer::new();
+/// let state = Rc::new(RefCell::new(BasicState::new()));
+///
+/// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(hall)));
+/// gm.set_output(Box::new(NullOutput));
+/// gm.loader().borrow_mut().set_structured_output(Box::new(events.clone()));
+/// gm.loader().borrow_mut()
+///     .register("garden".to_string(), Rc::new(RefCell::new(garden)));
+///
+/// gm.step("north");
+///
+/// assert_eq!(events.events(), vec![
+///     OutputEvent::Message(" ".to_string()),
+///     OutputEvent::RoomChanged("garden".to_string()),
+///     OutputEvent::Message("A walled garden.".to_string()),
+/// ]);
+/// ```
+#[derive(Clone)]
+pub struct EventBuffer {
+    events: Rc<RefCell<Vec<OutputEvent>>>,
+}
+
+impl Default for EventBuffer {
+    fn default() -> EventBuffer {
+        EventBuffer::new()
+    }
+}
+
+impl EventBuffer {
+    /// Create an empty list of events
+    pub fn new() -> EventBuffer {
+        EventBuffer { events: Rc::new(RefCell::new(Vec::new())) }
+    }
+
+    /// Obtain a copy of the events received so far
+    pub fn events(&self) -> Vec<OutputEvent> {
+        self.events.borrow().clone()
+    }
+
+    /// Remove the events received so far
+    pub fn clear(&self) {
+        self.events.borrow_mut().clear();
+    }
+}
+
+impl StructuredOutput for EventBuffer {
+    fn emit(&mut self, event: OutputEvent) {
+        self.events.borrow_mut().push(event);
+    }
+}
+
+/// Build a message from a template with named numbers
+///
+/// The template may contain:
+///
+/// - `{name}`: replaced by the number
+/// - `{name|plural}`: replaced by `plural` unless the number is 1
+/// - `{name|singular|plural}`: replaced by `singular` if the number is 1 and
+///   by `plural` otherwise
+///
+/// Placeholders with unknown names are kept as they are.
+///
+/// # Examples
+///
+/// ```
+/// use texture::output::format_msg;
+///
+/// assert_eq!(format_msg("You have {n} coin{n|s}", &[("n", 1)]),
+///            "You have 1 coin");
+/// assert_eq!(format_msg("You have {n} coin{n|s}", &[("n", 3)]),
+///            "You have 3 coins");
+/// assert_eq!(format_msg("{n} {n|child|children} and {m} {m|mouse|mice}",
+///                       &[("n", 2), ("m", 1)]),
+///            "2 children and 1 mouse");
+/// assert_eq!(format_msg("{unknown}", &[]), "{unknown}");
+/// ```
+pub fn format_msg(template: &str, values: &[(&str, i32)]) -> String {
+    let mut result = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(e) => { start + e },
+            None => { break }
+        };
+
+        result.push_str(&rest[..start]);
+
+        let placeholder = &rest[start + 1..end];
+        let parts: Vec<&str> = placeholder.split('|').collect();
+
+        let value = values.iter()
+            .find(|&&(name, _)| name == parts[0])
+            .map(|&(_, value)| value);
+
+        match (value, parts.len()) {
+            (Some(v), 1) => { result.push_str(&v.to_string()) },
+            (Some(v), 2) => { if v != 1 { result.push_str(parts[1]) } },
+            (Some(v), 3) => {
+                result.push_str(if v == 1 { parts[1] } else { parts[2] })
+            },
+            _ => { result.push_str(&rest[start..end + 1]) }
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    result.push_str(rest);
+
+    result
+}
+
+/// Split text into the smallest pieces that can be written on their own
+#[cfg(not(feature = "unicode"))]
+fn char_units(text: &str) -> Vec<&str> {
+    text.char_indices()
+        .map(|(i, c)| &text[i..i + c.len_utf8()])
+        .collect()
+}
+
+/// Split text into the smallest pieces that can be written on their own
+#[cfg(feature = "unicode")]
+fn char_units(text: &str) -> Vec<&str> {
+    text.graphemes(true).collect()
+}
@@ -0,0 +1,161 @@
+// MIT License
+//
+// Copyright (c) 2016 Rafael Medina García <rafamedgar@gmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::str;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+#[cfg(feature = "serde")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "serde")]
+use serde_json;
+
+/// Error obtained when saving or restoring a game
+#[derive(Debug)]
+pub enum SaveError {
+    /// The save could not be read or written
+    Io(io::Error),
+    /// The saved data could not be produced or understood
+    Format(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SaveError::Io(ref e) => { write!(f, "save I/O error: {}", e) },
+            SaveError::Format(ref e) => { write!(f, "invalid save data: {}", e) }
+        }
+    }
+}
+
+impl Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(e: io::Error) -> SaveError {
+        SaveError::Io(e)
+    }
+}
+
+/// State that can be saved and restored
+///
+/// Required by `GameMaster::save_game()` and `GameMaster::load_game()`. With
+/// the `serde` feature enabled, every type implementing `Serialize` and
+/// `DeserializeOwned` implements this trait automatically.
+pub trait Persistable {
+    /// Serialize the state into a string
+    fn save(&self) -> Result<String, SaveError>;
+
+    /// Build a new state from previously saved data
+    fn restore(data: &str) -> Result<Self, SaveError> where Self: Sized;
+
+    /// Serialize the state into bytes, e.g. to keep it without a filesystem
+    fn save_to_bytes(&self) -> Result<Vec<u8>, SaveError> {
+        self.save().map(|data| data.into_bytes())
+    }
+
+    /// Build a new state from data previously saved with `save_to_bytes()`
+    fn restore_from_bytes(data: &[u8]) -> Result<Self, SaveError>
+        where Self: Sized {
+        match str::from_utf8(data) {
+            Ok(data) => { Self::restore(data) },
+            Err(e) => { Err(SaveError::Format(e.to_string())) }
+        }
+    }
+}
+
+/// Store states as JSON
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// extern crate texture;
+///
+/// use std::cell::RefCell;
+/// use std::env;
+/// use std::fs;
+/// use std::rc::Rc;
+///
+/// use serde::{Deserialize, Serialize};
+///
+/// use texture::master::GameMaster;
+/// use texture::scenario::{Loader, Scenario};
+/// use texture::util::TICK;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct MyState {
+///     gold: i32,
+///     lamp_lit: bool
+/// }
+///
+/// struct Room;
+///
+/// impl <S> Scenario <S> for Room {
+///     fn load(&self, state: &Rc<RefCell<S>>,
+///             loader: &Rc<RefCell<Loader<S>>>)
+///             -> i32 {
+///         TICK
+///     }
+///
+///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+///                  loader: &Rc<RefCell<Loader<S>>>)
+///                  -> i32 {
+///         TICK
+///     }
+/// }
+///
+/// fn main() {
+///     let state = Rc::new(RefCell::new(MyState { gold: 10, lamp_lit: true }));
+///     let mut gm = GameMaster::new(state.clone(), Rc::new(RefCell::new(Room)));
+///
+///     let name = format!("texture-persist-{}.json", std::process::id());
+///     let path = env::temp_dir().join(name);
+///     let path = path.to_str().unwrap();
+///
+///     gm.save_game(path).unwrap();
+///
+///     state.borrow_mut().gold = 0;
+///     state.borrow_mut().lamp_lit = false;
+///
+///     gm.load_game(path).unwrap();
+///
+///     assert_eq!(state.borrow().gold, 10);
+///     assert!(state.borrow().lamp_lit);
+///
+///     fs::remove_file(path).unwrap();
+/// }
+/// ```
+#[cfg(feature = "serde")]
+impl <T: Serialize + DeserializeOwned> Persistable for T {
+    fn save(&self) -> Result<String, SaveError> {
+        serde_json::to_string(self)
+            .map_err(|e| SaveError::Format(e.to_string()))
+    }
+
+    fn restore(data: &str) -> Result<T, SaveError> {
+        serde_json::from_str(data)
+            .map_err(|e| SaveError::Format(e.to_string()))
+    }
+}
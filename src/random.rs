@@ -0,0 +1,325 @@
+// MIT License
+//
+// Copyright (c) 2016 Rafael Medina García <rafamedgar@gmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// Seeded pseudo-random number generator
+///
+/// Simple xorshift generator meant for game logic (loot, encounters...),
+/// **not** for anything requiring cryptographic strength. Using the same
+/// seed always produces the same sequence, which makes games reproducible.
+pub struct Rng {
+    state: u64,
+    seed: u64,
+}
+
+impl Rng {
+    /// Create a new generator from the given seed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::random::Rng;
+    ///
+    /// let mut a = Rng::new(42);
+    /// let mut b = Rng::new(42);
+    ///
+    /// assert_eq!(a.next_u64(), b.next_u64());
+    /// ```
+    pub fn new(seed: u64) -> Rng {
+        // Xorshift gets stuck on zero
+        let state = if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed };
+
+        Rng { state: state, seed: seed }
+    }
+
+    /// Obtain the seed the generator was created with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Obtain the next random 64-bit number
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Obtain a random number in the range `[0, max)`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::random::Rng;
+    ///
+    /// let mut rng = Rng::new(7);
+    ///
+    /// for _ in 0..100 {
+    ///     assert!(rng.below(6) < 6);
+    /// }
+    /// ```
+    pub fn below(&mut self, max: u64) -> u64 {
+        assert!(max > 0, "upper bound must be greater than zero");
+
+        self.next_u64() % max
+    }
+}
+
+/// Error obtained when picking from an unusable table
+#[derive(Debug, PartialEq)]
+pub enum TableError {
+    /// The table has no entries
+    Empty,
+    /// All the entries in the table have a weight of zero
+    ZeroWeight,
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TableError::Empty => {
+                write!(f, "cannot pick from an empty table")
+            },
+            TableError::ZeroWeight => {
+                write!(f, "cannot pick from a table where all weights are zero")
+            }
+        }
+    }
+}
+
+impl Error for TableError {}
+
+/// Table of outcomes picked at random according to their weights
+///
+/// Useful for loot drops or random encounters: an entry with weight 3 is
+/// three times as likely to be picked as an entry with weight 1.
+pub struct WeightedTable<T> {
+    // Items and their weights
+    entries: Vec<(T, u32)>,
+    // Sum of all weights
+    total: u64,
+}
+
+impl <T> Default for WeightedTable<T> {
+    fn default() -> WeightedTable<T> {
+        WeightedTable::new()
+    }
+}
+
+impl <T> WeightedTable<T> {
+    /// Create an empty table
+    pub fn new() -> WeightedTable<T> {
+        WeightedTable {
+            entries: Vec::new(),
+            total: 0,
+        }
+    }
+
+    /// Add an item with the given weight
+    ///
+    /// Items with a weight of zero are kept, but never picked.
+    pub fn add(&mut self, item: T, weight: u32) {
+        self.entries.push((item, weight));
+        self.total += weight as u64;
+    }
+
+    /// Number of items in the table
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether the table has no items
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Pick an item at random according to the weights
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::random::{Rng, TableError, WeightedTable};
+    ///
+    /// let mut rng = Rng::new(1234);
+    ///
+    /// // Single entry is always picked
+    /// let mut single = WeightedTable::new();
+    /// single.add("sword", 5);
+    /// assert_eq!(single.pick(&mut rng), Ok(&"sword"));
+    ///
+    /// // Zero-weight tables are rejected
+    /// let mut broken = WeightedTable::new();
+    /// broken.add("nothing", 0);
+    /// assert_eq!(broken.pick(&mut rng), Err(TableError::ZeroWeight));
+    ///
+    /// // Picks follow the weights
+    /// let mut loot = WeightedTable::new();
+    /// loot.add("gold", 3);
+    /// loot.add("gem", 1);
+    ///
+    /// let mut gold = 0;
+    /// for _ in 0..10000 {
+    ///     if *loot.pick(&mut rng).unwrap() == "gold" {
+    ///         gold += 1;
+    ///     }
+    /// }
+    ///
+    /// assert!(gold > 7200 && gold < 7800);
+    /// ```
+    pub fn pick(&self, rng: &mut Rng) -> Result<&T, TableError> {
+        if self.entries.is_empty() {
+            return Err(TableError::Empty);
+        }
+
+        if self.total == 0 {
+            return Err(TableError::ZeroWeight);
+        }
+
+        let mut roll = rng.below(self.total);
+
+        for &(ref item, weight) in &self.entries {
+            if roll < weight as u64 {
+                return Ok(item);
+            }
+
+            roll -= weight as u64;
+        }
+
+        unreachable!()
+    }
+}
+
+/// Generator of random text from templates and word lists
+///
+/// Templates contain placeholders such as `{noun}` or `{adjective}`, each
+/// replaced by a random word from the list with that name. Placeholders
+/// without a list are kept as they are. Useful for flavor text like the
+/// names of random characters.
+///
+/// # Examples
+///
+/// ```
+/// use texture::random::{Generator, Rng};
+///
+/// let mut generator = Generator::new();
+/// generator.add_template("A {adjective} {noun}".to_string());
+/// generator.add_template("The {noun} of the {place}".to_string());
+/// generator.add_words("adjective", vec!["rusty".to_string(),
+///                                      "gleaming".to_string()]);
+/// generator.add_words("noun", vec!["sword".to_string(),
+///                                 "lantern".to_string()]);
+///
+/// let mut a = Rng::new(99);
+/// let mut b = Rng::new(99);
+///
+/// let first: Vec<String> = (0..5)
+///     .map(|_| generator.generate(&mut a).unwrap())
+///     .collect();
+/// let second: Vec<String> = (0..5)
+///     .map(|_| generator.generate(&mut b).unwrap())
+///     .collect();
+///
+/// assert_eq!(first, second);
+///
+/// for text in &first {
+///     assert!(text.starts_with("A ") || text.ends_with(" of the {place}"));
+///     assert!(text.contains("sword") || text.contains("lantern"));
+/// }
+/// ```
+pub struct Generator {
+    // Texts with placeholders
+    templates: Vec<String>,
+    // Words replacing each placeholder, by placeholder name
+    words: HashMap<String, Vec<String>>,
+}
+
+impl Default for Generator {
+    fn default() -> Generator {
+        Generator::new()
+    }
+}
+
+impl Generator {
+    /// Create a generator without templates or words
+    pub fn new() -> Generator {
+        Generator {
+            templates: Vec::new(),
+            words: HashMap::new(),
+        }
+    }
+
+    /// Add a template, picked with the same chance as the others
+    pub fn add_template(&mut self, template: String) {
+        self.templates.push(template);
+    }
+
+    /// Add words to the list replacing the given placeholder
+    pub fn add_words(&mut self, placeholder: &str, words: Vec<String>) {
+        self.words.entry(placeholder.to_string())
+            .or_insert_with(Vec::new)
+            .extend(words);
+    }
+
+    /// Generate a text from a random template
+    ///
+    /// Returns `None` if there are no templates.
+    pub fn generate(&self, rng: &mut Rng) -> Option<String> {
+        if self.templates.is_empty() {
+            return None;
+        }
+
+        let index = rng.below(self.templates.len() as u64) as usize;
+        let mut rest = self.templates[index].as_str();
+        let mut text = String::new();
+
+        while let Some(start) = rest.find('{') {
+            let end = match rest[start..].find('}') {
+                Some(end) => { start + end },
+                None => { break }
+            };
+
+            text.push_str(&rest[..start]);
+
+            match self.words.get(&rest[start + 1..end]) {
+                Some(words) if !words.is_empty() => {
+                    let word = rng.below(words.len() as u64) as usize;
+                    text.push_str(&words[word]);
+                },
+                _ => { text.push_str(&rest[start..end + 1]) }
+            }
+
+            rest = &rest[end + 1..];
+        }
+
+        text.push_str(rest);
+
+        Some(text)
+    }
+}
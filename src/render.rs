@@ -0,0 +1,196 @@
+// MIT License
+//
+// Copyright (c) 2016 Rafael Medina García <rafamedgar@gmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use state::BaseState;
+
+/// Function receiving the problems found while rendering
+pub type RenderLogger = Box<Fn(&str)>;
+
+/// Renders descriptions containing placeholders that depend on the state
+///
+/// Placeholders are enclosed in braces:
+///
+/// - `{value:gold}`: replaced by the integer value
+/// - `{flag:door_open?open:closed}`: replaced by `open` if the flag is set,
+///   and by `closed` otherwise. The second part may be omitted (e.g.
+///   `{flag:lit?The lamp glows.}`).
+///
+/// Unknown placeholders, as well as flags and values that were never set
+/// (see `BaseState::contains_flag()`), render as empty text and are
+/// reported to the logger. Problems are ignored by default; they can be
+/// written to the standard error with `log_to_stderr()`.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::render::Renderer;
+/// use texture::state::{BaseState, BasicState};
+///
+/// let state = Rc::new(RefCell::new(BasicState::new()));
+/// state.borrow_mut().set_flag("door_open".to_string(), true);
+/// state.borrow_mut().set_flag("lamp_lit".to_string(), false);
+/// state.borrow_mut().set_value("gold".to_string(), 12);
+///
+/// let problems = Rc::new(RefCell::new(Vec::new()));
+/// let record = problems.clone();
+///
+/// let mut renderer = Renderer::new();
+/// renderer.set_logger(Box::new(move |problem| {
+///     record.borrow_mut().push(problem.to_string());
+/// }));
+///
+/// assert_eq!(
+///     renderer.render_description(
+///         "The door is {flag:door_open?open:closed}.", &state),
+///     "The door is open.");
+/// assert_eq!(
+///     renderer.render_description(
+///         "The lamp is {flag:lamp_lit?lit:dark}.{flag:lamp_lit? It hums.}",
+///         &state),
+///     "The lamp is dark.");
+/// assert_eq!(
+///     renderer.render_description("You have {value:gold} coins.", &state),
+///     "You have 12 coins.");
+/// assert_eq!(
+///     renderer.render_description("A {colour:sky} sky{.", &state),
+///     "A  sky{.");
+///
+/// // Missing keys
+/// assert_eq!(
+///     renderer.render_description(
+///         "[{value:silver}] [{flag:window_open?open:shut}]", &state),
+///     "[] []");
+///
+/// assert_eq!(*problems.borrow(), vec![
+///     "unknown placeholder '{colour:sky}'".to_string(),
+///     "unknown value 'silver'".to_string(),
+///     "unknown flag 'window_open'".to_string(),
+/// ]);
+/// ```
+pub struct Renderer {
+    logger: RenderLogger,
+}
+
+impl Default for Renderer {
+    fn default() -> Renderer {
+        Renderer::new()
+    }
+}
+
+impl Renderer {
+    /// Create a renderer ignoring the problems found
+    pub fn new() -> Renderer {
+        Renderer {
+            logger: Box::new(|_| {}),
+        }
+    }
+
+    /// Replace the function receiving the problems found while rendering
+    pub fn set_logger(&mut self, logger: RenderLogger) {
+        self.logger = logger;
+    }
+
+    /// Write the problems found while rendering to the standard error
+    pub fn log_to_stderr(&mut self) {
+        self.logger = Box::new(|problem| eprintln!("render: {}", problem));
+    }
+
+    /// Replace the placeholders in the template using the state
+    pub fn render_description<S: BaseState>(&self, template: &str,
+                                            state: &Rc<RefCell<S>>)
+                                            -> String {
+        let mut result = String::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            let end = match rest[start..].find('}') {
+                Some(e) => { start + e },
+                None => { break }
+            };
+
+            result.push_str(&rest[..start]);
+
+            let placeholder = &rest[start + 1..end];
+
+            match self.substitute(placeholder, &*state.borrow()) {
+                Some(text) => { result.push_str(&text) },
+                None => {
+                    (self.logger)(&format!("unknown placeholder '{{{}}}'",
+                                           placeholder));
+                }
+            }
+
+            rest = &rest[end + 1..];
+        }
+
+        result.push_str(rest);
+
+        result
+    }
+
+    /// Obtain the text for a placeholder, if it is valid
+    ///
+    /// Missing keys are logged and render as empty text.
+    fn substitute<S: BaseState>(&self, placeholder: &str, state: &S)
+                                -> Option<String> {
+        if let Some(name) = placeholder.strip_prefix("value:") {
+            if !state.contains_value(name) {
+                (self.logger)(&format!("unknown value '{}'", name));
+                return Some(String::new());
+            }
+
+            return Some(state.get_value(name.to_string()).to_string());
+        }
+
+        if let Some(condition) = placeholder.strip_prefix("flag:") {
+            let question = condition.find('?')?;
+
+            let name = &condition[..question];
+            let choices = &condition[question + 1..];
+
+            if !state.contains_flag(name) {
+                (self.logger)(&format!("unknown flag '{}'", name));
+                return Some(String::new());
+            }
+
+            let (then, otherwise) = match choices.find(':') {
+                Some(colon) => { (&choices[..colon], &choices[colon + 1..]) },
+                None => { (choices, "") }
+            };
+
+            return if state.get_flag(name.to_string()) {
+                Some(then.to_string())
+            } else {
+                Some(otherwise.to_string())
+            };
+        }
+
+        None
+    }
+}
@@ -20,21 +20,300 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::mem;
 use std::rc::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use clock::{Clock, SystemClock};
+use command::GameCommand;
+use cue::{CuePlayer, NullCuePlayer};
+use data::MergePolicy;
+use input::{self, Input};
+use output::{BufferOutput, Output, OutputEvent, OutputFilter, StdoutOutput,
+             StructuredOutput};
+use random::{Generator, Rng, WeightedTable};
+use util::{NOTURN, TICK, UNKNOWN};
+
+/// Apply the output filters to a text, in the order they were added
+fn apply_filters(filters: &[OutputFilter], text: &str) -> String {
+    let mut line = text.to_string();
+
+    for filter in filters {
+        line = filter(line);
+    }
+
+    line
+}
+
+/// Output applying the output filters to every line, used when inputs
+/// write to the player
+struct FilteredOutput<'a> {
+    output: &'a mut Output,
+    filters: &'a [OutputFilter],
+}
+
+impl <'a> FilteredOutput<'a> {
+    fn new(output: &'a mut Output, filters: &'a [OutputFilter])
+           -> FilteredOutput<'a> {
+        FilteredOutput { output, filters }
+    }
+}
+
+impl <'a> Output for FilteredOutput<'a> {
+    fn write(&mut self, text: &str) {
+        self.output.write(text);
+    }
+
+    fn write_line(&mut self, text: &str) {
+        self.output.write_line(&apply_filters(self.filters, text));
+    }
+
+    fn flush(&mut self) {
+        self.output.flush();
+    }
+}
+
+/// Closure building a scenario on demand
+pub type ScenarioFactory<S> = Box<Fn() -> Rc<RefCell<Scenario<S>>>>;
+
+/// Function called when a scenario name cannot be resolved
+///
+/// Receives the loader and the name that was requested.
+pub type MissingScenarioHandler<S> = Box<Fn(&mut Loader<S>, &str)>;
+
+/// Function called when a scheduled event fires
+///
+/// Returning `LOAD` transitions to the scenario set in the loader.
+pub type ScheduledEvent<S> = Box<Fn(&Rc<RefCell<S>>, &Rc<RefCell<Loader<S>>>)
+                                    -> i32>;
+
+/// Identifier of a scheduled event
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EventId(u64);
+
+/// Change to the global commands requested through the loader
+pub enum CommandRequest<S> {
+    /// Add (or replace) a global command
+    Add(String, Box<GameCommand<S>>),
+    /// Remove a global command
+    Remove(String),
+    /// Enter an input mode (see `GameMaster::push_mode()`)
+    PushMode(String),
+    /// Leave the innermost input mode
+    PopMode,
+}
+
+/// How scenarios are described when entered
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DescriptionMode {
+    /// Always show the full description
+    Verbose,
+    /// Show the short description of registered scenarios visited before
+    Brief,
+    /// Always show the short description
+    SuperBrief,
+}
 
 /// Scenario loader
+///
+/// Scenarios do not load each other directly. Instead, a scenario (or
+/// command) sets the *pending* scenario during `do_action()`, through
+/// `set_scenario()`, `load_by_name()` and similar methods, and then returns
+/// `LOAD`. The game master then promotes the pending scenario to *current*,
+/// loads and describes it. Until then, the scenario that requested the
+/// transition is still the current one, and setting another pending
+/// scenario simply replaces the previous request.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::master::GameMaster;
+/// use texture::scenario::{Loader, Scenario};
+/// use texture::state::{BaseState, BasicState};
+/// use texture::util::{LOAD, TICK};
+///
+/// struct Room(&'static str, Rc<RefCell<Vec<Option<String>>>>);
+///
+/// impl <S> Scenario <S> for Room {
+///     fn load(&self, state: &Rc<RefCell<S>>,
+///             loader: &Rc<RefCell<Loader<S>>>)
+///             -> i32 {
+///         TICK
+///     }
+///
+///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+///                  loader: &Rc<RefCell<Loader<S>>>)
+///                  -> i32 {
+///         let mut loader = loader.borrow_mut();
+///         loader.load_by_name(self.0);
+///
+///         // Requested, but not loaded yet
+///         self.1.borrow_mut().push(loader.pending_scenario_name());
+///         self.1.borrow_mut().push(loader.current_scenario_name());
+///
+///         LOAD
+///     }
+/// }
+///
+/// let probe = Rc::new(RefCell::new(Vec::new()));
+/// let hall = Rc::new(RefCell::new(Room("garden", probe.clone())));
+/// let state = Rc::new(RefCell::new(BasicState::new()));
+///
+/// let mut gm = GameMaster::new(state, hall.clone());
+/// gm.set_output(Box::new(texture::output::BufferOutput::new()));
+///
+/// let loader = gm.loader();
+/// loader.borrow_mut().register("hall".to_string(), hall);
+/// loader.borrow_mut().register(
+///     "garden".to_string(), Rc::new(RefCell::new(Room("hall", probe.clone()))));
+///
+/// gm.step("go");
+///
+/// assert_eq!(*probe.borrow(),
+///            vec![Some("garden".to_string()), Some("hall".to_string())]);
+/// assert_eq!(loader.borrow().pending_scenario_name(), None);
+/// assert_eq!(loader.borrow().current_scenario_name(),
+///            Some("garden".to_string()));
+
+/// // The loaded scenario is kept as the last one set
+/// let garden = loader.borrow_mut().get_by_name("garden").unwrap();
+/// assert!(Rc::ptr_eq(&loader.borrow().get_scenario(), &garden));
+/// assert!(!loader.borrow().has_pending_scenario());
+/// ```
 pub struct Loader<S> {
-    pub scenario: Option<Rc<RefCell<Scenario<S>>>>
+    /// Scenario last set to load (if any)
+    pub scenario: Option<Rc<RefCell<Scenario<S>>>>,
+    // Whether the scenario to load was set after the last promotion
+    pending: bool,
+    // Scenario currently loaded by the game master
+    current: Option<Rc<RefCell<Scenario<S>>>>,
+    // Payload for the scenario to load
+    arg: Option<String>,
+    // Scenarios to return to, most recent last
+    stack: Vec<Rc<RefCell<Scenario<S>>>>,
+    // Whether the scenario to load is being returned to
+    returning: bool,
+    // Scenarios available by name
+    registry: HashMap<String, Rc<RefCell<Scenario<S>>>>,
+    // Names of the registered scenarios, by address
+    names: HashMap<usize, String>,
+    // Scenarios built the first time they are requested
+    factories: HashMap<String, ScenarioFactory<S>>,
+    // Free-form data attached to registered scenarios, by name
+    metadata: HashMap<String, BTreeMap<String, String>>,
+    // Data files the scenarios were read from
+    content_paths: Vec<String>,
+    // Names of the scenarios read from data files
+    content: HashSet<String>,
+    // Where text shown to the player is written
+    output: Box<Output>,
+    // Transformations applied to every line written
+    filters: Vec<OutputFilter>,
+    // Lines written before waiting for the player (0 to never wait)
+    page_size: usize,
+    // Lines written since the player last typed something
+    paged_lines: usize,
+    // Where text typed by the player is read from
+    input: Box<Input>,
+    // Player of sound/asset cues
+    cues: Box<CuePlayer>,
+    // Ending reached (if any), with its text
+    ending: Option<(String, Option<String>)>,
+    // Receiver of structured events (if any)
+    structured: Option<Box<StructuredOutput>>,
+    // Changes to global commands, applied after the turn
+    command_requests: Vec<CommandRequest<S>>,
+    // Inputs processed by the game loop before reading from the player
+    queued_inputs: VecDeque<String>,
+    // Called when a scenario name cannot be resolved
+    missing_handler: Option<MissingScenarioHandler<S>>,
+    // Whether a scenario name could not be resolved during this turn
+    missing_reported: bool,
+    // Generator used for random transitions
+    rng: Rng,
+    // How scenarios are described when entered
+    description_mode: DescriptionMode,
+    // Directory where numbered save slots are stored
+    save_dir: String,
+    // Whether the state changed since the game was last saved or loaded
+    unsaved: Rc<Cell<bool>>,
+    // Called when a saved game replaces the state
+    load_observer: Option<Box<Fn()>>,
+    // What to do when merged data files define the same scenario
+    merge_policy: MergePolicy,
+    // Names of the scenarios entered so far
+    visited: HashSet<String>,
+    // Canonical object names by synonym
+    synonyms: HashMap<String, String>,
+    // Turns played, as last reported by the game master
+    turn: u64,
+    // Identifier for the next scheduled event
+    next_event: u64,
+    // Scheduled events and the turn they fire at
+    events: BTreeMap<EventId, (u64, ScheduledEvent<S>)>,
+    // Source of the real time elapsed
+    clock: Box<Clock>,
+    // Scheduled events and the elapsed time they fire at
+    timed_events: BTreeMap<EventId, (Duration, ScheduledEvent<S>)>,
 }
 
 impl <S> Loader<S> {
     /// Initialize empty loader
     pub fn new() -> Loader<S> {
-        Loader { scenario: None }
+        Loader {
+            scenario: None,
+            pending: false,
+            current: None,
+            arg: None,
+            stack: Vec::new(),
+            returning: false,
+            registry: HashMap::new(),
+            names: HashMap::new(),
+            factories: HashMap::new(),
+            metadata: HashMap::new(),
+            content_paths: Vec::new(),
+            content: HashSet::new(),
+            output: Box::new(StdoutOutput),
+            filters: Vec::new(),
+            page_size: 0,
+            paged_lines: 0,
+            input: input::default_input(),
+            cues: Box::new(NullCuePlayer),
+            ending: None,
+            structured: None,
+            command_requests: Vec::new(),
+            queued_inputs: VecDeque::new(),
+            missing_handler: None,
+            missing_reported: false,
+            rng: Rng::new(time_seed()),
+            description_mode: DescriptionMode::Verbose,
+            save_dir: ".".to_string(),
+            unsaved: Rc::new(Cell::new(false)),
+            load_observer: None,
+            merge_policy: MergePolicy::Reject,
+            visited: HashSet::new(),
+            synonyms: HashMap::new(),
+            turn: 0,
+            next_event: 0,
+            events: BTreeMap::new(),
+            clock: Box::new(SystemClock::new()),
+            timed_events: BTreeMap::new(),
+        }
     }
 
     /// Get scenario to load
+    ///
+    /// The scenario is kept after being loaded, so this is the current
+    /// scenario unless another one is pending.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no scenario was ever set.
     pub fn get_scenario(&self) -> Rc<RefCell<Scenario<S>>> {
         self.scenario.clone().unwrap().clone()
     }
@@ -42,6 +321,1554 @@ impl <S> Loader<S> {
     /// Set scenario to load
     pub fn set_scenario(&mut self, scenario: Rc<RefCell<Scenario<S>>>) {
         self.scenario = Some(scenario.clone());
+        self.pending = true;
+        self.arg = None;
+        self.returning = false;
+        self.missing_reported = false;
+    }
+
+    /// Set scenario to load, passing it a payload
+    ///
+    /// The payload can be obtained once with `take_arg()` while the
+    /// scenario is being loaded, and is discarded afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::util::{LOAD, TICK};
+    ///
+    /// struct Ledge;
+    /// struct Infirmary;
+    ///
+    /// impl <S> Scenario <S> for Ledge {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         let infirmary = Rc::new(RefCell::new(Infirmary));
+    ///
+    ///         if command == "jump" {
+    ///             loader.borrow_mut()
+    ///                 .set_scenario_with_arg(infirmary, "wounded".to_string());
+    ///         } else {
+    ///             loader.borrow_mut().set_scenario(infirmary);
+    ///         }
+    ///
+    ///         LOAD
+    ///     }
+    /// }
+    ///
+    /// impl <S> Scenario <S> for Infirmary {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         let mut loader = loader.borrow_mut();
+    ///
+    ///         match loader.take_arg() {
+    ///             Some(how) => { loader.write(&format!("You arrive {}.", how)) },
+    ///             None => { loader.write("You arrive.") }
+    ///         }
+    ///
+    ///         // Only delivered once
+    ///         assert_eq!(loader.take_arg(), None);
+    ///
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         loader.borrow_mut().set_scenario(Rc::new(RefCell::new(Ledge)));
+    ///         LOAD
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let mut gm = GameMaster::new(Rc::new(RefCell::new(())),
+    ///                              Rc::new(RefCell::new(Ledge)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    ///
+    /// gm.step("jump");
+    /// gm.step("leave");
+    /// gm.step("climb");
+    ///
+    /// assert_eq!(buffer.contents(), " \nYou arrive wounded.\n \n \nYou arrive.\n");
+    /// ```
+    pub fn set_scenario_with_arg(&mut self,
+                                 scenario: Rc<RefCell<Scenario<S>>>,
+                                 arg: String) {
+        self.set_scenario(scenario);
+        self.arg = Some(arg);
+    }
+
+    /// Obtain the payload given to the scenario being loaded (if any)
+    ///
+    /// Returns `None` after the first call.
+    pub fn take_arg(&mut self) -> Option<String> {
+        self.arg.take()
+    }
+
+    /// Discard the payload of the scenario that was just loaded
+    ///
+    /// A payload for a newly requested scenario is kept.
+    pub(crate) fn clear_arg(&mut self) {
+        if !self.has_pending_scenario() {
+            self.arg = None;
+        }
+    }
+
+    /// Set a modal scenario to load, remembering the current one
+    ///
+    /// Once the modal scenario is done, `pop_scenario()` returns to the
+    /// scenario that was current when this was called. Modal scenarios may
+    /// be nested.
+    pub fn push_scenario(&mut self, scenario: Rc<RefCell<Scenario<S>>>) {
+        if let Some(current) = self.current.clone() {
+            self.stack.push(current);
+        }
+
+        self.set_scenario(scenario);
+    }
+
+    /// Set the scenario to load back to the one a modal scenario was pushed
+    /// from
+    ///
+    /// The scenario returned to is neither loaded nor described again.
+    /// Returns `false` if no modal scenario was pushed.
+    pub fn pop_scenario(&mut self) -> bool {
+        match self.stack.pop() {
+            Some(scenario) => {
+                self.set_scenario(scenario);
+                self.returning = true;
+                true
+            },
+            None => { false }
+        }
+    }
+
+    /// Check whether the scenario to load is being returned to, clearing
+    /// the mark
+    pub(crate) fn take_returning(&mut self) -> bool {
+        let returning = self.returning;
+        self.returning = false;
+
+        returning
+    }
+
+    /// Check whether a scenario name could not be resolved since the last
+    /// check, clearing the mark
+    pub(crate) fn take_missing_reported(&mut self) -> bool {
+        let reported = self.missing_reported;
+        self.missing_reported = false;
+
+        reported
+    }
+
+    /// Obtain the scenario currently loaded by the game master (if any)
+    pub fn current_scenario(&self) -> Option<Rc<RefCell<Scenario<S>>>> {
+        self.current.clone()
+    }
+
+    /// Check whether a scenario was set to be loaded next
+    pub fn has_pending_scenario(&self) -> bool {
+        match self.scenario {
+            Some(ref scenario) => {
+                self.pending || !self.is_current(scenario)
+            },
+            None => { false }
+        }
+    }
+
+    /// Name of the pending scenario, if any and registered
+    pub fn pending_scenario_name(&self) -> Option<String> {
+        match self.scenario {
+            Some(ref scenario) if self.has_pending_scenario() => {
+                self.name_of(scenario)
+            },
+            _ => { None }
+        }
+    }
+
+    /// Name of the current scenario, if registered
+    pub fn current_scenario_name(&self) -> Option<String> {
+        match self.current {
+            Some(ref scenario) => { self.name_of(scenario) },
+            None => { None }
+        }
+    }
+
+    /// Mark the pending scenario as the current one, returning it
+    ///
+    /// When there is no pending scenario, the current one is returned so it
+    /// is loaded again.
+    pub(crate) fn promote(&mut self) -> Rc<RefCell<Scenario<S>>> {
+        let scenario = match self.scenario {
+            Some(ref s) if self.has_pending_scenario() => { s.clone() },
+            _ => { self.current.clone().expect("no scenario to load") }
+        };
+
+        self.scenario = Some(scenario.clone());
+        self.pending = false;
+        self.current = Some(scenario.clone());
+
+        scenario
+    }
+
+    /// Check whether the given scenario is the one currently loaded
+    fn is_current(&self, scenario: &Rc<RefCell<Scenario<S>>>) -> bool {
+        match self.current {
+            Some(ref current) => { Rc::ptr_eq(current, scenario) },
+            None => { false }
+        }
+    }
+
+    /// Obtain the name the given scenario was registered with (if any)
+    pub(crate) fn name_of(&self, scenario: &Rc<RefCell<Scenario<S>>>)
+                          -> Option<String> {
+        self.names.get(&address(scenario)).cloned()
+    }
+
+    /// Add a scenario to the registry, keeping track of its name
+    fn insert_scenario(&mut self, name: String,
+                       scenario: Rc<RefCell<Scenario<S>>>) -> bool {
+        let replaced = self.remove_scenario(&name);
+
+        self.names.insert(address(&scenario), name.clone());
+        self.registry.insert(name, scenario);
+
+        replaced
+    }
+
+    /// Remove a scenario from the registry, forgetting its name
+    fn remove_scenario(&mut self, name: &str) -> bool {
+        let scenario = match self.registry.remove(name) {
+            Some(s) => { s },
+            None => { return false }
+        };
+
+        let key = address(&scenario);
+
+        if self.names.get(&key).map_or(false, |n| n == name) {
+            self.names.remove(&key);
+
+            // The same scenario may be registered with another name
+            let other = self.registry.iter()
+                .find(|&(_, s)| Rc::ptr_eq(s, &scenario))
+                .map(|(n, _)| n.clone());
+
+            if let Some(other) = other {
+                self.names.insert(key, other);
+            }
+        }
+
+        true
+    }
+
+    /// Check whether the scenario with the given name has been entered
+    ///
+    /// Only entering a scenario while it is registered counts as a visit;
+    /// scenarios set directly without a name are not tracked.
+    pub fn has_visited(&self, name: &str) -> bool {
+        self.visited.contains(name)
+    }
+
+    /// Obtain the names of the scenarios entered so far, sorted
+    pub fn visited(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.visited.iter().cloned().collect();
+        names.sort();
+
+        names
+    }
+
+    /// Remember that a scenario has been entered
+    ///
+    /// Returns `true` if it was the first time.
+    pub(crate) fn mark_visited(&mut self, name: String) -> bool {
+        self.visited.insert(name)
+    }
+
+    /// Obtain how scenarios are described when entered
+    pub fn description_mode(&self) -> DescriptionMode {
+        self.description_mode
+    }
+
+    /// Change how scenarios are described when entered
+    pub fn set_description_mode(&mut self, mode: DescriptionMode) {
+        self.description_mode = mode;
+    }
+
+    /// Obtain the directory where numbered save slots are stored
+    pub fn save_dir(&self) -> &str {
+        &self.save_dir
+    }
+
+    /// Change the directory where numbered save slots are stored
+    ///
+    /// Defaults to the working directory.
+    pub fn set_save_dir(&mut self, dir: String) {
+        self.save_dir = dir;
+    }
+
+    /// Check whether the state changed since the game was last saved or
+    /// loaded
+    ///
+    /// Only tracked when enabled in the game master (see
+    /// `GameMaster::track_unsaved_changes()`). Used by `QuitCommand` to
+    /// decide whether to ask for confirmation.
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.unsaved.get()
+    }
+
+    /// Mark the state as changed since the game was last saved
+    pub fn mark_unsaved(&mut self) {
+        self.unsaved.set(true);
+    }
+
+    /// Mark the state as matching a saved game, after saving
+    pub fn mark_saved(&mut self) {
+        self.unsaved.set(false);
+    }
+
+    /// Mark the state as matching a saved game, after it was replaced by
+    /// a loaded one
+    pub fn mark_loaded(&mut self) {
+        self.unsaved.set(false);
+
+        if let Some(ref observer) = self.load_observer {
+            observer();
+        }
+    }
+
+    /// Shared flag telling whether there are unsaved changes
+    pub(crate) fn unsaved_flag(&self) -> Rc<Cell<bool>> {
+        self.unsaved.clone()
+    }
+
+    /// Set the function called when a loaded game replaces the state
+    pub(crate) fn set_load_observer(&mut self, observer: Box<Fn()>) {
+        self.load_observer = Some(observer);
+    }
+
+    /// Call a function once `delay` more turns have been played
+    ///
+    /// Events fire at the end of the turn, before the current scenario is
+    /// notified (see `Scenario::on_dwell()`), in the order they were
+    /// scheduled. A delay of zero is treated as one turn.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::util::TICK;
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let mut gm = GameMaster::new(Rc::new(RefCell::new(())),
+    ///                              Rc::new(RefCell::new(Room)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    ///
+    /// let loader = gm.loader();
+    /// let bomb = loader.borrow_mut().schedule(3, Box::new(|_, loader| {
+    ///     loader.borrow_mut().write("BOOM!");
+    ///     TICK
+    /// }));
+    /// let bell = loader.borrow_mut().schedule(2, Box::new(|_, loader| {
+    ///     loader.borrow_mut().write("The bell tolls.");
+    ///     TICK
+    /// }));
+    ///
+    /// assert_eq!(loader.borrow().pending_events(), vec![(bell, 2), (bomb, 3)]);
+    ///
+    /// gm.step("wait");
+    ///
+    /// // The bomb gets defused
+    /// assert!(loader.borrow_mut().cancel(bomb));
+    /// assert!(!loader.borrow_mut().cancel(bomb));
+    ///
+    /// gm.step("wait");
+    /// gm.step("wait");
+    /// gm.step("wait");
+    ///
+    /// assert_eq!(buffer.contents(), "The bell tolls.\n");
+    /// assert!(!loader.borrow_mut().cancel(bell));
+    /// assert!(loader.borrow().pending_events().is_empty());
+    /// ```
+    pub fn schedule(&mut self, delay: u64, event: ScheduledEvent<S>)
+                    -> EventId {
+        let id = EventId(self.next_event);
+        self.next_event += 1;
+
+        let fire = self.turn + if delay == 0 { 1 } else { delay };
+        self.events.insert(id, (fire, event));
+
+        id
+    }
+
+    /// Schedule an event to fire at the end of the given turn
+    ///
+    /// Unlike `schedule()`, the turn is absolute, so it may already be in
+    /// the past (e.g. when rebuilding the schedule after loading a game).
+    /// Such events fire at the end of the next turn, or right away with
+    /// `GameMaster::process_due_events()`.
+    pub fn schedule_at(&mut self, turn: u64, event: ScheduledEvent<S>)
+                       -> EventId {
+        let id = EventId(self.next_event);
+        self.next_event += 1;
+
+        self.events.insert(id, (turn, event));
+
+        id
+    }
+
+    /// Prevent a scheduled event from firing
+    ///
+    /// Returns `false` if the event does not exist or already fired.
+    pub fn cancel(&mut self, id: EventId) -> bool {
+        self.events.remove(&id).is_some()
+            || self.timed_events.remove(&id).is_some()
+    }
+
+    /// Scheduled events and the turn they fire at, in firing order
+    pub fn pending_events(&self) -> Vec<(EventId, u64)> {
+        let mut pending: Vec<(EventId, u64)> = self.events.iter()
+            .map(|(id, &(fire, _))| (*id, fire))
+            .collect();
+
+        pending.sort_by_key(|&(id, fire)| (fire, id));
+
+        pending
+    }
+
+    /// Forget the progress of the current game: visited scenarios, modal
+    /// scenarios, the ending, scheduled events and queued inputs
+    ///
+    /// Registered scenarios, command requests and settings are kept.
+    pub(crate) fn reset_progress(&mut self) {
+        self.pending = false;
+        self.arg = None;
+        self.stack.clear();
+        self.returning = false;
+        self.missing_reported = false;
+        self.ending = None;
+        self.queued_inputs.clear();
+        self.visited.clear();
+        self.turn = 0;
+        self.events.clear();
+        self.timed_events.clear();
+    }
+
+    /// Update the number of turns played, returning the events due
+    pub(crate) fn advance_turn(&mut self, turn: u64) -> Vec<ScheduledEvent<S>> {
+        self.turn = turn;
+
+        let due: Vec<EventId> = self.pending_events().into_iter()
+            .take_while(|&(_, fire)| fire <= turn)
+            .map(|(id, _)| id)
+            .collect();
+
+        due.into_iter()
+            .filter_map(|id| self.events.remove(&id))
+            .map(|(_, event)| event)
+            .collect()
+    }
+
+    /// Replace the source of real time (see `schedule_after()`)
+    pub fn set_clock(&mut self, clock: Box<Clock>) {
+        self.clock = clock;
+    }
+
+    /// Call a function once the given real time has elapsed
+    ///
+    /// The game loop waits for input, so these events are only checked
+    /// when an input is processed (whether it takes a turn or not): an
+    /// event fires with the first input received after its time. Events
+    /// returning `LOAD` load the scenario set in the loader. Can be
+    /// cancelled with `cancel()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use std::time::Duration;
+    ///
+    /// use texture::clock::ManualClock;
+    /// use texture::command::WaitCommand;
+    /// use texture::data::DataScenario;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// let clock = ManualClock::new();
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    /// let hall = DataScenario::new("A long hall.".to_string());
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(hall)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.add_command("wait".to_string(), Box::new(WaitCommand));
+    ///
+    /// let loader = gm.loader();
+    /// loader.borrow_mut().set_clock(Box::new(clock.clone()));
+    /// let fuse = loader.borrow_mut().schedule_after(
+    ///     Duration::from_secs(30),
+    ///     Box::new(|_, loader| {
+    ///         loader.borrow_mut().write("The fuse burns out.");
+    ///         TICK
+    ///     }));
+    ///
+    /// assert_eq!(loader.borrow().pending_timed_events(),
+    ///            vec![(fuse, Duration::from_secs(30))]);
+    ///
+    /// clock.advance(Duration::from_secs(10));
+    /// gm.step("wait");
+    ///
+    /// clock.advance(Duration::from_secs(20));
+    /// gm.step("wait");
+    ///
+    /// assert_eq!(buffer.contents(),
+    ///            "Time passes.\nTime passes.\nThe fuse burns out.\n");
+    /// assert!(loader.borrow().pending_timed_events().is_empty());
+    /// ```
+    pub fn schedule_after(&mut self, delay: Duration, event: ScheduledEvent<S>)
+                          -> EventId {
+        let id = EventId(self.next_event);
+        self.next_event += 1;
+
+        let fire = self.clock.elapsed() + delay;
+        self.timed_events.insert(id, (fire, event));
+
+        id
+    }
+
+    /// Obtain the events scheduled after a real time, with the elapsed time
+    /// they fire at
+    ///
+    /// Sorted by the time they fire at, then by the order they were
+    /// scheduled in.
+    pub fn pending_timed_events(&self) -> Vec<(EventId, Duration)> {
+        let mut pending: Vec<(EventId, Duration)> = self.timed_events.iter()
+            .map(|(id, &(fire, _))| (*id, fire))
+            .collect();
+
+        pending.sort_by_key(|&(id, fire)| (fire, id));
+
+        pending
+    }
+
+    /// Remove the events whose time has come, in the order they fire in
+    pub(crate) fn due_timed_events(&mut self) -> Vec<ScheduledEvent<S>> {
+        let now = self.clock.elapsed();
+
+        let due: Vec<EventId> = self.pending_timed_events().into_iter()
+            .take_while(|&(_, fire)| fire <= now)
+            .map(|(id, _)| id)
+            .collect();
+
+        due.into_iter()
+            .filter_map(|id| self.timed_events.remove(&id))
+            .map(|(_, event)| event)
+            .collect()
+    }
+
+    /// Make a word refer to an object by another name
+    ///
+    /// Commands resolving object names (e.g. `ExamineCommand` and
+    /// `TakeCommand`) look objects up by their canonical name. Synonyms are
+    /// not case sensitive.
+    pub fn add_synonym(&mut self, canonical: &str, synonym: &str) {
+        self.synonyms.insert(synonym.to_lowercase(), canonical.to_lowercase());
+    }
+
+    /// Obtain the canonical name of an object, in lowercase
+    ///
+    /// Names without synonyms are their own canonical name.
+    pub fn resolve_object(&self, name: &str) -> String {
+        let name = name.to_lowercase();
+
+        match self.synonyms.get(&name) {
+            Some(canonical) => { canonical.clone() },
+            None => { name }
+        }
+    }
+
+    /// Replace the output used to show text to the player
+    pub fn set_output(&mut self, output: Box<Output>) {
+        self.output = output;
+    }
+
+    /// Install a new output, returning the previous one
+    pub(crate) fn replace_output(&mut self, output: Box<Output>) -> Box<Output> {
+        mem::replace(&mut self.output, output)
+    }
+
+    /// Write a line of text to the player
+    ///
+    /// The output filters are applied first, in the order they were added.
+    pub fn write(&mut self, text: &str) {
+        let line = apply_filters(&self.filters, text);
+
+        if self.page_size > 0 && self.input.is_interactive() {
+            for part in line.split('\n') {
+                if self.paged_lines >= self.page_size {
+                    self.wait_for_more();
+                }
+
+                self.output.write_line(part);
+                self.paged_lines += 1;
+            }
+        } else {
+            self.output.write_line(&line);
+        }
+
+        self.emit(OutputEvent::Message(line));
+    }
+
+    /// Wait for the player after a full page of text
+    ///
+    /// Once enabled, `write()` stops after writing the given number of lines
+    /// without the player typing anything, shows `--More--` and waits for
+    /// the player to press enter. Only interactive inputs are waited for
+    /// (see `Input::is_interactive()`). 0 (the default) disables it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::input::{Input, VecInput};
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::Loader;
+    ///
+    /// let buffer = BufferOutput::new();
+    ///
+    /// let mut loader: Loader<()> = Loader::new();
+    /// loader.set_output(Box::new(buffer.clone()));
+    /// loader.set_input(Box::new(VecInput::new(vec![
+    ///     "".to_string(),
+    ///     "".to_string(),
+    ///     "look".to_string(),
+    /// ])));
+    /// loader.set_pager(2);
+    ///
+    /// loader.write("One\nTwo\nThree");
+    /// loader.write("Four\nFive");
+    ///
+    /// assert_eq!(buffer.contents(),
+    ///            "One\nTwo\n--More--\nThree\nFour\n--More--\nFive\n");
+    ///
+    /// // Both pauses read from the input
+    /// assert_eq!(loader.read_line("> "), Some("look".to_string()));
+    /// ```
+    pub fn set_pager(&mut self, lines: usize) {
+        self.page_size = lines;
+        self.paged_lines = 0;
+    }
+
+    /// Obtain the lines written before waiting for the player (0 if never)
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// Show `--More--` and wait for the player to continue
+    fn wait_for_more(&mut self) {
+        let more = apply_filters(&self.filters, "--More--");
+        self.output.write_line(&more);
+        self.output.flush();
+        self.input.read_line("");
+        self.paged_lines = 0;
+    }
+
+    /// Name the ending reached, to be reported when `END` is returned
+    ///
+    /// The text (if any) is written by the game master when the game ends.
+    pub fn set_ending(&mut self, id: String, text: Option<String>) {
+        self.ending = Some((id, text));
+    }
+
+    /// Check whether an ending was reached
+    pub(crate) fn has_ending(&self) -> bool {
+        self.ending.is_some()
+    }
+
+    /// Obtain the ending reached (if any), clearing it
+    pub(crate) fn take_ending(&mut self) -> Option<(String, Option<String>)> {
+        self.ending.take()
+    }
+
+    /// Install a receiver of structured events, used alongside the text
+    /// output
+    pub fn set_structured_output(&mut self, output: Box<StructuredOutput>) {
+        self.structured = Some(output);
+    }
+
+    /// Send an event to the structured output (if any)
+    pub fn emit(&mut self, event: OutputEvent) {
+        if let Some(ref mut output) = self.structured {
+            output.emit(event);
+        }
+    }
+
+    /// Write a line of text centered in the given width
+    ///
+    /// See `Output::write_centered()`. The output filters are applied to
+    /// the centered line, as with `write()`.
+    pub fn write_centered(&mut self, text: &str, width: usize) {
+        let mut buffer = BufferOutput::new();
+        buffer.write_centered(text, width);
+
+        self.write_lines(&buffer.contents());
+    }
+
+    /// Write the lines inside a box
+    ///
+    /// See `Output::write_boxed()`. The output filters are applied to each
+    /// line of the box, as with `write()`.
+    pub fn write_boxed(&mut self, lines: &[&str]) {
+        let mut buffer = BufferOutput::new();
+        buffer.write_boxed(lines);
+
+        self.write_lines(&buffer.contents());
+    }
+
+    /// Write each line of a text rendered beforehand
+    fn write_lines(&mut self, text: &str) {
+        for line in text.lines() {
+            self.write(line);
+        }
+    }
+
+    /// Add a transformation applied to every line written
+    ///
+    /// Applies to the text written with `write()`, `write_centered()` and
+    /// `write_boxed()`, the `--More--` line of the pager, and the lines
+    /// written when asking the player something (e.g. `menu()`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::input::VecInput;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::Loader;
+    ///
+    /// let buffer = BufferOutput::new();
+    ///
+    /// let mut loader: Loader<()> = Loader::new();
+    /// loader.set_output(Box::new(buffer.clone()));
+    /// loader.set_input(Box::new(VecInput::new(vec!["2".to_string()])));
+    /// loader.add_output_filter(Box::new(|line| line.to_uppercase()));
+    ///
+    /// loader.write_boxed(&["Exit"]);
+    /// assert_eq!(loader.menu("Which way?", &["left", "right"]), Some(1));
+    ///
+    /// assert_eq!(buffer.contents(), "\
+    /// +------+
+    /// | EXIT |
+    /// +------+
+    /// WHICH WAY?
+    /// 1) LEFT
+    /// 2) RIGHT
+    /// ");
+    /// ```
+    pub fn add_output_filter(&mut self, filter: OutputFilter) {
+        self.filters.push(filter);
+    }
+
+    /// Make sure everything written so far is shown
+    pub fn flush(&mut self) {
+        self.output.flush();
+    }
+
+    /// Replace the source of player input
+    pub fn set_input(&mut self, input: Box<Input>) {
+        self.input = input;
+    }
+
+    /// Check whether the player already sees the input read
+    ///
+    /// See `Input::shows_input()`.
+    pub fn shows_input(&self) -> bool {
+        self.input.shows_input()
+    }
+
+    /// Read a line of input from the player after showing the prompt
+    ///
+    /// Returns `None` when there is no more input.
+    pub fn read_line(&mut self, prompt: &str) -> Option<String> {
+        self.paged_lines = 0;
+        self.input.read_line(prompt)
+    }
+
+    /// Ask the player to choose one of the options
+    ///
+    /// See `Input::menu()`.
+    pub fn menu(&mut self, prompt: &str, options: &[&str]) -> Option<usize> {
+        self.paged_lines = 0;
+        let mut output = FilteredOutput::new(&mut *self.output, &self.filters);
+        self.input.menu(&mut output, prompt, options)
+    }
+
+    /// Read lines from the player until one is accepted by the validator
+    ///
+    /// See `Input::read_validated()`.
+    pub fn read_validated(&mut self, prompt: &str, error: &str,
+                          validate: &Fn(&str) -> bool)
+                          -> Option<String> {
+        self.paged_lines = 0;
+        let mut output = FilteredOutput::new(&mut *self.output, &self.filters);
+        self.input.read_validated(&mut output, prompt, error, validate)
+    }
+
+    /// Read lines from the player until one is a number within the range
+    ///
+    /// See `Input::read_int()`.
+    pub fn read_int(&mut self, prompt: &str, range: Option<(i32, i32)>)
+                    -> Option<i32> {
+        self.paged_lines = 0;
+        let mut output = FilteredOutput::new(&mut *self.output, &self.filters);
+        self.input.read_int(&mut output, prompt, range)
+    }
+
+    /// Replace the player used for asset cues
+    pub fn set_cue_player(&mut self, cues: Box<CuePlayer>) {
+        self.cues = cues;
+    }
+
+    /// Request playback of an asset cue (e.g. a sound)
+    pub fn play_cue(&mut self, cue: &str) {
+        self.cues.play(cue);
+    }
+
+    /// Request a new global command
+    ///
+    /// Scenarios and commands do not have access to the game master, so the
+    /// command is added by the master once the current turn is over.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::GameCommand;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// struct Fireball;
+    ///
+    /// impl <S> GameCommand <S> for Fireball {
+    ///     fn execute(&self, state: &Rc<RefCell<S>>,
+    ///                loader: &Rc<RefCell<Loader<S>>>)
+    ///                -> i32 {
+    ///         loader.borrow_mut().write("Flames burst from your hands!");
+    ///
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// // Reading the scroll teaches a new spell
+    /// struct Library;
+    ///
+    /// impl <S> Scenario <S> for Library {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         if command == "read scroll" {
+    ///             loader.borrow_mut().queue_command(
+    ///                 "fireball".to_string(), Box::new(Fireball));
+    ///         }
+    ///
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Library)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    ///
+    /// gm.step("fireball");
+    /// assert_eq!(buffer.contents(), "");
+    ///
+    /// gm.step("read scroll");
+    /// gm.step("fireball");
+    /// assert_eq!(buffer.contents(), "Flames burst from your hands!\n");
+    /// ```
+    pub fn queue_command(&mut self, name: String, command: Box<GameCommand<S>>) {
+        self.command_requests.push(CommandRequest::Add(name, command));
+    }
+
+    /// Request removal of a global command
+    ///
+    /// The command is removed by the master once the current turn is over.
+    pub fn queue_command_removal(&mut self, name: String) {
+        self.command_requests.push(CommandRequest::Remove(name));
+    }
+
+    /// Request entering an input mode (see `GameMaster::push_mode()`)
+    ///
+    /// The mode is entered by the master once the current turn is over.
+    pub fn queue_push_mode(&mut self, name: String) {
+        self.command_requests.push(CommandRequest::PushMode(name));
+    }
+
+    /// Request leaving the innermost input mode
+    ///
+    /// The mode is left by the master once the current turn is over.
+    pub fn queue_pop_mode(&mut self) {
+        self.command_requests.push(CommandRequest::PopMode);
+    }
+
+    /// Obtain the requested changes to global commands, clearing them
+    pub(crate) fn take_command_requests(&mut self) -> Vec<CommandRequest<S>> {
+        self.command_requests.drain(..).collect()
+    }
+
+    /// Queue an input for the game loop, as if the player typed it
+    ///
+    /// Queued inputs are processed in order before reading from the player
+    /// again, which is useful for cutscenes. Games driven by
+    /// `GameMaster::step()` process them at the end of the step. See
+    /// `GameMaster::set_queued_input_limit()` for the protection against
+    /// inputs queueing themselves forever.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::command::GameCommand;
+    /// use texture::input::VecInput;
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{BaseScenario, Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::{NOTURN, TICK};
+    ///
+    /// struct Stage;
+    ///
+    /// impl <S> Scenario <S> for Stage {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn describe(&self, state: &Rc<RefCell<S>>,
+    ///                 loader: &Rc<RefCell<Loader<S>>>) {
+    ///         loader.borrow_mut().write("An empty stage.");
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         BaseScenario.do_action(command, state, loader)
+    ///     }
+    /// }
+    ///
+    /// struct Cue;
+    ///
+    /// impl <S> GameCommand <S> for Cue {
+    ///     fn execute(&self, state: &Rc<RefCell<S>>,
+    ///                loader: &Rc<RefCell<Loader<S>>>)
+    ///                -> i32 {
+    ///         loader.borrow_mut().write("The lights go up.");
+    ///         loader.borrow_mut().queue_input("look".to_string());
+    ///         NOTURN
+    ///     }
+    /// }
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Stage)));
+    /// gm.set_output(Box::new(buffer.clone()));
+    /// gm.set_input(Box::new(VecInput::new(vec!["cue".to_string()])));
+    /// gm.add_command("cue".to_string(), Box::new(Cue));
+    ///
+    /// gm.start_game();
+    ///
+    /// assert!(buffer.contents()
+    ///     .ends_with("The lights go up.\n \nAn empty stage.\n"));
+    /// assert_eq!(gm.recent_inputs(), vec!["cue", "look"]);
+    ///
+    /// // Same when driving the game step by step
+    /// buffer.clear();
+    /// gm.step("cue");
+    /// assert_eq!(buffer.contents(), "The lights go up.\nAn empty stage.\n");
+    /// ```
+    pub fn queue_input(&mut self, input: String) {
+        self.queued_inputs.push_back(input);
+    }
+
+    /// Obtain the next queued input, removing it
+    pub(crate) fn take_queued_input(&mut self) -> Option<String> {
+        self.queued_inputs.pop_front()
+    }
+
+    /// Forget all the queued inputs
+    pub(crate) fn clear_queued_inputs(&mut self) {
+        self.queued_inputs.clear();
+    }
+
+    /// Register an already built scenario under the given name
+    ///
+    /// Any scenario previously registered with the same name is replaced,
+    /// in which case `true` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::util::TICK;
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let mut loader: Loader<()> = Loader::new();
+    ///
+    /// assert!(!loader.register("hall".to_string(), Rc::new(RefCell::new(Room))));
+    /// assert!(loader.register("hall".to_string(), Rc::new(RefCell::new(Room))));
+    ///
+    /// assert!(loader.unregister("hall"));
+    /// assert!(!loader.unregister("hall"));
+    /// assert!(!loader.load_by_name("hall"));
+    /// ```
+    pub fn register(&mut self, name: String,
+                    scenario: Rc<RefCell<Scenario<S>>>) -> bool {
+        let factory = self.factories.remove(&name).is_some();
+
+        self.insert_scenario(name, scenario) || factory
+    }
+
+    /// Check whether a scenario (or factory) is registered with the name
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.registry.contains_key(name) || self.factories.contains_key(name)
+    }
+
+    /// Attach a piece of data to a registered scenario
+    ///
+    /// The engine does not use metadata itself: it is meant for tools and
+    /// game logic (e.g. the region of the scenario, or the music to play).
+    /// Any previous value for the key is replaced. Returns `false`, storing
+    /// nothing, if no scenario is registered with the name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::data::DataScenario;
+    /// use texture::scenario::Loader;
+    /// use texture::state::BasicState;
+    ///
+    /// let beach = DataScenario::new("Waves crash on the sand.".to_string());
+    ///
+    /// let mut loader: Loader<BasicState> = Loader::new();
+    /// loader.register("beach".to_string(), Rc::new(RefCell::new(beach)));
+    ///
+    /// assert!(loader.set_scenario_meta("beach", "region".to_string(),
+    ///                                  "coast".to_string()));
+    /// assert!(!loader.set_scenario_meta("cave", "region".to_string(),
+    ///                                   "coast".to_string()));
+    ///
+    /// assert_eq!(loader.scenario_meta("beach", "region"),
+    ///            Some("coast".to_string()));
+    /// assert_eq!(loader.scenario_meta("beach", "music"), None);
+    /// assert_eq!(loader.scenario_meta("cave", "region"), None);
+    /// ```
+    pub fn set_scenario_meta(&mut self, name: &str, key: String, value: String)
+                             -> bool {
+        if !self.is_registered(name) {
+            return false;
+        }
+
+        self.metadata.entry(name.to_string())
+            .or_insert_with(BTreeMap::new)
+            .insert(key, value);
+
+        true
+    }
+
+    /// Obtain a piece of data attached to a registered scenario (if any)
+    pub fn scenario_meta(&self, name: &str, key: &str) -> Option<String> {
+        self.metadata.get(name)
+            .and_then(|meta| meta.get(key))
+            .cloned()
+    }
+
+    /// Obtain what happens when merged data files define the same scenario
+    pub fn merge_policy(&self) -> MergePolicy {
+        self.merge_policy
+    }
+
+    /// Change what happens when merged data files define the same scenario
+    ///
+    /// See `load_and_merge()`.
+    pub fn set_merge_policy(&mut self, policy: MergePolicy) {
+        self.merge_policy = policy;
+    }
+
+    /// Remove a registered scenario (or factory)
+    ///
+    /// The scenario currently loaded cannot be removed. Returns `true` if
+    /// the scenario was removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState};
+    /// use texture::util::TICK;
+    ///
+    /// struct Room;
+    ///
+    /// impl <S> Scenario <S> for Room {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let start = Rc::new(RefCell::new(Room));
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    ///
+    /// let mut gm = GameMaster::new(state, start.clone());
+    /// let loader = gm.loader();
+    /// loader.borrow_mut().register("start".to_string(), start);
+    /// loader.borrow_mut().register("hall".to_string(), Rc::new(RefCell::new(Room)));
+    ///
+    /// // Current scenario cannot be removed
+    /// assert!(!loader.borrow_mut().unregister("start"));
+    ///
+    /// gm.load_by_name_quiet("hall");
+    /// assert!(loader.borrow_mut().unregister("start"));
+    /// assert!(!loader.borrow_mut().unregister("hall"));
+    /// ```
+    pub fn unregister(&mut self, name: &str) -> bool {
+        let active = match self.registry.get(name) {
+            Some(scenario) => { self.is_current(scenario) },
+            None => { false }
+        };
+
+        if active {
+            return false;
+        }
+
+        let factory = self.factories.remove(name).is_some();
+        self.metadata.remove(name);
+        self.content.remove(name);
+
+        self.remove_scenario(name) || factory
+    }
+
+    /// Register a factory that builds the scenario the first time it is
+    /// requested
+    ///
+    /// The built scenario is cached, so the factory runs at most once. Any
+    /// scenario previously registered with the same name is replaced, in
+    /// which case `true` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::{Cell, RefCell};
+    /// use std::rc::Rc;
+    ///
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::util::TICK;
+    ///
+    /// struct Cave;
+    ///
+    /// impl <S> Scenario <S> for Cave {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         println!("A damp cave");
+    ///
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let built = Rc::new(Cell::new(0));
+    /// let counter = built.clone();
+    ///
+    /// let mut loader: Loader<()> = Loader::new();
+    /// loader.register_factory("cave".to_string(), Box::new(move || {
+    ///     counter.set(counter.get() + 1);
+    ///     Rc::new(RefCell::new(Cave))
+    /// }));
+    ///
+    /// // Nothing is built until requested
+    /// assert_eq!(built.get(), 0);
+    ///
+    /// assert!(loader.load_by_name("cave"));
+    /// assert!(loader.load_by_name("cave"));
+    ///
+    /// assert_eq!(built.get(), 1);
+    /// ```
+    pub fn register_factory(&mut self, name: String,
+                            factory: ScenarioFactory<S>) -> bool {
+        let built = self.remove_scenario(&name);
+
+        self.factories.insert(name, factory).is_some() || built
+    }
+
+    /// Obtain the registered scenarios already built, sorted by name
+    pub(crate) fn registered_scenarios(&self)
+                                       -> Vec<(String, Rc<RefCell<Scenario<S>>>)> {
+        let mut scenarios: Vec<(String, Rc<RefCell<Scenario<S>>>)> =
+            self.registry.iter()
+                .map(|(name, scenario)| (name.clone(), scenario.clone()))
+                .collect();
+
+        scenarios.sort_by(|a, b| a.0.cmp(&b.0));
+
+        scenarios
+    }
+
+    /// Data files read with `load_and_merge()`, in order
+    pub fn content_paths(&self) -> &[String] {
+        &self.content_paths
+    }
+
+    /// Check whether a registered scenario was read from a data file
+    pub fn is_content(&self, name: &str) -> bool {
+        self.content.contains(name)
+    }
+
+    /// Remember a data file to read again when reloading
+    #[cfg(feature = "serde")]
+    pub(crate) fn add_content_path(&mut self, path: String) {
+        if !self.content_paths.contains(&path) {
+            self.content_paths.push(path);
+        }
+    }
+
+    /// Remember that a scenario was read from a data file
+    #[cfg(feature = "serde")]
+    pub(crate) fn mark_content(&mut self, name: String) {
+        self.content.insert(name);
+    }
+
+    /// Obtain the names of the scenarios read from data files, clearing them
+    #[cfg(feature = "serde")]
+    pub(crate) fn take_content(&mut self) -> Vec<String> {
+        self.content.drain().collect()
+    }
+
+    /// Remove a registered scenario, even if it is the current one
+    #[cfg(feature = "serde")]
+    pub(crate) fn forget(&mut self, name: &str) {
+        self.remove_scenario(name);
+        self.factories.remove(name);
+        self.metadata.remove(name);
+        self.content.remove(name);
+    }
+
+    /// Obtain a registered scenario by its name
+    ///
+    /// Scenarios registered through a factory are built (and cached) here.
+    pub fn get_by_name(&mut self, name: &str)
+                       -> Option<Rc<RefCell<Scenario<S>>>> {
+        if let Some(factory) = self.factories.remove(name) {
+            self.insert_scenario(name.to_string(), factory());
+        }
+
+        self.registry.get(name).cloned()
+    }
+
+    /// Set a registered scenario to load by its name
+    ///
+    /// If no scenario was registered with that name, the missing scenario
+    /// handler is called and `false` is returned, leaving the scenario to
+    /// load untouched.
+    pub fn load_by_name(&mut self, name: &str) -> bool {
+        match self.get_by_name(name) {
+            Some(scenario) => { self.set_scenario(scenario); true },
+            None => { self.missing_scenario(name); false }
+        }
+    }
+
+    /// Obtain the seed of the generator used for random transitions
+    pub fn rng_seed(&self) -> u64 {
+        self.rng.seed()
+    }
+
+    /// Replace the generator used for random transitions
+    ///
+    /// By default, the generator is seeded from the system clock. Use a
+    /// fixed seed to make transitions reproducible.
+    pub fn set_rng(&mut self, rng: Rng) {
+        self.rng = rng;
+    }
+
+    /// Generate a random text using the generator of the loader
+    ///
+    /// See `Generator::generate()`. The text is reproducible when a seeded
+    /// generator is installed with `set_rng()`.
+    pub fn generate(&mut self, generator: &Generator) -> Option<String> {
+        generator.generate(&mut self.rng)
+    }
+
+    /// Set one of the named scenarios to load, chosen at random
+    ///
+    /// Every candidate is equally likely. Returns whether the chosen
+    /// scenario exists (see `load_by_name()`), or `false` if there are no
+    /// candidates.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::random::Rng;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::util::TICK;
+    ///
+    /// struct Passage;
+    ///
+    /// impl <S> Scenario <S> for Passage {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         TICK
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         TICK
+    ///     }
+    /// }
+    ///
+    /// let names = ["north", "south", "west"];
+    ///
+    /// let mut loader: Loader<()> = Loader::new();
+    /// loader.set_rng(Rng::new(42));
+    ///
+    /// for name in names.iter() {
+    ///     loader.register(name.to_string(), Rc::new(RefCell::new(Passage)));
+    /// }
+    ///
+    /// // Same seed, same choices
+    /// let mut rng = Rng::new(42);
+    ///
+    /// for _ in 0..10 {
+    ///     assert!(loader.load_random(&names));
+    ///
+    ///     let expected = names[rng.below(3) as usize];
+    ///     let expected = loader.get_by_name(expected).unwrap();
+    ///     assert!(Rc::ptr_eq(&loader.get_scenario(), &expected));
+    /// }
+    ///
+    /// assert!(!loader.load_random(&[]));
+    /// ```
+    pub fn load_random(&mut self, candidates: &[&str]) -> bool {
+        if candidates.is_empty() {
+            return false;
+        }
+
+        let index = self.rng.below(candidates.len() as u64) as usize;
+
+        self.load_by_name(candidates[index])
+    }
+
+    /// Set one of the named scenarios to load, chosen according to weights
+    ///
+    /// A scenario with weight 3 is three times as likely to be chosen as
+    /// one with weight 1. Returns whether the chosen scenario exists (see
+    /// `load_by_name()`), or `false` if nothing can be chosen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::random::{Rng, WeightedTable};
+    /// use texture::scenario::Loader;
+    ///
+    /// let candidates = [("pit", 1), ("treasury", 0), ("corridor", 4)];
+    ///
+    /// let chosen = Rc::new(RefCell::new(Vec::new()));
+    /// let record = chosen.clone();
+    ///
+    /// // Nothing registered, so the choices reach the missing handler
+    /// let mut loader: Loader<()> = Loader::new();
+    /// loader.set_rng(Rng::new(7));
+    /// loader.set_missing_scenario_handler(Box::new(move |_, name| {
+    ///     record.borrow_mut().push(name.to_string());
+    /// }));
+    ///
+    /// for _ in 0..20 {
+    ///     assert!(!loader.load_weighted(&candidates));
+    /// }
+    ///
+    /// // Same seed, same choices
+    /// let mut table = WeightedTable::new();
+    /// for &(name, weight) in candidates.iter() {
+    ///     table.add(name.to_string(), weight);
+    /// }
+    ///
+    /// let mut rng = Rng::new(7);
+    /// let expected: Vec<String> = (0..20)
+    ///     .map(|_| table.pick(&mut rng).unwrap().clone())
+    ///     .collect();
+    ///
+    /// assert_eq!(*chosen.borrow(), expected);
+    /// assert!(!chosen.borrow().contains(&"treasury".to_string()));
+    /// ```
+    pub fn load_weighted(&mut self, candidates: &[(&str, u32)]) -> bool {
+        let mut table = WeightedTable::new();
+
+        for &(name, weight) in candidates {
+            table.add(name, weight);
+        }
+
+        let name = match table.pick(&mut self.rng) {
+            Ok(name) => { *name },
+            Err(_) => return false
+        };
+
+        self.load_by_name(name)
+    }
+
+    /// Set the function called when a scenario name cannot be resolved
+    ///
+    /// By default, an error message is written to the output.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::Loader;
+    ///
+    /// let buffer = BufferOutput::new();
+    /// let mut loader: Loader<()> = Loader::new();
+    /// loader.set_output(Box::new(buffer.clone()));
+    ///
+    /// assert!(!loader.load_by_name("cellar"));
+    /// assert_eq!(buffer.contents(),
+    ///            "Error: there is no scenario named 'cellar'\n");
+    ///
+    /// let missing = Rc::new(RefCell::new(Vec::new()));
+    /// let record = missing.clone();
+    ///
+    /// loader.set_missing_scenario_handler(Box::new(move |_, name| {
+    ///     record.borrow_mut().push(name.to_string());
+    /// }));
+    ///
+    /// assert!(!loader.load_by_name("attic"));
+    /// assert_eq!(*missing.borrow(), vec!["attic".to_string()]);
+    /// ```
+    pub fn set_missing_scenario_handler(&mut self,
+                                        handler: MissingScenarioHandler<S>) {
+        self.missing_handler = Some(handler);
+    }
+
+    /// Check whether a handler for unresolved scenario names is set
+    pub(crate) fn has_missing_scenario_handler(&self) -> bool {
+        self.missing_handler.is_some()
+    }
+
+    /// Report a scenario name that could not be resolved
+    fn missing_scenario(&mut self, name: &str) {
+        self.missing_reported = true;
+
+        match self.missing_handler.take() {
+            Some(handler) => {
+                handler(self, name);
+
+                // The handler may have installed a replacement
+                if self.missing_handler.is_none() {
+                    self.missing_handler = Some(handler);
+                }
+            },
+            None => {
+                self.write(&format!("Error: there is no scenario named '{}'",
+                                    name));
+            }
+        }
     }
 }
 
@@ -55,8 +1882,179 @@ pub trait Scenario<S> {
     fn load(&self, state: &Rc<RefCell<S>>, loader: &Rc<RefCell<Loader<S>>>)
             -> i32;
 
+    /// Method executed right after `load()` and whenever the scenario has to
+    /// be described again (e.g. a `look` command).
+    ///
+    /// Should only print text; one-time setup belongs in `load()`. Does
+    /// nothing by default, so scenarios describing themselves in `load()`
+    /// keep working.
+    fn describe(&self, _state: &Rc<RefCell<S>>,
+                _loader: &Rc<RefCell<Loader<S>>>) {
+    }
+
+    /// Short description shown instead of `describe()` in the brief
+    /// description modes
+    ///
+    /// Returns `None` by default, which shows the full description.
+    fn short_describe(&self) -> Option<String> {
+        None
+    }
+
+    /// Detailed description of an object found in the scenario
+    ///
+    /// Used by `ExamineCommand`. Returns `None` by default, meaning there is
+    /// nothing special about the object.
+    fn examine(&self, _object: &str) -> Option<String> {
+        None
+    }
+
+    /// Check whether an object in the scenario can be picked up
+    ///
+    /// Used by `TakeCommand`. Returns `false` by default.
+    fn can_take(&self, _object: &str, _state: &Rc<RefCell<S>>) -> bool {
+        false
+    }
+
+    /// Check whether the player may enter the scenario right now
+    ///
+    /// Used by `TravelCommand`. Returns `true` by default.
+    fn can_enter(&self, _state: &Rc<RefCell<S>>) -> bool {
+        true
+    }
+
+    /// Names of the state keys the scenario expects to be set
+    ///
+    /// Only used to catch mistakes while developing (see
+    /// `GameMaster::validate_state_requirements()`). Empty by default.
+    fn required_keys(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Check whether the player respawns here after entering the scenario
+    ///
+    /// See `GameMaster::respawn()`. Returns `false` by default.
+    fn is_checkpoint(&self) -> bool {
+        false
+    }
+
+    /// Method executed on every turn spent in the scenario
+    ///
+    /// `turns` is the number of turns since the scenario was entered
+    /// (starting at 1). Returning `LOAD` transitions to the scenario set in
+    /// the loader, which is useful for traps or timed events. Does nothing
+    /// by default.
+    fn on_dwell(&self, _turns: u64, _state: &Rc<RefCell<S>>,
+                _loader: &Rc<RefCell<Loader<S>>>)
+                -> i32 {
+        TICK
+    }
+
     /// Method executed when user input is received
     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
                  loader: &Rc<RefCell<Loader<S>>>)
                  -> i32;
 }
+
+/// Obtain a seed from the system clock
+/// Address identifying a scenario, whatever its type
+fn address<S>(scenario: &Rc<RefCell<Scenario<S>>>) -> usize {
+    Rc::as_ptr(scenario) as *const () as usize
+}
+
+fn time_seed() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => { elapsed.as_secs() ^ elapsed.subsec_nanos() as u64 },
+        Err(_) => { 0 }
+    }
+}
+
+/// Actions shared by many scenarios
+///
+/// Scenarios can fall back to it for the inputs they do not handle
+/// themselves, so common verbs are written only once:
+///
+/// - `look` or `l`: describes the current scenario again (see
+///   `Scenario::describe()`), without taking a turn
+/// - `wait` or `z`: lets a turn go by
+///
+/// Any other input returns `UNKNOWN`.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::master::GameMaster;
+/// use texture::output::BufferOutput;
+/// use texture::scenario::{BaseScenario, Loader, Scenario};
+/// use texture::state::{BaseState, BasicState};
+/// use texture::util::TICK;
+///
+/// struct Kitchen {
+///     base: BaseScenario,
+/// }
+///
+/// impl <S> Scenario <S> for Kitchen {
+///     fn load(&self, state: &Rc<RefCell<S>>,
+///             loader: &Rc<RefCell<Loader<S>>>)
+///             -> i32 {
+///         TICK
+///     }
+///
+///     fn describe(&self, state: &Rc<RefCell<S>>,
+///                 loader: &Rc<RefCell<Loader<S>>>) {
+///         loader.borrow_mut().write("A small kitchen.");
+///     }
+///
+///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+///                  loader: &Rc<RefCell<Loader<S>>>)
+///                  -> i32 {
+///         match command {
+///             "cook" => {
+///                 loader.borrow_mut().write("You make a sandwich.");
+///                 TICK
+///             },
+///             _ => { self.base.do_action(command, state, loader) }
+///         }
+///     }
+/// }
+///
+/// let buffer = BufferOutput::new();
+/// let state = Rc::new(RefCell::new(BasicState::new()));
+///
+/// let kitchen = Kitchen { base: BaseScenario };
+/// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(kitchen)));
+/// gm.set_output(Box::new(buffer.clone()));
+///
+/// gm.step("cook");
+/// gm.step("look");
+///
+/// assert_eq!(buffer.contents(), "You make a sandwich.\nA small kitchen.\n");
+/// assert_eq!(gm.turns(), 1);
+/// ```
+pub struct BaseScenario;
+
+impl BaseScenario {
+    /// Execute one of the shared actions
+    pub fn do_action<S>(&self, command: &str, state: &Rc<RefCell<S>>,
+                        loader: &Rc<RefCell<Loader<S>>>)
+                        -> i32 {
+        match command {
+            "look" | "l" => {
+                let current = loader.borrow().current_scenario();
+
+                if let Some(scenario) = current {
+                    scenario.borrow().describe(state, loader);
+                }
+
+                NOTURN
+            },
+            "wait" | "z" => {
+                loader.borrow_mut().write("Time passes.");
+                TICK
+            },
+            _ => { UNKNOWN }
+        }
+    }
+}
@@ -20,7 +20,184 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::rc::Rc;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Function called when a flag changes
+pub type FlagObserver = Box<Fn(bool)>;
+
+/// Function called when an integer value changes
+pub type ValueObserver = Box<Fn(i32)>;
+
+/// Function called when any flag or value changes
+///
+/// Receives the name of the flag or value and the phase of the game in which
+/// it changed.
+pub type ChangeObserver = Box<Fn(&str, Phase)>;
+
+/// Part of the game being run when the state changes
+///
+/// The game master updates the phase around each call to scenarios and
+/// commands (see `GameMaster::phase_cell()`).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Phase {
+    /// Outside of any scenario or command (e.g. while setting up the game)
+    #[default]
+    Idle,
+    /// Loading a scenario
+    Load,
+    /// Performing an action of the current scenario
+    Action,
+    /// Executing a global command
+    Command,
+}
+
+/// How flag and value names are normalized before being used
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Normalization {
+    /// Names are used as they are
+    #[default]
+    Exact,
+    /// Names are lowercased
+    Lowercase,
+    /// Names are lowercased and accents are removed (e.g. `Café` is
+    /// `cafe`)
+    LowercaseNoAccents,
+}
+
+impl Normalization {
+    /// Normalize a name
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::state::Normalization;
+    ///
+    /// assert_eq!(Normalization::Exact.apply("Café"), "Café");
+    /// assert_eq!(Normalization::Lowercase.apply("Café"), "café");
+    /// assert_eq!(Normalization::LowercaseNoAccents.apply("Café Ñandú"),
+    ///            "cafe nandu");
+    /// ```
+    pub fn apply(&self, name: &str) -> String {
+        match *self {
+            Normalization::Exact => { name.to_string() },
+            Normalization::Lowercase => { name.to_lowercase() },
+            Normalization::LowercaseNoAccents => {
+                name.to_lowercase()
+                    .chars()
+                    .filter(|c| !is_combining_mark(*c))
+                    .map(strip_accent)
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Check whether the character is a combining diacritical mark
+fn is_combining_mark(c: char) -> bool {
+    ('\u{300}'..='\u{36f}').contains(&c)
+}
+
+/// Obtain the base letter of a lowercase accented latin letter
+fn strip_accent(c: char) -> char {
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => { 'a' },
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => { 'c' },
+        'ď' => { 'd' },
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => { 'e' },
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => { 'g' },
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' => { 'i' },
+        'ñ' | 'ń' | 'ņ' | 'ň' => { 'n' },
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => { 'o' },
+        'ŕ' | 'ŗ' | 'ř' => { 'r' },
+        'ś' | 'ŝ' | 'ş' | 'š' => { 's' },
+        'ţ' | 'ť' => { 't' },
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => { 'u' },
+        'ý' | 'ÿ' => { 'y' },
+        'ź' | 'ż' | 'ž' => { 'z' },
+        other => { other }
+    }
+}
+
+/// One of the maps kept by `BasicState`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StateMap {
+    /// Boolean flags
+    Flags,
+    /// Integer values
+    Values,
+    /// Lists of text
+    Lists,
+}
+
+/// Differences between two states, found by `BasicState::diff()`
+///
+/// Entries are named like in `BasicState::debug_dump()` (e.g.
+/// `flag door_open` or `value gold`) and sorted.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StateDiff {
+    /// Entries only found in the newer state
+    pub added: Vec<String>,
+    /// Entries only found in the older state
+    pub removed: Vec<String>,
+    /// Entries found in both states, with different contents
+    pub changed: Vec<String>,
+}
+
+impl StateDiff {
+    /// Check whether the states were the same
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Compare two maps, adding the differences with the given kind
+    fn compare<T: PartialEq>(&mut self, kind: &str,
+                             old: &BTreeMap<String, T>,
+                             new: &BTreeMap<String, T>) {
+        for (name, value) in new {
+            match old.get(name) {
+                None => { self.added.push(format!("{} {}", kind, name)) },
+                Some(v) if v != value => {
+                    self.changed.push(format!("{} {}", kind, name))
+                },
+                _ => {}
+            }
+        }
+
+        for name in old.keys() {
+            if !new.contains_key(name) {
+                self.removed.push(format!("{} {}", kind, name));
+            }
+        }
+    }
+}
+
+impl fmt::Display for StateDiff {
+    /// One line per entry, starting with `+` (added), `-` (removed) or `~`
+    /// (changed)
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for name in &self.added {
+            writeln!(f, "+ {}", name)?;
+        }
+
+        for name in &self.removed {
+            writeln!(f, "- {}", name)?;
+        }
+
+        for name in &self.changed {
+            writeln!(f, "~ {}", name)?;
+        }
+
+        Ok(())
+    }
+}
 
 /// Basic state structure
 ///
@@ -28,11 +205,630 @@ use std::collections::HashMap;
 ///
 /// It is recommended to build a custom state type rather than using this
 /// basic one.
+///
+/// Flags and values are kept sorted by name, so dumps and saved games are
+/// always written in the same order. States are small enough for the sorted
+/// maps not to make a noticeable difference in speed.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BasicState {
     // Boolean flags
-    flags: HashMap<String, bool>,
+    flags: BTreeMap<String, bool>,
     // Integer values
-    values: HashMap<String, i32>,
+    values: BTreeMap<String, i32>,
+    // Lists of text
+    #[cfg_attr(feature = "serde", serde(default))]
+    lists: BTreeMap<String, Vec<String>>,
+    // Observers of specific flags
+    #[cfg_attr(feature = "serde", serde(skip))]
+    flag_observers: HashMap<String, Vec<FlagObserver>>,
+    // Observers of specific values
+    #[cfg_attr(feature = "serde", serde(skip))]
+    value_observers: HashMap<String, Vec<ValueObserver>>,
+    // Observers of any change
+    #[cfg_attr(feature = "serde", serde(skip))]
+    change_observers: Vec<ChangeObserver>,
+    // Phase of the game, shared with the game master
+    #[cfg_attr(feature = "serde", serde(skip))]
+    phase: Rc<Cell<Phase>>,
+    // How names are normalized
+    #[cfg_attr(feature = "serde", serde(default))]
+    normalization: Normalization,
+}
+
+impl BasicState {
+    /// Register a function called whenever the given flag changes
+    ///
+    /// The function receives the new value of the flag. Changes to other
+    /// flags do not call it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::state::BaseState;
+    /// use texture::state::BasicState;
+    ///
+    /// let calls = Rc::new(Cell::new(0));
+    /// let counter = calls.clone();
+    ///
+    /// let mut state = BasicState::new();
+    /// state.on_flag_change("door_open", Box::new(move |open| {
+    ///     assert!(open);
+    ///     counter.set(counter.get() + 1);
+    /// }));
+    ///
+    /// state.set_flag("lamp_lit".to_string(), true);
+    /// assert_eq!(calls.get(), 0);
+    ///
+    /// state.set_flag("door_open".to_string(), true);
+    /// state.set_flag("door_open".to_string(), true);
+    /// assert_eq!(calls.get(), 1);
+    /// ```
+    pub fn on_flag_change(&mut self, key: &str, f: FlagObserver) {
+        self.flag_observers.entry(self.normalization.apply(key))
+            .or_insert_with(Vec::new)
+            .push(f);
+    }
+
+    /// Register a function called whenever the given integer value changes
+    ///
+    /// The function receives the new value. Changes to other values do not
+    /// call it.
+    pub fn on_value_change(&mut self, key: &str, f: ValueObserver) {
+        self.value_observers.entry(self.normalization.apply(key))
+            .or_insert_with(Vec::new)
+            .push(f);
+    }
+
+    /// Register a function called whenever any flag or value changes
+    ///
+    /// The function receives the name of the flag or value and the phase in
+    /// which it changed, which tells changes made while loading a scenario
+    /// apart from those made by the player's actions. The phase is only
+    /// known when shared with the game master (see `set_phase_cell()`), and
+    /// is `Phase::Idle` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// use texture::master::GameMaster;
+    /// use texture::output::BufferOutput;
+    /// use texture::scenario::{Loader, Scenario};
+    /// use texture::state::{BaseState, BasicState, Phase};
+    /// use texture::util::NOTURN;
+    ///
+    /// struct Cellar;
+    ///
+    /// impl <S: BaseState> Scenario <S> for Cellar {
+    ///     fn load(&self, state: &Rc<RefCell<S>>,
+    ///             loader: &Rc<RefCell<Loader<S>>>)
+    ///             -> i32 {
+    ///         state.borrow_mut().set_flag("dark".to_string(), true);
+    ///         NOTURN
+    ///     }
+    ///
+    ///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+    ///                  loader: &Rc<RefCell<Loader<S>>>)
+    ///                  -> i32 {
+    ///         state.borrow_mut().set_flag("dark".to_string(), false);
+    ///         NOTURN
+    ///     }
+    /// }
+    ///
+    /// let changes = Rc::new(RefCell::new(Vec::new()));
+    /// let seen = changes.clone();
+    ///
+    /// let state = Rc::new(RefCell::new(BasicState::new()));
+    /// state.borrow_mut().on_change(Box::new(move |name, phase| {
+    ///     seen.borrow_mut().push((name.to_string(), phase));
+    /// }));
+    ///
+    /// let cellar = Rc::new(RefCell::new(Cellar));
+    /// let mut gm = GameMaster::new(state.clone(), cellar);
+    /// gm.set_output(Box::new(BufferOutput::new()));
+    /// state.borrow_mut().set_phase_cell(gm.phase_cell());
+    ///
+    /// gm.begin();
+    /// gm.step("light candle");
+    ///
+    /// assert_eq!(*changes.borrow(), vec![
+    ///     ("dark".to_string(), Phase::Load),
+    ///     ("dark".to_string(), Phase::Action),
+    /// ]);
+    /// ```
+    pub fn on_change(&mut self, f: ChangeObserver) {
+        self.change_observers.push(f);
+    }
+
+    /// Share the current phase of the game with the game master
+    pub fn set_phase_cell(&mut self, phase: Rc<Cell<Phase>>) {
+        self.phase = phase;
+    }
+
+    /// Call the observers of any change
+    fn notify_change(&self, name: &str) {
+        for f in &self.change_observers {
+            f(name, self.phase.get());
+        }
+    }
+
+    /// Change how flag and value names are normalized
+    ///
+    /// Names are normalized both when setting and when reading, so with
+    /// `Lowercase` the flag `Lamp` is the same as `lamp`. Names already in
+    /// the state are **not** normalized again when changing modes, so the
+    /// mode should be chosen before populating the state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::state::{BaseState, BasicState, Normalization};
+    ///
+    /// let mut state = BasicState::new();
+    /// state.set_key_normalization(Normalization::Lowercase);
+    ///
+    /// state.set_flag("lamp".to_string(), true);
+    /// assert!(state.get_flag("Lamp".to_string()));
+    ///
+    /// state.set_value("GOLD".to_string(), 3);
+    /// assert_eq!(state.get_value("gold".to_string()), 3);
+    ///
+    /// state.set_key_normalization(Normalization::LowercaseNoAccents);
+    /// state.set_flag("Café".to_string(), true);
+    /// assert!(state.get_flag("cafe".to_string()));
+    /// ```
+    pub fn set_key_normalization(&mut self, normalization: Normalization) {
+        self.normalization = normalization;
+    }
+
+    /// Describe every flag and value, sorted by name
+    ///
+    /// Meant for debugging; the same state always produces the same text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::state::BaseState;
+    /// use texture::state::BasicState;
+    ///
+    /// let mut state = BasicState::new();
+    /// state.set_value("gold".to_string(), 5);
+    /// state.set_flag("lamp_lit".to_string(), true);
+    /// state.set_value("arrows".to_string(), 12);
+    /// state.set_flag("door_open".to_string(), false);
+    ///
+    /// let dump = state.debug_dump();
+    ///
+    /// assert_eq!(dump, "\
+    /// flag door_open = false
+    /// flag lamp_lit = true
+    /// value arrows = 12
+    /// value gold = 5
+    /// ");
+    ///
+    /// for _ in 0..10 {
+    ///     assert_eq!(state.debug_dump(), dump);
+    /// }
+    /// ```
+    pub fn debug_dump(&self) -> String {
+        let mut dump = String::new();
+
+        for (name, flag) in &self.flags {
+            dump.push_str(&format!("flag {} = {}\n", name, flag));
+        }
+
+        for (name, value) in &self.values {
+            dump.push_str(&format!("value {} = {}\n", name, value));
+        }
+
+        for (name, list) in &self.lists {
+            dump.push_str(&format!("list {} = {:?}\n", name, list));
+        }
+
+        dump
+    }
+
+    /// Remove every flag and value except the ones with the given names
+    ///
+    /// Useful to restart a game while keeping some settings (e.g. the
+    /// difficulty). Observers are not notified, as with `clear()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::state::BaseState;
+    /// use texture::state::BasicState;
+    ///
+    /// let mut state = BasicState::new();
+    /// state.set_value("difficulty".to_string(), 2);
+    /// state.set_flag("hard_mode".to_string(), true);
+    /// state.set_value("gold".to_string(), 5);
+    /// state.set_flag("door_open".to_string(), true);
+    ///
+    /// state.clear_except(&["difficulty", "hard_mode"]);
+    ///
+    /// assert_eq!(state.debug_dump(), "\
+    /// flag hard_mode = true
+    /// value difficulty = 2
+    /// ");
+    /// ```
+    pub fn clear_except(&mut self, keep: &[&str]) {
+        let keep: Vec<String> = keep.iter()
+            .map(|name| self.normalization.apply(name))
+            .collect();
+
+        self.flags.retain(|name, _| keep.contains(name));
+        self.values.retain(|name, _| keep.contains(name));
+        self.lists.retain(|name, _| keep.contains(name));
+    }
+
+    /// Remove everything in one of the maps, leaving the other untouched
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::state::{BaseState, BasicState, StateMap};
+    ///
+    /// let mut state = BasicState::new();
+    /// state.set_value("gold".to_string(), 5);
+    /// state.set_flag("door_open".to_string(), true);
+    ///
+    /// state.clear_map(StateMap::Flags);
+    ///
+    /// assert_eq!(state.debug_dump(), "value gold = 5\n");
+    /// ```
+    pub fn clear_map(&mut self, which: StateMap) {
+        match which {
+            StateMap::Flags => { self.flags.clear() },
+            StateMap::Values => { self.values.clear() },
+            StateMap::Lists => { self.lists.clear() }
+        }
+    }
+
+    /// Replace a list of text
+    pub fn set_list(&mut self, name: &str, list: Vec<String>) {
+        self.lists.insert(self.normalization.apply(name), list);
+    }
+
+    /// Obtain a list of text, empty if it was never set
+    pub fn get_list(&self, name: &str) -> Vec<String> {
+        self.lists.get(&self.normalization.apply(name))
+            .cloned()
+            .unwrap_or_else(Vec::new)
+    }
+
+    /// Check whether a flag was ever set, whatever its value
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::state::{BaseState, BasicState};
+    ///
+    /// let mut state = BasicState::new();
+    /// state.set_flag("has_map".to_string(), false);
+    /// state.set_value("gold".to_string(), 0);
+    ///
+    /// assert!(state.contains_flag("has_map"));
+    /// assert!(!state.contains_flag("has_key"));
+    /// assert!(state.contains_value("gold"));
+    /// assert!(!state.contains_list("gold"));
+    /// ```
+    pub fn contains_flag(&self, name: &str) -> bool {
+        self.flags.contains_key(&self.normalization.apply(name))
+    }
+
+    /// Check whether an integer value was ever set, whatever its value
+    pub fn contains_value(&self, name: &str) -> bool {
+        self.values.contains_key(&self.normalization.apply(name))
+    }
+
+    /// Check whether a list of text was ever set, even if empty
+    pub fn contains_list(&self, name: &str) -> bool {
+        self.lists.contains_key(&self.normalization.apply(name))
+    }
+
+    /// Iterate over the flags, sorted by name
+    ///
+    /// Nothing is copied: the iterator borrows the state, so the state
+    /// cannot be modified until the iterator is dropped. When the state is
+    /// shared in a `RefCell`, the iterator must not outlive the borrow
+    /// (e.g. collect what is needed while borrowed).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::state::BaseState;
+    /// use texture::state::BasicState;
+    ///
+    /// let mut state = BasicState::new();
+    /// state.set_flag("lamp_lit".to_string(), true);
+    /// state.set_flag("door_open".to_string(), false);
+    /// state.set_value("gold".to_string(), 5);
+    /// state.set_value("arrows".to_string(), 12);
+    /// state.set_list("notes", vec!["hello".to_string()]);
+    ///
+    /// assert_eq!(state.flags_iter().collect::<Vec<_>>(),
+    ///            vec![("door_open", false), ("lamp_lit", true)]);
+    /// assert_eq!(state.values_iter().collect::<Vec<_>>(),
+    ///            vec![("arrows", 12), ("gold", 5)]);
+    /// assert_eq!(state.lists_iter().collect::<Vec<_>>(),
+    ///            vec![("notes", &["hello".to_string()][..])]);
+    /// ```
+    pub fn flags_iter<'a>(&'a self)
+                          -> impl Iterator<Item = (&'a str, bool)> + 'a {
+        self.flags.iter().map(|(name, &flag)| (name.as_str(), flag))
+    }
+
+    /// Iterate over the integer values, sorted by name
+    ///
+    /// Borrows the state like `flags_iter()`.
+    pub fn values_iter<'a>(&'a self)
+                           -> impl Iterator<Item = (&'a str, i32)> + 'a {
+        self.values.iter().map(|(name, &value)| (name.as_str(), value))
+    }
+
+    /// Iterate over the lists of text, sorted by name
+    ///
+    /// Borrows the state like `flags_iter()`.
+    pub fn lists_iter<'a>(&'a self)
+                          -> impl Iterator<Item = (&'a str, &'a [String])> + 'a {
+        self.lists.iter().map(|(name, list)| (name.as_str(), list.as_slice()))
+    }
+
+    /// Join a list of text into a single string
+    ///
+    /// Values are separated by `delimiter`, which must not be empty. To
+    /// tell delimiters apart from text, every backslash and every
+    /// occurrence of the first character of the delimiter inside a value
+    /// is preceded by a backslash. `import_list()` reverses this.
+    ///
+    /// Empty values are kept (`a,,b`), but a list with a single empty
+    /// value exports as an empty string, which imports as an empty list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delimiter` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::state::BaseState;
+    /// use texture::state::BasicState;
+    ///
+    /// let mut state = BasicState::new();
+    /// state.set_list("notes", vec![
+    ///     "milk, eggs".to_string(),
+    ///     "".to_string(),
+    ///     "C:\\games".to_string(),
+    /// ]);
+    ///
+    /// let data = state.export_list("notes", ",");
+    /// assert_eq!(data, "milk\\, eggs,,C:\\\\games");
+    ///
+    /// let mut other = BasicState::new();
+    /// other.import_list("notes", &data, ",");
+    ///
+    /// assert_eq!(other.get_list("notes"), state.get_list("notes"));
+    /// ```
+    pub fn export_list(&self, name: &str, delimiter: &str) -> String {
+        assert!(!delimiter.is_empty(), "the delimiter must not be empty");
+
+        let first = delimiter.chars().next().unwrap();
+
+        self.get_list(name).iter()
+            .map(|value| {
+                let mut escaped = String::new();
+
+                for c in value.chars() {
+                    if c == '\\' || c == first {
+                        escaped.push('\\');
+                    }
+
+                    escaped.push(c);
+                }
+
+                escaped
+            })
+            .collect::<Vec<String>>()
+            .join(delimiter)
+    }
+
+    /// Replace a list of text with the values in a string
+    ///
+    /// The string is split as written by `export_list()`. An empty string
+    /// is an empty list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delimiter` is empty.
+    pub fn import_list(&mut self, name: &str, data: &str, delimiter: &str) {
+        assert!(!delimiter.is_empty(), "the delimiter must not be empty");
+
+        let mut list = Vec::new();
+
+        if !data.is_empty() {
+            let mut current = String::new();
+            let mut rest = data;
+
+            while let Some(c) = rest.chars().next() {
+                if c == '\\' {
+                    rest = &rest[1..];
+
+                    if let Some(escaped) = rest.chars().next() {
+                        current.push(escaped);
+                        rest = &rest[escaped.len_utf8()..];
+                    }
+                } else if rest.starts_with(delimiter) {
+                    list.push(current);
+                    current = String::new();
+                    rest = &rest[delimiter.len()..];
+                } else {
+                    current.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+
+            list.push(current);
+        }
+
+        self.set_list(name, list);
+    }
+
+    /// Copy the flags, values and lists, without the observers
+    pub fn snapshot(&self) -> BasicState {
+        BasicState {
+            flags: self.flags.clone(),
+            values: self.values.clone(),
+            lists: self.lists.clone(),
+            flag_observers: HashMap::new(),
+            value_observers: HashMap::new(),
+            change_observers: Vec::new(),
+            phase: Rc::new(Cell::new(Phase::Idle)),
+            normalization: self.normalization,
+        }
+    }
+
+    /// Find what changed from `self` to `other`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::state::BaseState;
+    /// use texture::state::BasicState;
+    ///
+    /// let mut before = BasicState::new();
+    /// before.set_flag("door_open".to_string(), false);
+    /// before.set_flag("lamp_lit".to_string(), true);
+    ///
+    /// let mut after = before.snapshot();
+    /// after.set_flag("door_open".to_string(), true);
+    /// after.set_value("gold".to_string(), 5);
+    ///
+    /// let diff = before.diff(&after);
+    ///
+    /// assert_eq!(diff.added, vec!["value gold".to_string()]);
+    /// assert!(diff.removed.is_empty());
+    /// assert_eq!(diff.changed, vec!["flag door_open".to_string()]);
+    /// assert_eq!(diff.to_string(), "+ value gold\n~ flag door_open\n");
+    /// ```
+    pub fn diff(&self, other: &BasicState) -> StateDiff {
+        let mut diff = StateDiff::default();
+
+        diff.compare("flag", &self.flags, &other.flags);
+        diff.compare("value", &self.values, &other.values);
+        diff.compare("list", &self.lists, &other.lists);
+
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort();
+
+        diff
+    }
+
+    /// Apply several changes at once
+    ///
+    /// If the function returns an error, every flag and value is restored
+    /// to what it was before the transaction and the error is returned.
+    /// Observers are not notified of the rollback.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::state::BaseState;
+    /// use texture::state::BasicState;
+    ///
+    /// let mut state = BasicState::new();
+    /// state.set_value("gold".to_string(), 5);
+    ///
+    /// let result = state.transaction(|s| {
+    ///     s.set_flag("has_sword".to_string(), true);
+    ///     s.set_value("gold".to_string(), -5);
+    ///
+    ///     if s.get_value("gold".to_string()) < 0 {
+    ///         return Err("not enough gold");
+    ///     }
+    ///
+    ///     Ok(())
+    /// });
+    ///
+    /// assert_eq!(result, Err("not enough gold"));
+    /// assert_eq!(state.get_flag("has_sword".to_string()), false);
+    /// assert_eq!(state.get_value("gold".to_string()), 5);
+    /// ```
+    pub fn transaction<E, F>(&mut self, f: F) -> Result<(), E>
+        where F: FnOnce(&mut BasicState) -> Result<(), E> {
+
+        let flags = self.flags.clone();
+        let values = self.values.clone();
+        let lists = self.lists.clone();
+
+        let result = f(self);
+
+        if result.is_err() {
+            self.flags = flags;
+            self.values = values;
+            self.lists = lists;
+        }
+
+        result
+    }
+}
+
+/// Scoped access to the shared state
+///
+/// Scenarios and commands receive the state as `&Rc<RefCell<S>>`. These
+/// helpers borrow it only for the duration of the closure, so the borrow
+/// cannot accidentally outlive the statement that needs it.
+///
+/// `RefCell` panics when the state is borrowed mutably while any other
+/// borrow is alive. This happens when the helpers are nested, or mixed with
+/// a manual `borrow()`/`borrow_mut()` in the same expression (e.g. reading
+/// the state inside the closure given to `with_state()`). Read what is
+/// needed first, then modify the state in a separate statement.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::state::{BaseState, BasicState, StateAccess};
+///
+/// let state = Rc::new(RefCell::new(BasicState::new()));
+/// state.with_state(|s| s.set_value("gold".to_string(), 5));
+///
+/// // Read first...
+/// let gold = state.read_state(|s| s.get_value("gold".to_string()));
+///
+/// // ...then write
+/// state.with_state(|s| s.set_value("gold".to_string(), gold * 2));
+///
+/// assert_eq!(state.read_state(|s| s.get_value("gold".to_string())), 10);
+///
+/// // No borrow is left behind
+/// assert!(state.try_borrow_mut().is_ok());
+/// ```
+pub trait StateAccess<S> {
+    /// Call the function with mutable access to the state
+    fn with_state<R, F: FnOnce(&mut S) -> R>(&self, f: F) -> R;
+
+    /// Call the function with read-only access to the state
+    fn read_state<R, F: FnOnce(&S) -> R>(&self, f: F) -> R;
+}
+
+impl <S> StateAccess<S> for RefCell<S> {
+    fn with_state<R, F: FnOnce(&mut S) -> R>(&self, f: F) -> R {
+        f(&mut *self.borrow_mut())
+    }
+
+    fn read_state<R, F: FnOnce(&S) -> R>(&self, f: F) -> R {
+        f(&*self.borrow())
+    }
 }
 
 pub trait BaseState {
@@ -53,6 +849,128 @@ pub trait BaseState {
 
     /// Obtain an internal integer value
     fn get_value(&self, name: String) -> i32;
+
+    /// Check whether a flag was ever set, whatever its value
+    ///
+    /// States that cannot tell consider every flag set.
+    fn contains_flag(&self, _name: &str) -> bool {
+        true
+    }
+
+    /// Check whether an integer value was ever set, whatever its value
+    ///
+    /// States that cannot tell consider every value set.
+    fn contains_value(&self, _name: &str) -> bool {
+        true
+    }
+
+    /// Add to an internal integer value, returning the new value
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::state::BaseState;
+    /// use texture::state::BasicState;
+    ///
+    /// let mut state = BasicState::new();
+    ///
+    /// state.set_value("hp".to_string(), 10);
+    /// assert_eq!(state.increment("hp".to_string(), -3), 7);
+    /// assert_eq!(state.increment("gold".to_string(), 5), 5);
+    /// ```
+    fn increment(&mut self, name: String, amount: i32) -> i32 {
+        let value = self.get_value(name.clone()) + amount;
+        self.set_value(name, value);
+
+        value
+    }
+
+    /// Award points to the player for the given reason
+    ///
+    /// Each reason only awards points once; returns `false` if it was
+    /// already awarded. The score is stored in the `score` value, and
+    /// awarded reasons as `score_awarded:<reason>` flags, so it is saved
+    /// along the rest of the state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::state::BaseState;
+    /// use texture::state::BasicState;
+    ///
+    /// let mut state = BasicState::new();
+    /// state.set_max_score(100);
+    ///
+    /// assert!(state.award_points(10, "found the lamp"));
+    /// assert!(!state.award_points(10, "found the lamp"));
+    /// assert!(state.award_points(25, "opened the vault"));
+    ///
+    /// assert_eq!(state.score(), 35);
+    /// assert_eq!(state.max_score(), 100);
+    /// ```
+    fn award_points(&mut self, points: i32, reason: &str) -> bool {
+        let key = format!("score_awarded:{}", reason);
+
+        if self.get_flag(key.clone()) {
+            return false;
+        }
+
+        self.set_flag(key, true);
+        self.increment("score".to_string(), points);
+
+        true
+    }
+
+    /// Add a number of units of an item to the inventory, returning the new
+    /// amount
+    ///
+    /// Negative amounts remove units. Items are stored as `item:<name>`
+    /// values, so they are saved along the rest of the state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use texture::state::BaseState;
+    /// use texture::state::BasicState;
+    ///
+    /// let mut state = BasicState::new();
+    ///
+    /// assert!(!state.has_item("lamp"));
+    /// state.add_item("lamp", 1);
+    /// assert!(state.has_item("lamp"));
+    ///
+    /// assert_eq!(state.add_item("coin", 5), 5);
+    /// assert_eq!(state.add_item("coin", -2), 3);
+    /// assert_eq!(state.item_count("coin"), 3);
+    /// ```
+    fn add_item(&mut self, name: &str, amount: i32) -> i32 {
+        self.increment(format!("item:{}", name), amount)
+    }
+
+    /// Obtain the number of units of an item in the inventory
+    fn item_count(&self, name: &str) -> i32 {
+        self.get_value(format!("item:{}", name))
+    }
+
+    /// Check whether there is at least one unit of an item in the inventory
+    fn has_item(&self, name: &str) -> bool {
+        self.item_count(name) > 0
+    }
+
+    /// Obtain the points awarded so far
+    fn score(&self) -> i32 {
+        self.get_value("score".to_string())
+    }
+
+    /// Set the maximum score that can be obtained
+    fn set_max_score(&mut self, points: i32) {
+        self.set_value("max_score".to_string(), points);
+    }
+
+    /// Obtain the maximum score that can be obtained
+    fn max_score(&self) -> i32 {
+        self.get_value("max_score".to_string())
+    }
 }
 
 impl BaseState for BasicState {
@@ -66,12 +984,18 @@ impl BaseState for BasicState {
     /// ```
     fn new() -> BasicState {
         BasicState {
-            flags: HashMap::new(),
-            values: HashMap::new(),
+            flags: BTreeMap::new(),
+            values: BTreeMap::new(),
+            lists: BTreeMap::new(),
+            flag_observers: HashMap::new(),
+            value_observers: HashMap::new(),
+            change_observers: Vec::new(),
+            phase: Rc::new(Cell::new(Phase::Idle)),
+            normalization: Normalization::Exact,
         }
     }
 
-    /// Simply removes all keys from the internal hashmaps
+    /// Simply removes all keys from the internal maps
     /// # Examples
     ///
     /// ```
@@ -88,6 +1012,7 @@ impl BaseState for BasicState {
     fn clear(&mut self) {
         self.flags.clear();
         self.values.clear();
+        self.lists.clear();
     }
 
     /// # Examples
@@ -104,6 +1029,20 @@ impl BaseState for BasicState {
     /// assert_eq!(state.get_flag("in_start".to_string()), true);
     /// ```
     fn set_flag(&mut self, name: String, value: bool) {
+        let name = self.normalization.apply(&name);
+
+        if self.flags.get(&name) == Some(&value) {
+            return;
+        }
+
+        if let Some(observers) = self.flag_observers.get(&name) {
+            for f in observers {
+                f(value);
+            }
+        }
+
+        self.notify_change(&name);
+
         self.flags.insert(name, value);
     }
 
@@ -123,7 +1062,7 @@ impl BaseState for BasicState {
     /// assert_eq!(state.get_flag("in_start".to_string()), false);
     /// ```
     fn get_flag(&self, name: String) -> bool {
-        let val = match self.flags.get(&name) {
+        let val = match self.flags.get(&self.normalization.apply(&name)) {
             Some(s) => { s.clone() },
             None => { false }
         };
@@ -144,6 +1083,20 @@ impl BaseState for BasicState {
     /// assert_eq!(state.get_value("time".to_string()), 850);
     /// ```
     fn set_value(&mut self, name: String, value: i32) {
+        let name = self.normalization.apply(&name);
+
+        if self.values.get(&name) == Some(&value) {
+            return;
+        }
+
+        if let Some(observers) = self.value_observers.get(&name) {
+            for f in observers {
+                f(value);
+            }
+        }
+
+        self.notify_change(&name);
+
         self.values.insert(name, value);
     }
 
@@ -163,11 +1116,250 @@ impl BaseState for BasicState {
     /// assert_eq!(state.get_value("time".to_string()), 700);
     /// ```
     fn get_value(&self, name: String) -> i32 {
-        let val = match self.values.get(&name) {
+        let val = match self.values.get(&self.normalization.apply(&name)) {
             Some(s) => { s.clone() },
             None => { 0 }
         };
 
         return val;
     }
+
+    fn contains_flag(&self, name: &str) -> bool {
+        BasicState::contains_flag(self, name)
+    }
+
+    fn contains_value(&self, name: &str) -> bool {
+        BasicState::contains_value(self, name)
+    }
+}
+
+/// Thread-safe state with the same flags, values and lists as `BasicState`
+///
+/// Every operation takes `&self` and locks the maps internally, so the
+/// state can be shared between threads through an `Arc` (e.g. with a
+/// background thread saving the game). Names are normalized and unchanged
+/// values are skipped exactly as in `BasicState`, but there are no
+/// observers.
+///
+/// Note that `GameMaster` and `Loader` are built on `Rc` and are still not
+/// `Send`: this state is meant to be read and written from outside the game
+/// loop. It also implements `BaseState`, so it can be used as the game
+/// state as well.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// use texture::state::SyncBasicState;
+///
+/// let state = Arc::new(SyncBasicState::new());
+/// state.set_value("gold".to_string(), 5);
+///
+/// let readers: Vec<_> = (0..4).map(|_| {
+///     let state = state.clone();
+///     thread::spawn(move || state.get_value("gold".to_string()))
+/// }).collect();
+///
+/// for reader in readers {
+///     assert!(reader.join().unwrap() >= 5);
+/// }
+///
+/// let writer = {
+///     let state = state.clone();
+///     thread::spawn(move || {
+///         state.set_flag("saved".to_string(), true);
+///         state.increment("gold".to_string(), 10);
+///         state.set_list("quests", vec!["dragon".to_string()]);
+///     })
+/// };
+/// writer.join().unwrap();
+///
+/// assert!(state.get_flag("saved".to_string()));
+/// assert_eq!(state.get_value("gold".to_string()), 15);
+/// assert_eq!(state.get_list("quests"), vec!["dragon"]);
+/// ```
+pub struct SyncBasicState {
+    // Boolean flags
+    flags: RwLock<BTreeMap<String, bool>>,
+    // Integer values
+    values: RwLock<BTreeMap<String, i32>>,
+    // Lists of text
+    lists: RwLock<BTreeMap<String, Vec<String>>>,
+    // How names are normalized
+    normalization: Normalization,
+}
+
+impl Default for SyncBasicState {
+    fn default() -> SyncBasicState {
+        SyncBasicState::new()
+    }
+}
+
+impl SyncBasicState {
+    /// Create an empty state
+    pub fn new() -> SyncBasicState {
+        SyncBasicState {
+            flags: RwLock::new(BTreeMap::new()),
+            values: RwLock::new(BTreeMap::new()),
+            lists: RwLock::new(BTreeMap::new()),
+            normalization: Normalization::Exact,
+        }
+    }
+
+    /// Change how flag and value names are normalized
+    ///
+    /// See `BasicState::set_key_normalization()`. Takes `&mut self`, so the
+    /// mode is chosen before sharing the state.
+    pub fn set_key_normalization(&mut self, normalization: Normalization) {
+        self.normalization = normalization;
+    }
+
+    /// Remove every flag, value and list
+    pub fn clear(&self) {
+        write_lock(&self.flags).clear();
+        write_lock(&self.values).clear();
+        write_lock(&self.lists).clear();
+    }
+
+    /// Set a flag
+    pub fn set_flag(&self, name: String, value: bool) {
+        let name = self.normalization.apply(&name);
+        let mut flags = write_lock(&self.flags);
+
+        if flags.get(&name) != Some(&value) {
+            flags.insert(name, value);
+        }
+    }
+
+    /// Obtain the value of a flag, `false` if it was never set
+    pub fn get_flag(&self, name: String) -> bool {
+        let name = self.normalization.apply(&name);
+
+        read_lock(&self.flags).get(&name).cloned().unwrap_or(false)
+    }
+
+    /// Set an integer value
+    pub fn set_value(&self, name: String, value: i32) {
+        let name = self.normalization.apply(&name);
+        let mut values = write_lock(&self.values);
+
+        if values.get(&name) != Some(&value) {
+            values.insert(name, value);
+        }
+    }
+
+    /// Obtain an integer value, 0 if it was never set
+    pub fn get_value(&self, name: String) -> i32 {
+        let name = self.normalization.apply(&name);
+
+        read_lock(&self.values).get(&name).cloned().unwrap_or(0)
+    }
+
+    /// Check whether a flag was ever set, whatever its value
+    pub fn contains_flag(&self, name: &str) -> bool {
+        read_lock(&self.flags).contains_key(&self.normalization.apply(name))
+    }
+
+    /// Check whether an integer value was ever set, whatever its value
+    pub fn contains_value(&self, name: &str) -> bool {
+        read_lock(&self.values).contains_key(&self.normalization.apply(name))
+    }
+
+    /// Replace a list of text
+    pub fn set_list(&self, name: &str, list: Vec<String>) {
+        write_lock(&self.lists).insert(self.normalization.apply(name), list);
+    }
+
+    /// Obtain a list of text, empty if it was never set
+    pub fn get_list(&self, name: &str) -> Vec<String> {
+        read_lock(&self.lists).get(&self.normalization.apply(name))
+            .cloned()
+            .unwrap_or_else(Vec::new)
+    }
+
+    /// Check whether a list of text was ever set, even if empty
+    pub fn contains_list(&self, name: &str) -> bool {
+        read_lock(&self.lists).contains_key(&self.normalization.apply(name))
+    }
+
+    /// Add `amount` to an integer value, returning the new value
+    ///
+    /// The value is updated while locked, so concurrent increments are
+    /// never lost.
+    pub fn increment(&self, name: String, amount: i32) -> i32 {
+        let name = self.normalization.apply(&name);
+        let mut values = write_lock(&self.values);
+
+        let value = values.entry(name).or_insert(0);
+        *value += amount;
+
+        *value
+    }
+
+    /// Describe every flag, value and list, sorted by name
+    ///
+    /// See `BasicState::debug_dump()`.
+    pub fn debug_dump(&self) -> String {
+        let mut dump = String::new();
+
+        for (name, flag) in read_lock(&self.flags).iter() {
+            dump.push_str(&format!("flag {} = {}\n", name, flag));
+        }
+
+        for (name, value) in read_lock(&self.values).iter() {
+            dump.push_str(&format!("value {} = {}\n", name, value));
+        }
+
+        for (name, list) in read_lock(&self.lists).iter() {
+            dump.push_str(&format!("list {} = {:?}\n", name, list));
+        }
+
+        dump
+    }
+}
+
+impl BaseState for SyncBasicState {
+    fn new() -> SyncBasicState {
+        SyncBasicState::new()
+    }
+
+    fn clear(&mut self) {
+        SyncBasicState::clear(self);
+    }
+
+    fn set_flag(&mut self, name: String, value: bool) {
+        SyncBasicState::set_flag(self, name, value);
+    }
+
+    fn get_flag(&self, name: String) -> bool {
+        SyncBasicState::get_flag(self, name)
+    }
+
+    fn set_value(&mut self, name: String, value: i32) {
+        SyncBasicState::set_value(self, name, value);
+    }
+
+    fn get_value(&self, name: String) -> i32 {
+        SyncBasicState::get_value(self, name)
+    }
+
+    fn contains_flag(&self, name: &str) -> bool {
+        SyncBasicState::contains_flag(self, name)
+    }
+
+    fn contains_value(&self, name: &str) -> bool {
+        SyncBasicState::contains_value(self, name)
+    }
+}
+
+/// Lock for reading, ignoring panics in other threads holding the lock
+fn read_lock<'a, T>(lock: &'a RwLock<T>) -> RwLockReadGuard<'a, T> {
+    lock.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Lock for writing, ignoring panics in other threads holding the lock
+fn write_lock<'a, T>(lock: &'a RwLock<T>) -> RwLockWriteGuard<'a, T> {
+    lock.write().unwrap_or_else(|e| e.into_inner())
 }
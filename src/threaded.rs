@@ -0,0 +1,342 @@
+// MIT License
+//
+// Copyright (c) 2016 Rafael Medina García <rafamedgar@gmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+use master::GameMaster;
+use output::BufferOutput;
+use util::{LOAD, TICK};
+
+/// Handle to a game running on its own thread
+///
+/// Scenarios, commands and states are shared through `Rc<RefCell<_>>`, so a
+/// `GameMaster` is not `Send` and cannot be moved between threads (or held
+/// across `.await` points in a multi-threaded async runtime). Rewriting the
+/// engine around `Arc<Mutex<_>>` would make every scenario pay for locking
+/// and force `Send` bounds on all game code, so instead the game master is
+/// built and kept on a dedicated thread, and `GameThread` forwards inputs
+/// to it over channels.
+///
+/// `GameThread` is `Send`, so it may be moved into the task handling a
+/// connection. `step()` blocks until the game replies, which is usually
+/// quick; async drivers may call it from a blocking task (e.g.
+/// `spawn_blocking` in Tokio), or wrap it in a `Mutex` to share it. Each
+/// game runs on its own thread, so this does not scale to thousands of
+/// simultaneous games. For those, see `SyncGameMaster`.
+///
+/// Requires the `threaded` feature.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+/// use std::thread;
+///
+/// use texture::master::GameMaster;
+/// use texture::scenario::{Loader, Scenario};
+/// use texture::state::{BaseState, BasicState};
+/// use texture::threaded::GameThread;
+/// use texture::util::TICK;
+///
+/// struct Echo;
+///
+/// impl <S> Scenario <S> for Echo {
+///     fn load(&self, state: &Rc<RefCell<S>>,
+///             loader: &Rc<RefCell<Loader<S>>>)
+///             -> i32 {
+///         loader.borrow_mut().write("Say something.");
+///         TICK
+///     }
+///
+///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+///                  loader: &Rc<RefCell<Loader<S>>>)
+///                  -> i32 {
+///         loader.borrow_mut().write(&format!("You said '{}'.", command));
+///         TICK
+///     }
+/// }
+///
+/// let game = GameThread::spawn(|| {
+///     let state = Rc::new(RefCell::new(BasicState::new()));
+///     GameMaster::new(state, Rc::new(RefCell::new(Echo)))
+/// });
+///
+/// assert_eq!(game.opening(), "Say something.\n");
+///
+/// // The handle may be used from another thread
+/// let reply = thread::spawn(move || game.step("hi")).join().unwrap();
+///
+/// assert_eq!(reply, Some((TICK, "You said 'hi'.\n".to_string())));
+/// ```
+pub struct GameThread {
+    // Inputs for the game
+    inputs: Sender<String>,
+    // Codes and text produced by each input
+    replies: Receiver<(i32, String)>,
+    // Text shown when the game began
+    opening: String,
+    // Thread running the game
+    handle: Option<JoinHandle<()>>,
+}
+
+impl GameThread {
+    /// Build a game master on a new thread and begin the game
+    ///
+    /// The function runs on the new thread, so the game master and
+    /// everything in it never need to be `Send`. Its output is replaced to
+    /// capture the text produced by each step.
+    pub fn spawn<S, F>(build: F) -> GameThread
+        where F: FnOnce() -> GameMaster<S> + Send + 'static {
+
+        let (input_tx, input_rx) = mpsc::channel::<String>();
+        let (reply_tx, reply_rx) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            let buffer = BufferOutput::new();
+
+            let mut gm = build();
+            gm.set_output(Box::new(buffer.clone()));
+            gm.begin();
+
+            if reply_tx.send((TICK, buffer.contents())).is_err() {
+                return;
+            }
+
+            // Runs until the handle is dropped
+            for input in input_rx {
+                buffer.clear();
+                let code = gm.step(&input);
+
+                if reply_tx.send((code, buffer.contents())).is_err() {
+                    return;
+                }
+            }
+        });
+
+        let opening = match reply_rx.recv() {
+            Ok((_, text)) => { text },
+            Err(_) => { String::new() }
+        };
+
+        GameThread {
+            inputs: input_tx,
+            replies: reply_rx,
+            opening: opening,
+            handle: Some(handle),
+        }
+    }
+
+    /// Text shown when the game began
+    pub fn opening(&self) -> &str {
+        &self.opening
+    }
+
+    /// Process a line of input, returning the code and text produced
+    ///
+    /// Blocks until the game replies. Returns `None` if the game thread is
+    /// gone (e.g. a scenario panicked).
+    pub fn step(&self, input: &str) -> Option<(i32, String)> {
+        self.inputs.send(input.to_string()).ok()?;
+        self.replies.recv().ok()
+    }
+}
+
+impl Drop for GameThread {
+    fn drop(&mut self) {
+        // Closing the channel ends the loop in the game thread
+        let (closed, _) = mpsc::channel();
+        self.inputs = closed;
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Scenario that can be shared between threads (see `SyncGameMaster`)
+pub trait SyncScenario <S> : Send {
+    /// Called when the scenario is entered
+    ///
+    /// Text written to the loader is returned to the driver.
+    fn load(&self, state: &Arc<Mutex<S>>, loader: &mut SyncLoader<S>) -> i32;
+
+    /// Process the input of the player, returning an action code
+    ///
+    /// Returning `LOAD` enters the scenario set in the loader.
+    fn do_action(&self, command: &str, state: &Arc<Mutex<S>>,
+                 loader: &mut SyncLoader<S>)
+                 -> i32;
+}
+
+/// Scenario shared between threads
+pub type SharedScenario<S> = Arc<Mutex<SyncScenario<S>>>;
+
+/// Text and transitions requested by a `SyncScenario`
+pub struct SyncLoader<S> {
+    // Text written since the last input
+    text: String,
+    // Scenario to enter when `LOAD` is returned
+    next: Option<SharedScenario<S>>,
+}
+
+impl <S> SyncLoader<S> {
+    /// Write a line of text for the player
+    pub fn write(&mut self, text: &str) {
+        self.text.push_str(text);
+        self.text.push('\n');
+    }
+
+    /// Set the scenario entered when `LOAD` is returned
+    pub fn set_scenario(&mut self, scenario: SharedScenario<S>) {
+        self.next = Some(scenario);
+    }
+}
+
+/// `Send` game master built on `Arc<Mutex<_>>`
+///
+/// Unlike `GameMaster`, the state and scenarios are shared through
+/// `Arc<Mutex<_>>`, so the whole game can be moved to another thread or
+/// held across `.await` points, and no thread is needed per game (see
+/// `GameThread`). The price is locking on every access and `Send` bounds on
+/// all game code, which is why it is a separate, much smaller engine: it
+/// only runs scenarios and their transitions, without global commands,
+/// events, modes or any other feature of `GameMaster`. Games needing those
+/// should use `GameThread` instead.
+///
+/// Requires the `threaded` feature.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use std::thread;
+///
+/// use texture::threaded::{SyncGameMaster, SyncLoader, SyncScenario};
+/// use texture::util::{LOAD, NOTURN, TICK};
+///
+/// struct Gate;
+/// struct Yard;
+///
+/// impl SyncScenario <u32> for Gate {
+///     fn load(&self, _state: &Arc<Mutex<u32>>,
+///             loader: &mut SyncLoader<u32>)
+///             -> i32 {
+///         loader.write("A closed gate.");
+///         TICK
+///     }
+///
+///     fn do_action(&self, command: &str, state: &Arc<Mutex<u32>>,
+///                  loader: &mut SyncLoader<u32>)
+///                  -> i32 {
+///         *state.lock().unwrap() += 1;
+///
+///         if command == "open gate" {
+///             loader.set_scenario(Arc::new(Mutex::new(Yard)));
+///             return LOAD;
+///         }
+///
+///         loader.write("The gate does not move.");
+///         NOTURN
+///     }
+/// }
+///
+/// impl SyncScenario <u32> for Yard {
+///     fn load(&self, _state: &Arc<Mutex<u32>>,
+///             loader: &mut SyncLoader<u32>)
+///             -> i32 {
+///         loader.write("An empty yard.");
+///         TICK
+///     }
+///
+///     fn do_action(&self, _command: &str, _state: &Arc<Mutex<u32>>,
+///                  _loader: &mut SyncLoader<u32>)
+///                  -> i32 {
+///         NOTURN
+///     }
+/// }
+///
+/// let state = Arc::new(Mutex::new(0));
+/// let mut gm = SyncGameMaster::new(state.clone(), Arc::new(Mutex::new(Gate)));
+/// assert_eq!(gm.begin(), "A closed gate.\n");
+///
+/// // Messages received by a server, handled on another thread
+/// let replies = thread::spawn(move || {
+///     ["push gate", "open gate"].iter()
+///         .map(|message| gm.step(message))
+///         .collect::<Vec<_>>()
+/// }).join().unwrap();
+///
+/// assert_eq!(replies, vec![
+///     (NOTURN, "The gate does not move.\n".to_string()),
+///     (LOAD, "An empty yard.\n".to_string()),
+/// ]);
+/// assert_eq!(*state.lock().unwrap(), 2);
+/// ```
+pub struct SyncGameMaster<S> {
+    // Global state
+    state: Arc<Mutex<S>>,
+    // Scenario the player is in
+    current: SharedScenario<S>,
+}
+
+impl <S> SyncGameMaster<S> {
+    /// Create a game master starting in the given scenario
+    pub fn new(state: Arc<Mutex<S>>, start: SharedScenario<S>)
+               -> SyncGameMaster<S> {
+        SyncGameMaster {
+            state: state,
+            current: start,
+        }
+    }
+
+    /// Enter the starting scenario, returning the text produced
+    pub fn begin(&mut self) -> String {
+        let mut loader = SyncLoader { text: String::new(), next: None };
+        lock(&self.current).load(&self.state, &mut loader);
+
+        loader.text
+    }
+
+    /// Process a line of input, returning the code and text produced
+    pub fn step(&mut self, input: &str) -> (i32, String) {
+        let mut loader = SyncLoader { text: String::new(), next: None };
+        let code = lock(&self.current)
+            .do_action(input.trim(), &self.state, &mut loader);
+
+        if code == LOAD {
+            if let Some(next) = loader.next.take() {
+                self.current = next;
+                lock(&self.current).load(&self.state, &mut loader);
+            }
+        }
+
+        (code, loader.text)
+    }
+}
+
+/// Lock a mutex, ignoring panics in other threads holding the lock
+fn lock<'a, T: ?Sized>(mutex: &'a Mutex<T>) -> MutexGuard<'a, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -22,8 +22,234 @@
 
 use linenoise;
 
+/// The action took a turn
 pub const TICK: i32 = 0;
+/// The scenario set in the loader must be loaded
 pub const LOAD: i32 = 1;
+/// The action did not take a turn (e.g. checking the inventory)
+///
+/// Turn counters are not advanced and no turn-based events are fired.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::command::GameCommand;
+/// use texture::master::GameMaster;
+/// use texture::scenario::{Loader, Scenario};
+/// use texture::state::{BaseState, BasicState};
+/// use texture::util::{NOTURN, TICK};
+///
+/// struct Room;
+///
+/// impl <S> Scenario <S> for Room {
+///     fn load(&self, state: &Rc<RefCell<S>>,
+///             loader: &Rc<RefCell<Loader<S>>>)
+///             -> i32 {
+///         TICK
+///     }
+///
+///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+///                  loader: &Rc<RefCell<Loader<S>>>)
+///                  -> i32 {
+///         TICK
+///     }
+/// }
+///
+/// struct Score;
+///
+/// impl <S> GameCommand <S> for Score {
+///     fn execute(&self, state: &Rc<RefCell<S>>,
+///                loader: &Rc<RefCell<Loader<S>>>)
+///                -> i32 {
+///         loader.borrow_mut().write("You have no points");
+///
+///         NOTURN
+///     }
+/// }
+///
+/// let state = Rc::new(RefCell::new(BasicState::new()));
+/// let mut gm = GameMaster::new(state, Rc::new(RefCell::new(Room)));
+/// gm.add_command("score".to_string(), Box::new(Score));
+///
+/// gm.step("jump");
+/// gm.step("score");
+/// gm.step("score");
+///
+/// assert_eq!(gm.turns(), 1);
+/// ```
+pub const NOTURN: i32 = 2;
+/// The game must end (e.g. the player wants to quit)
+pub const QUIT: i32 = 3;
+/// The input was not understood
+///
+/// The game master reports it through its unknown command handler. Does
+/// not take a turn.
+pub const UNKNOWN: i32 = 4;
+/// The game reached one of its endings
+///
+/// Unlike `QUIT`, this is part of the story. The ending can be named with
+/// `Loader::set_ending()`; see `GameMaster::on_game_over()`. Does not take
+/// a turn.
+pub const END: i32 = 5;
+/// The player must go back to the last checkpoint (e.g. after dying)
+///
+/// See `GameMaster::respawn()`. Does not take a turn.
+pub const RESPAWN: i32 = 6;
+
+/// Maximum number of turns a single action can take (see `tick_n()`)
+pub const MAX_TICKS: u32 = 10_000;
+
+// Codes from this one plus two to this one plus `MAX_TICKS` are actions
+// taking several turns
+const TICK_BASE: i32 = 1_000_000;
+
+/// Code of an action taking the given number of turns (e.g. sleeping)
+///
+/// The game master ends each of the turns in order, so events scheduled
+/// for any of them are fired. The remaining turns are skipped when one of
+/// them loads another scenario or an ending is reached. One turn is the
+/// same as `TICK`, no turns the same as `NOTURN`, and actions cannot take
+/// more than `MAX_TICKS` turns.
+///
+/// Codes from 1000002 to 1010000 are reserved for these actions, so they
+/// never reach `GameMaster::set_invalid_code_handler()`.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use texture::master::GameMaster;
+/// use texture::scenario::{Loader, Scenario};
+/// use texture::state::{BaseState, BasicState};
+/// use texture::util::{tick_n, turn_cost, LOAD, NOTURN, TICK};
+///
+/// struct Inn;
+///
+/// impl <S> Scenario <S> for Inn {
+///     fn load(&self, state: &Rc<RefCell<S>>,
+///             loader: &Rc<RefCell<Loader<S>>>)
+///             -> i32 {
+///         TICK
+///     }
+///
+///     fn do_action(&self, command: &str, state: &Rc<RefCell<S>>,
+///                  loader: &Rc<RefCell<Loader<S>>>)
+///                  -> i32 {
+///         match command {
+///             "nap" => { tick_n(3) },
+///             "sleep" => { tick_n(8) },
+///             _ => { TICK }
+///         }
+///     }
+/// }
+///
+/// assert_eq!(tick_n(1), TICK);
+/// assert_eq!(tick_n(0), NOTURN);
+/// assert_eq!(turn_cost(tick_n(8)), 8);
+/// assert_eq!(turn_cost(tick_n(u32::max_value())), 10_000);
+///
+/// let state = Rc::new(RefCell::new(BasicState::new()));
+/// let mut gm = GameMaster::new(state.clone(), Rc::new(RefCell::new(Inn)));
+/// gm.register_scenario("dream".to_string(), Rc::new(RefCell::new(Inn)));
+///
+/// gm.loader().borrow_mut().schedule(2, Box::new(|state, _| {
+///     state.borrow_mut().set_flag("rooster".to_string(), true);
+///     TICK
+/// }));
+///
+/// gm.step("nap");
+///
+/// assert_eq!(gm.turns(), 3);
+/// assert!(state.borrow().get_flag("rooster".to_string()));
+///
+/// // Sleep is interrupted by a dream two turns later
+/// gm.loader().borrow_mut().schedule(2, Box::new(|_, loader| {
+///     loader.borrow_mut().load_by_name("dream");
+///     LOAD
+/// }));
+///
+/// gm.step("sleep");
+///
+/// assert_eq!(gm.turns(), 5);
+/// ```
+pub fn tick_n(turns: u32) -> i32 {
+    match turns {
+        0 => { NOTURN },
+        1 => { TICK },
+        n => { TICK_BASE + n.min(MAX_TICKS) as i32 }
+    }
+}
+
+/// Number of turns taken by an action with the given code
+///
+/// `TICK` takes one turn, codes built with `tick_n()` the turns given and
+/// the rest of codes none.
+pub fn turn_cost(code: i32) -> u64 {
+    if code == TICK {
+        1
+    } else if is_multi_tick(code) {
+        (code - TICK_BASE) as u64
+    } else {
+        0
+    }
+}
+
+/// Check whether a code was built with `tick_n()` for several turns
+fn is_multi_tick(code: i32) -> bool {
+    code > TICK_BASE + 1 && code <= TICK_BASE + MAX_TICKS as i32
+}
+
+/// Check whether an action code means that a turn went by
+///
+/// Only `TICK` and codes built with `tick_n()` advance the turn counters;
+/// `LOAD`, `NOTURN`, `QUIT`, `UNKNOWN`, `END` and `RESPAWN` do not.
+///
+/// # Examples
+///
+/// ```
+/// use texture::util::{advances_turn, tick_n, END, LOAD, NOTURN, QUIT, RESPAWN,
+///                     TICK, UNKNOWN};
+///
+/// assert!(advances_turn(TICK));
+/// assert!(advances_turn(tick_n(3)));
+/// assert!(!advances_turn(LOAD));
+/// assert!(!advances_turn(NOTURN));
+/// assert!(!advances_turn(QUIT));
+/// assert!(!advances_turn(UNKNOWN));
+/// assert!(!advances_turn(END));
+/// assert!(!advances_turn(RESPAWN));
+/// ```
+pub fn advances_turn(code: i32) -> bool {
+    turn_cost(code) > 0
+}
+
+/// Check whether a number is one of the action codes defined here
+///
+/// Codes built with `tick_n()` are known too; other large codes are not.
+///
+/// # Examples
+///
+/// ```
+/// use texture::util::{is_known_code, tick_n, QUIT, TICK};
+///
+/// assert!(is_known_code(TICK));
+/// assert!(is_known_code(QUIT));
+/// assert!(is_known_code(tick_n(8)));
+/// assert!(!is_known_code(42));
+/// assert!(!is_known_code(1001));
+/// assert!(!is_known_code(-1));
+/// ```
+pub fn is_known_code(code: i32) -> bool {
+    match code {
+        TICK | LOAD | NOTURN | QUIT | UNKNOWN | END | RESPAWN => { true },
+        _ => { is_multi_tick(code) }
+    }
+}
 
 /// Clear the screen
 pub fn clear_screen() {